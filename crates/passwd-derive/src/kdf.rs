@@ -0,0 +1,104 @@
+//! Argon2 with all of its inputs
+//!
+//! [Argon2::hash_password] only takes a password and a salt: it builds the `argon2_context`
+//! itself with a null `secret` and `ad` and a length of 0 for both, and argon2-rs has no other
+//! way in. The secret and associated data inputs of the reference implementation are reached
+//! through `argon2_ctx` of argon2-sys directly, which is pinned to the version whose
+//! `argon2_context` layout this module was written against.
+
+use crate::Error;
+use argon2_rs::Argon2;
+use argon2_sys::{ARGON2_DEFAULT_FLAGS, argon2_context, argon2_ctx, argon2_error_message};
+use secure_types::Zeroize;
+use std::ffi::CStr;
+
+/// The length as the `u32` the C library takes
+fn length(name: &str, bytes: &[u8]) -> Result<u32, Error> {
+   u32::try_from(bytes.len()).map_err(|_| format!("The Argon2 {} is too long", name).into())
+}
+
+/// Hash `password` with `salt`, the `secret` key and the associated data `ad`
+///
+/// With an empty secret and associated data the output is the same as
+/// [Argon2::hash_password].
+pub fn argon2_hash(
+   argon2: &Argon2,
+   password: &[u8],
+   salt: &[u8],
+   secret: &[u8],
+   ad: &[u8],
+) -> Result<Vec<u8>, Error> {
+   let mut out = vec![0u8; argon2.hash_length as usize];
+   let outlen = length("output", &out)?;
+   let pwdlen = length("password", password)?;
+   let saltlen = length("salt", salt)?;
+   let secretlen = length("secret", secret)?;
+   let adlen = length("associated data", ad)?;
+
+   // The C struct has `*mut` fields for every input, but the library only writes through
+   // `out`, and through `pwd` and `secret` only when ARGON2_FLAG_CLEAR_PASSWORD or
+   // ARGON2_FLAG_CLEAR_SECRET is set, which ARGON2_DEFAULT_FLAGS doesn't. Casting the
+   // shared references to `*mut` is sound as long as those flags stay off.
+   let mut context = argon2_context {
+      out: out.as_mut_ptr(),
+      outlen,
+      pwd: password.as_ptr() as *mut u8,
+      pwdlen,
+      salt: salt.as_ptr() as *mut u8,
+      saltlen,
+      secret: secret.as_ptr() as *mut u8,
+      secretlen,
+      ad: ad.as_ptr() as *mut u8,
+      adlen,
+      t_cost: argon2.t_cost,
+      m_cost: argon2.m_cost,
+      lanes: argon2.p_cost,
+      threads: argon2.p_cost,
+      version: argon2.version as u32,
+      allocate_cbk: None,
+      free_cbk: None,
+      flags: ARGON2_DEFAULT_FLAGS,
+   };
+
+   // SAFETY: every pointer in `context` comes from a slice that outlives the call and is
+   // paired with that slice's length, `out` is exclusively borrowed and `outlen` bytes long,
+   // and no callbacks are set so the library allocates and frees its own memory.
+   let code = unsafe { argon2_ctx(&mut context, argon2.algorithm as u32) };
+   if code != 0 {
+      out.zeroize();
+      // SAFETY: argon2_error_message returns a pointer to a static NUL terminated string
+      // for every code, unknown ones included.
+      let message = unsafe { CStr::from_ptr(argon2_error_message(code)) };
+      return Err(format!("Argon2 error: {}", message.to_string_lossy()).into());
+   }
+
+   Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_rfc9106_argon2id() {
+      let argon2 = Argon2 {
+         m_cost: 32,
+         t_cost: 3,
+         p_cost: 4,
+         hash_length: 32,
+         ..Default::default()
+      };
+
+      let hash = argon2_hash(&argon2, &[1; 32], &[2; 16], &[3; 8], &[4; 12]).unwrap();
+      assert_eq!(
+         hex::encode(hash),
+         "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+      );
+
+      let invalid = Argon2 {
+         t_cost: 0,
+         ..argon2
+      };
+      assert!(argon2_hash(&invalid, &[1; 32], &[2; 16], &[3; 8], &[4; 12]).is_err());
+   }
+}
@@ -1,10 +1,27 @@
+#![allow(
+   clippy::inconsistent_digit_grouping,
+   clippy::let_and_return,
+   clippy::useless_vec
+)]
+
+pub mod kdf;
+pub mod shamir;
+
 use argon2_rs::{Argon2, RECOMMENDED_HASH_LENGTH};
+use crypto_box::{PublicKey, SecretKey, aead::OsRng};
 use hmac::{Hmac, Mac};
 use secure_types::{SecureArray, SecureString, SecureVec, Zeroize};
 use sha3::{Digest, Sha3_512};
+use std::path::Path;
 
 pub type Error = Box<dyn std::error::Error>;
 
+/// Domain tag of the v2 seed derivation, see [Scheme::V2]
+const SEED_DOMAIN: &[u8] = b"NoPassPlz master seed";
+
+/// Purpose of the key behind [PasswordDeriver::fingerprint]
+const FINGERPRINT_PURPOSE: &str = "credential fingerprint";
+
 /// Estimated time 17 seconds
 pub fn fast() -> Argon2 {
    Argon2 {
@@ -49,6 +66,121 @@ pub fn very_slow() -> Argon2 {
    }
 }
 
+/// How [PasswordDeriver::new] turns the credentials into the seed
+///
+/// Every scheme gives different seeds for the same credentials, so the one used first must
+/// always be used again. The version is recorded in the data file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+   /// The salt is SHA3-512 of the username, no context is given to Argon2
+   V1,
+   /// The salt and Argon2's associated data are bound to the app and the scheme version
+   V2,
+}
+
+impl Scheme {
+   /// The scheme to pick for new credentials, never picked without asking since existing
+   /// credentials would derive other passwords
+   pub const LATEST: Scheme = Scheme::V2;
+
+   pub fn version(self) -> u32 {
+      match self {
+         Scheme::V1 => 1,
+         Scheme::V2 => 2,
+      }
+   }
+
+   pub fn from_version(version: u32) -> Result<Self, Error> {
+      match version {
+         1 => Ok(Scheme::V1),
+         2 => Ok(Scheme::V2),
+         _ => Err(format!("Unknown derivation scheme version {}", version).into()),
+      }
+   }
+
+   pub fn to_str(self) -> &'static str {
+      match self {
+         Scheme::V1 => "v1 (before domain separation)",
+         Scheme::V2 => "v2",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![Self::V1, Self::V2]
+   }
+
+   /// How the salt is made, for the recovery kit
+   pub fn salt_description(self) -> &'static str {
+      match self {
+         Scheme::V1 => "SHA3-512 of the username",
+         Scheme::V2 => "SHA3-512 of the domain tag, version and username",
+      }
+   }
+
+   /// Argon2 salt and associated data
+   fn salt_and_ad(self, username: &str) -> (Vec<u8>, Vec<u8>) {
+      let mut hasher = Sha3_512::new();
+      let mut ad = Vec::new();
+      if self == Scheme::V2 {
+         ad.extend_from_slice(SEED_DOMAIN);
+         ad.extend_from_slice(&self.version().to_be_bytes());
+         hasher.update(&ad);
+      }
+      hasher.update(username.as_bytes());
+
+      let mut result = hasher.finalize();
+      let salt = result.to_vec();
+      result.zeroize();
+      (salt, ad)
+   }
+}
+
+/// A random seed, for secrets that don't come from credentials like a team seed
+pub fn random_seed() -> Result<SecureArray<u8, 64>, Error> {
+   let mut seed = [0u8; 64];
+   getrandom::fill(&mut seed).map_err(|e| e.to_string())?;
+   Ok(SecureArray::from_slice_mut(&mut seed)?)
+}
+
+/// The X25519 public key of a key from [PasswordDeriver::derive_key]
+///
+/// See [PasswordDeriver::seal_seed]
+pub fn public_key(secret: &SecureArray<u8, 32>) -> Result<[u8; 32], Error> {
+   secret.unlock(|secret| Ok(SecretKey::from_slice(secret)?.public_key().to_bytes()))
+}
+
+/// A file that must be present to derive the seed, on top of the credentials
+///
+/// Only the SHA3-512 hash of the file is kept, it's passed to Argon2 as its secret input.
+pub struct Keyfile(SecureArray<u8, 64>);
+
+impl Keyfile {
+   pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+      if bytes.is_empty() {
+         return Err("The keyfile is empty".into());
+      }
+
+      let mut result = Sha3_512::digest(bytes);
+      let mut hash = [0u8; 64];
+      hash.copy_from_slice(&result);
+      result.zeroize();
+      Ok(Self(SecureArray::from_slice_mut(&mut hash)?))
+   }
+
+   pub fn read(path: &Path) -> Result<Self, Error> {
+      let mut bytes = std::fs::read(path).map_err(|e| {
+         format!(
+            "Failed to read the keyfile {}: {}",
+            path.display(),
+            e
+         )
+      })?;
+      let keyfile = Self::from_bytes(&bytes);
+      bytes.zeroize();
+      keyfile
+   }
+}
+
 #[derive(Clone)]
 pub struct PasswordDeriver {
    seed: SecureArray<u8, 64>,
@@ -61,24 +193,107 @@ impl PasswordDeriver {
       password: SecureString,
       confirm_password: SecureString,
       argon2: Argon2,
+      keyfile: Option<&Keyfile>,
+      scheme: Scheme,
    ) -> Result<Self, Error> {
       validate_credentials(&username, &password, &confirm_password)?;
 
-      let mut hasher = Sha3_512::new();
+      let (salt, ad) = username.unlock_str(|username| scheme.salt_and_ad(username));
 
-      username.unlock_str(|username| {
-         hasher.update(username.as_bytes());
-      });
+      let hash = match (scheme, keyfile) {
+         // v1 without a keyfile predates the full Argon2 input
+         (Scheme::V1, None) => password.unlock_str(|passwd| argon2.hash_password(passwd, salt))?,
+         (_, keyfile) => password.unlock_str(|passwd| match keyfile {
+            Some(Keyfile(secret)) => secret
+               .unlock(|secret| kdf::argon2_hash(&argon2, passwd.as_bytes(), &salt, secret, &ad)),
+            None => kdf::argon2_hash(&argon2, passwd.as_bytes(), &salt, &[], &ad),
+         })?,
+      };
 
-      let mut result = hasher.finalize();
-      let username_hash = result.to_vec();
-      result.zeroize();
+      let sec_vec = SecureVec::from_vec(hash)?;
+      let seed = SecureArray::try_from(sec_vec)?;
 
-      let hash = password.unlock_str(|passwd| argon2.hash_password(passwd, username_hash))?;
+      Ok(Self { seed, argon2 })
+   }
 
-      let sec_vec = SecureVec::from_vec(hash)?;
+   /// A deriver for a seed that did not come from credentials, like a team seed
+   pub fn from_seed(seed: SecureArray<u8, 64>, argon2: Argon2) -> Self {
+      Self { seed, argon2 }
+   }
+
+   /// A 32 byte key for `purpose`, independent from every derived password
+   ///
+   /// Passwords are derived from 4 byte messages, so a longer purpose never collides with one.
+   pub fn derive_key(&self, purpose: &str) -> Result<SecureArray<u8, 32>, Error> {
+      if purpose.len() == 4 {
+         return Err("The key purpose must not be 4 bytes long".into());
+      }
+
+      self.seed.unlock(|seed| {
+         let mut mac = Hmac::<Sha3_512>::new_from_slice(seed).expect("HMAC");
+         mac.update(purpose.as_bytes());
+         let mut result = mac.finalize().into_bytes();
+
+         let mut key = [0u8; 32];
+         key.copy_from_slice(&result[..32]);
+         result.zeroize();
+
+         Ok(SecureArray::from_slice_mut(&mut key)?)
+      })
+   }
+
+   /// A short code that tells whether the credentials were typed the same as before
+   ///
+   /// It's derived like any other key so it reveals nothing useful about the seed.
+   pub fn fingerprint(&self) -> Result<String, Error> {
+      let key = self.derive_key(FINGERPRINT_PURPOSE)?;
+      let code = key.unlock(|key| hex::encode_upper(&key[..4]));
+      Ok(format!("{}-{}", &code[..4], &code[4..]))
+   }
+
+   /// Encrypt the seed to an X25519 public key with a libsodium compatible sealed box
+   pub fn seal_seed(&self, recipient: &[u8; 32]) -> Result<Vec<u8>, Error> {
+      let recipient = PublicKey::from(*recipient);
+      let sealed = self.seed.unlock(|seed| recipient.seal(&mut OsRng, seed));
+      sealed.map_err(|_| "Failed to seal the seed".into())
+   }
+
+   /// A deriver for a seed sealed with [Self::seal_seed] to the public key of `secret`
+   pub fn open_seed(
+      secret: &SecureArray<u8, 32>,
+      sealed: &[u8],
+      argon2: Argon2,
+   ) -> Result<Self, Error> {
+      let seed = secret.unlock(|secret| {
+         let secret = SecretKey::from_slice(secret)?;
+         secret
+            .unseal(sealed)
+            .map_err(|_| Error::from("The seed was not sealed to this key or was modified"))
+      })?;
+
+      if seed.len() != 64 {
+         return Err("The sealed seed has the wrong length".into());
+      }
+
+      let sec_vec = SecureVec::from_vec(seed)?;
       let seed = SecureArray::try_from(sec_vec)?;
+      Ok(Self { seed, argon2 })
+   }
+
+   /// Split the seed into `count` recovery shares, any `threshold` of them rebuild it
+   pub fn split_seed(&self, threshold: u8, count: u8) -> Result<Vec<shamir::Share>, Error> {
+      self.seed.unlock(|seed| shamir::split(seed, threshold, count))
+   }
+
+   /// A deriver for the seed rebuilt from recovery shares, see [Self::split_seed]
+   pub fn from_shares(shares: &[shamir::Share], argon2: Argon2) -> Result<Self, Error> {
+      let seed = shamir::combine(shares)?;
+      if seed.len() != 64 {
+         return Err("The shares are not from a master seed".into());
+      }
 
+      let sec_vec = SecureVec::from_vec(seed)?;
+      let seed = SecureArray::try_from(sec_vec)?;
       Ok(Self { seed, argon2 })
    }
 
@@ -152,6 +367,8 @@ mod tests {
          SecureString::from("password"),
          SecureString::from("password"),
          argon2,
+         None,
+         Scheme::V1,
       )
       .unwrap();
 
@@ -165,4 +382,119 @@ mod tests {
          println!("Passwd at index {} -> {}", index, passwd);
       }
    }
+
+   #[test]
+   fn test_keyfile() {
+      let argon2 = Argon2::new(16_000, 1, 1);
+      let keyfile = Keyfile::from_bytes(b"keyfile contents").unwrap();
+      let derive = |keyfile: Option<&Keyfile>| {
+         PasswordDeriver::new(
+            SecureString::from("username"),
+            SecureString::from("password"),
+            SecureString::from("password"),
+            argon2.clone(),
+            keyfile,
+            Scheme::V1,
+         )
+         .unwrap()
+         .derive_at(0)
+         .unlock_str(|s| String::from(s))
+      };
+
+      let expected = "36f7c426d1b9a7c6f0c67e87693ef69f970b8dbb195fd68b25a6ff13fdc5d450beb5e623072a8bdf72910270ff66722b7344e4b41532235d7ec21405703b714d";
+      assert_eq!(derive(Some(&keyfile)), expected);
+      assert_ne!(derive(None), expected);
+      assert!(Keyfile::from_bytes(&[]).is_err());
+
+      // Without a secret the full Argon2 input gives the same hash as before
+      let salt = Sha3_512::digest(b"username").to_vec();
+      assert_eq!(
+         kdf::argon2_hash(&argon2, b"password", &salt, &[], &[]).unwrap(),
+         argon2.hash_password("password", salt.clone()).unwrap()
+      );
+   }
+
+   #[test]
+   fn test_scheme_v2() {
+      let argon2 = Argon2::new(16_000, 1, 1);
+      let keyfile = Keyfile::from_bytes(b"keyfile contents").unwrap();
+      let derive = |keyfile: Option<&Keyfile>, scheme: Scheme| {
+         PasswordDeriver::new(
+            SecureString::from("username"),
+            SecureString::from("password"),
+            SecureString::from("password"),
+            argon2.clone(),
+            keyfile,
+            scheme,
+         )
+         .unwrap()
+         .derive_at(0)
+         .unlock_str(|s| String::from(s))
+      };
+
+      let expected = "68485ee1a7e8ffffe1ce62c0d71147bb20101f1684657c0924c62acfb647d35c69a1446ff88479790e77898b46aed0dbd93d58fe4575df3addbe9d512d704ab4";
+      let expected_keyfile = "ebda3aeaa813c7a62204bd139507234a674a51746a538ecf122d3d8119f4c5c3645eaa96d37157dc9e39f4e3ca412aadbfee06be7f3c75d69aeee9f6e9aacdaa";
+      assert_eq!(derive(None, Scheme::V2), expected);
+      assert_eq!(
+         derive(Some(&keyfile), Scheme::V2),
+         expected_keyfile
+      );
+      assert_ne!(derive(None, Scheme::V1), expected);
+
+      for scheme in Scheme::to_vec() {
+         assert_eq!(
+            Scheme::from_version(scheme.version()).unwrap(),
+            scheme
+         );
+      }
+      assert!(Scheme::from_version(0).is_err());
+   }
+
+   #[test]
+   fn test_seed_shares() {
+      let deriver = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      let shares = deriver.split_seed(2, 3).unwrap();
+
+      let recovered =
+         PasswordDeriver::from_shares(&shares[1..], Argon2::new(16_000, 1, 1)).unwrap();
+      assert_eq!(
+         recovered.derive_at(3).unlock_str(|s| String::from(s)),
+         deriver.derive_at(3).unlock_str(|s| String::from(s))
+      );
+      assert!(PasswordDeriver::from_shares(&shares[..1], Argon2::new(16_000, 1, 1)).is_err());
+      assert_eq!(
+         recovered.fingerprint().unwrap(),
+         deriver.fingerprint().unwrap()
+      );
+      assert_eq!(deriver.fingerprint().unwrap().len(), 9);
+   }
+
+   #[test]
+   fn test_sealed_seed() {
+      let team = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      let member = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+
+      let secret = member.derive_key("team member key").unwrap();
+      let public = public_key(&secret).unwrap();
+      assert!(member.derive_key("abcd").is_err());
+
+      let sealed = team.seal_seed(&public).unwrap();
+      let opened = PasswordDeriver::open_seed(&secret, &sealed, Argon2::new(16_000, 1, 1)).unwrap();
+
+      let expected = team.derive_at(7).unlock_str(|s| String::from(s));
+      assert_eq!(
+         opened.derive_at(7).unlock_str(|s| String::from(s)),
+         expected
+      );
+
+      let other = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      let other_secret = other.derive_key("team member key").unwrap();
+      assert!(
+         PasswordDeriver::open_seed(&other_secret, &sealed, Argon2::new(16_000, 1, 1)).is_err()
+      );
+
+      let mut tampered = sealed.clone();
+      tampered[40] ^= 1;
+      assert!(PasswordDeriver::open_seed(&secret, &tampered, Argon2::new(16_000, 1, 1)).is_err());
+   }
 }
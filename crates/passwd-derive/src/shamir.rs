@@ -0,0 +1,355 @@
+//! Shamir's secret sharing over GF(256)
+//!
+//! Every byte of the secret is the constant term of its own random polynomial of degree
+//! `threshold - 1`, a share is the value of all polynomials at one non zero x. Any `threshold`
+//! shares rebuild the secret with Lagrange interpolation at zero, fewer reveal nothing about it.
+//!
+//! Every share of a split carries the same random set id, so shares of different splits are
+//! refused, and a digest of the secret that is checked after interpolation, so a wrong share
+//! never silently rebuilds a wrong secret. Shares are written down as words, one per byte,
+//! ending with a checksum so a typo is caught before that.
+
+use crate::Error;
+use secure_types::Zeroize;
+use sha3::{Digest, Sha3_256};
+
+const SHARE_VERSION: u8 = 2;
+const CHECKSUM_LEN: usize = 2;
+const SET_ID_LEN: usize = 4;
+const DIGEST_LEN: usize = 4;
+
+/// Version, threshold, x, set id and digest
+const HEADER_LEN: usize = 3 + SET_ID_LEN + DIGEST_LEN;
+
+const DIGEST_DOMAIN: &[u8] = b"NoPassPlz share digest";
+
+/// One word per byte, sorted so a word's position is its value
+const WORDS: [&str; 256] = [
+   "acid", "acorn", "actor", "adult", "agent", "alarm", "album", "alert", "alley", "amber",
+   "angel", "ankle", "apple", "april", "apron", "arena", "armor", "arrow", "aspen", "atlas",
+   "attic", "audio", "aunt", "autumn", "badge", "badger", "bagel", "baker", "bamboo", "banana",
+   "banjo", "barn", "basil", "basket", "beach", "beacon", "beard", "beaver", "bell", "bench",
+   "berry", "bicycle", "bison", "blade", "blanket", "blossom", "board", "bonnet", "boots",
+   "bottle", "bounce", "bowl", "brave", "bread", "brick", "bridge", "broom", "brush", "bubble",
+   "bucket", "buffalo", "bugle", "bunny", "butter", "button", "cabin", "cactus", "camel", "camera",
+   "candle", "canoe", "canyon", "carpet", "carrot", "castle", "cedar", "cello", "chalk", "cherry",
+   "chess", "circus", "clock", "cloud", "clover", "coast", "cobra", "cocoa", "comet", "copper",
+   "coral", "cotton", "cougar", "cradle", "crayon", "cricket", "crystal", "daisy", "dancer",
+   "delta", "desert", "diamond", "dinner", "dolphin", "donkey", "dragon", "drum", "eagle", "earth",
+   "easel", "echo", "elbow", "ember", "engine", "falcon", "feather", "fence", "ferry", "fiddle",
+   "finch", "flame", "flute", "forest", "fossil", "fountain", "fox", "frost", "garden", "garlic",
+   "gecko", "ginger", "giraffe", "glove", "goat", "gravel", "guitar", "hammer", "harbor", "harp",
+   "hazel", "helmet", "heron", "hockey", "honey", "horizon", "igloo", "island", "ivory", "jacket",
+   "jaguar", "jelly", "jigsaw", "kayak", "kettle", "kiwi", "koala", "ladder", "lagoon", "lantern",
+   "lemon", "leopard", "lettuce", "lily", "lizard", "lobster", "magnet", "mango", "maple",
+   "marble", "meadow", "melon", "mirror", "monkey", "moose", "mosaic", "muffin", "museum",
+   "needle", "nickel", "noodle", "oasis", "ocean", "olive", "onion", "orange", "orbit", "otter",
+   "owl", "paddle", "panda", "papaya", "parrot", "peach", "pebble", "pencil", "pepper", "piano",
+   "pillow", "pilot", "pine", "pirate", "planet", "plum", "pocket", "pony", "potato", "puzzle",
+   "quartz", "quilt", "rabbit", "radar", "radish", "raven", "ribbon", "river", "robin", "rocket",
+   "saddle", "salmon", "sandal", "saturn", "scarf", "shadow", "sheep", "shovel", "silver",
+   "sketch", "sled", "snail", "spider", "spoon", "stable", "tiger", "timber", "toast", "tomato",
+   "tractor", "trumpet", "tulip", "turtle", "unicorn", "valley", "velvet", "violin", "volcano",
+   "wagon", "walnut", "whale", "willow", "window", "winter", "wizard", "wolf", "yacht", "yogurt",
+   "zebra", "zipper",
+];
+
+/// Multiply in GF(256) with the AES polynomial, without branching on secret data
+fn mul(mut a: u8, mut b: u8) -> u8 {
+   let mut product = 0u8;
+   for _ in 0..8 {
+      product ^= 0u8.wrapping_sub(b & 1) & a;
+      let carry = 0u8.wrapping_sub(a >> 7);
+      a = (a << 1) ^ (carry & 0x1B);
+      b >>= 1;
+   }
+   product
+}
+
+/// a^254, the inverse of a non zero element
+fn inverse(a: u8) -> u8 {
+   let mut result = 1u8;
+   let mut power = a;
+   let mut exponent = 254u8;
+   while exponent > 0 {
+      if exponent & 1 == 1 {
+         result = mul(result, power);
+      }
+      power = mul(power, power);
+      exponent >>= 1;
+   }
+   result
+}
+
+/// One share of a secret
+pub struct Share {
+   /// Number of shares needed to rebuild the secret
+   pub threshold: u8,
+   /// Random, the same for all shares of one split
+   pub set_id: [u8; SET_ID_LEN],
+   /// Digest of the secret, see [digest]
+   pub digest: [u8; DIGEST_LEN],
+   pub x: u8,
+   pub y: Vec<u8>,
+}
+
+impl Share {
+   /// Version, threshold, x, set id, digest, the share bytes and a checksum
+   pub fn to_bytes(&self) -> Vec<u8> {
+      let mut bytes = Vec::with_capacity(self.y.len() + HEADER_LEN + CHECKSUM_LEN);
+      bytes.extend_from_slice(&[SHARE_VERSION, self.threshold, self.x]);
+      bytes.extend_from_slice(&self.set_id);
+      bytes.extend_from_slice(&self.digest);
+      bytes.extend_from_slice(&self.y);
+      let checksum = checksum(&bytes);
+      bytes.extend_from_slice(&checksum);
+      bytes
+   }
+
+   pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+      if bytes.len() < HEADER_LEN + 1 + CHECKSUM_LEN {
+         return Err("The share is too short".into());
+      }
+
+      let (body, expected) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+      if checksum(body) != expected {
+         return Err("The share checksum does not match, check for a typo".into());
+      }
+
+      if body[0] != SHARE_VERSION {
+         return Err(format!("Unsupported share version {}", body[0]).into());
+      }
+
+      let (threshold, x) = (body[1], body[2]);
+      if threshold == 0 || x == 0 {
+         return Err("The share is invalid".into());
+      }
+
+      let (set_id, rest) = body[3..].split_at(SET_ID_LEN);
+      let (digest, y) = rest.split_at(DIGEST_LEN);
+      Ok(Self {
+         threshold,
+         set_id: set_id.try_into()?,
+         digest: digest.try_into()?,
+         x,
+         y: y.to_vec(),
+      })
+   }
+
+   /// The share as space separated words
+   pub fn to_words(&self) -> String {
+      let mut bytes = self.to_bytes();
+      let words = bytes.iter().map(|byte| WORDS[*byte as usize]).collect::<Vec<_>>().join(" ");
+      bytes.zeroize();
+      words
+   }
+
+   /// Parse a share written with [Self::to_words], case and whitespace don't matter
+   pub fn from_words(words: &str) -> Result<Self, Error> {
+      let mut bytes = Vec::new();
+      for word in words.split_whitespace() {
+         let word = word.to_lowercase();
+         match WORDS.binary_search(&word.as_str()) {
+            Ok(byte) => bytes.push(byte as u8),
+            Err(_) => {
+               bytes.zeroize();
+               return Err(format!("\"{}\" is not a share word", word).into());
+            }
+         }
+      }
+
+      let share = Self::from_bytes(&bytes);
+      bytes.zeroize();
+      share
+   }
+}
+
+impl Drop for Share {
+   fn drop(&mut self) {
+      self.y.zeroize();
+   }
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+   let hash = Sha3_256::digest(bytes);
+   [hash[0], hash[1]]
+}
+
+/// Truncated hash of the secret and the set id, checked after interpolation
+///
+/// The secret is random so a few bytes of its hash give nothing away.
+fn digest(set_id: &[u8; SET_ID_LEN], secret: &[u8]) -> [u8; DIGEST_LEN] {
+   let hash = Sha3_256::new()
+      .chain_update(DIGEST_DOMAIN)
+      .chain_update(set_id)
+      .chain_update(secret)
+      .finalize();
+   [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Split `secret` into `count` shares, any `threshold` of them rebuild it
+pub fn split(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<Share>, Error> {
+   if threshold < 2 {
+      return Err("At least 2 shares must be needed".into());
+   }
+
+   if count < threshold {
+      return Err("There can't be fewer shares than are needed".into());
+   }
+
+   let mut set_id = [0u8; SET_ID_LEN];
+   getrandom::fill(&mut set_id).map_err(|e| e.to_string())?;
+   let digest = digest(&set_id, secret);
+
+   let mut shares = (1..=count)
+      .map(|x| Share {
+         threshold,
+         set_id,
+         digest,
+         x,
+         y: vec![0u8; secret.len()],
+      })
+      .collect::<Vec<_>>();
+
+   let mut coefficients = vec![0u8; threshold as usize - 1];
+   for (i, byte) in secret.iter().enumerate() {
+      getrandom::fill(&mut coefficients).map_err(|e| e.to_string())?;
+
+      for share in shares.iter_mut() {
+         // Horner's method, the secret byte is the constant term
+         let mut y = 0u8;
+         for coefficient in coefficients.iter().rev() {
+            y = mul(y, share.x) ^ coefficient;
+         }
+         share.y[i] = mul(y, share.x) ^ byte;
+      }
+   }
+   coefficients.zeroize();
+
+   Ok(shares)
+}
+
+/// Rebuild the secret from at least `threshold` shares
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>, Error> {
+   let first = shares.first().ok_or("No shares given")?;
+   let threshold = first.threshold as usize;
+   let len = first.y.len();
+
+   for (i, share) in shares.iter().enumerate() {
+      if share.set_id != first.set_id {
+         return Err("The shares are not from the same split".into());
+      }
+
+      if share.threshold != first.threshold || share.digest != first.digest || share.y.len() != len
+      {
+         return Err("The shares are not from the same secret".into());
+      }
+
+      if shares[..i].iter().any(|other| other.x == share.x) {
+         return Err(format!("Share {} was given twice", share.x).into());
+      }
+   }
+
+   if shares.len() < threshold {
+      return Err(
+         format!(
+            "{} shares are needed, got {}",
+            threshold,
+            shares.len()
+         )
+         .into(),
+      );
+   }
+
+   let shares = &shares[..threshold];
+   let mut secret = vec![0u8; len];
+   for (i, share) in shares.iter().enumerate() {
+      // Lagrange basis polynomial of this share at x = 0
+      let mut basis = 1u8;
+      for (j, other) in shares.iter().enumerate() {
+         if i != j {
+            basis = mul(basis, mul(other.x, inverse(other.x ^ share.x)));
+         }
+      }
+
+      for (byte, y) in secret.iter_mut().zip(&share.y) {
+         *byte ^= mul(basis, *y);
+      }
+   }
+
+   if digest(&first.set_id, &secret) != first.digest {
+      secret.zeroize();
+      return Err("The shares don't rebuild the secret they were split from".into());
+   }
+
+   Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_field() {
+      assert_eq!(mul(0x57, 0x83), 0xC1);
+      for a in 1..=255u8 {
+         assert_eq!(mul(a, inverse(a)), 1);
+      }
+      assert!(WORDS.windows(2).all(|pair| pair[0] < pair[1]));
+   }
+
+   #[test]
+   fn test_split_and_combine() {
+      let secret = (0..64).collect::<Vec<u8>>();
+      let shares = split(&secret, 3, 5).unwrap();
+
+      assert_eq!(combine(&shares[..3]).unwrap(), secret);
+      assert_eq!(combine(&shares[2..]).unwrap(), secret);
+      assert!(combine(&shares[..2]).is_err());
+      assert_ne!(shares[0].y, secret);
+
+      let words = shares[4].to_words();
+      assert_eq!(
+         words.split(' ').count(),
+         64 + HEADER_LEN + CHECKSUM_LEN
+      );
+      let parsed = Share::from_words(&words.to_uppercase()).unwrap();
+      assert_eq!((parsed.x, parsed.threshold), (5, 3));
+      assert_eq!(parsed.y, shares[4].y);
+
+      // A swapped word is caught by the checksum
+      let mut typo = words.split(' ').collect::<Vec<_>>();
+      typo.swap(10, 11);
+      assert!(Share::from_words(&typo.join(" ")).is_err());
+
+      let twice = [
+         parsed,
+         Share::from_words(&words).unwrap(),
+         split(&secret, 3, 5).unwrap().remove(0),
+      ];
+      assert!(combine(&twice).is_err());
+   }
+
+   #[test]
+   fn test_set_id_and_digest() {
+      let secret = (0..64).collect::<Vec<u8>>();
+      let shares = split(&secret, 2, 3).unwrap();
+      let other = split(&secret, 2, 3).unwrap();
+      assert!(shares.iter().all(|share| share.set_id == shares[0].set_id));
+      assert_ne!(shares[0].set_id, other[0].set_id);
+
+      // Shares of another split of the same secret don't mix
+      let mixed = [
+         Share::from_bytes(&shares[0].to_bytes()).unwrap(),
+         Share::from_bytes(&other[1].to_bytes()).unwrap(),
+      ];
+      assert!(combine(&mixed).is_err());
+
+      // A share whose bytes changed but whose checksum was made to match
+      let mut forged = Share::from_bytes(&shares[1].to_bytes()).unwrap();
+      forged.y[0] ^= 1;
+      let forged = Share::from_bytes(&forged.to_bytes()).unwrap();
+      let err = combine(&[Share::from_bytes(&shares[0].to_bytes()).unwrap(), forged]);
+      assert!(err.is_err());
+   }
+}
@@ -0,0 +1,131 @@
+//! Command line operations, run instead of the GUI when arguments are given
+
+use crate::gui::{
+   app::AppData,
+   merge::{MergePlan, Side, load_data_file},
+};
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage:
+  NoPassPlz [--keyfile <file>]
+  NoPassPlz merge <data file> <other data file> [--out <file>] [--prefer newer|ours|theirs]
+
+Without a command the app starts, --keyfile preselects the keyfile on the login screen.
+
+merge merges the other data file into the first one (or into --out).
+Conflicting indices keep the copy modified last unless --prefer says otherwise.";
+
+/// Which copy wins a conflict without asking
+#[derive(Clone, Copy, PartialEq)]
+enum Prefer {
+   Newer,
+   Ours,
+   Theirs,
+}
+
+/// Run the command in `args`, returns the exit code or `None` to start the GUI
+pub fn run(args: &[String]) -> Option<i32> {
+   let (command, rest) = args.split_first()?;
+
+   let res = match command.as_str() {
+      "--keyfile" if rest.len() == 1 => return None,
+      "merge" => merge(rest),
+      "help" | "--help" | "-h" => {
+         println!("{}", USAGE);
+         Ok(())
+      }
+      _ => Err(format!("Unknown command {}\n\n{}", command, USAGE).into()),
+   };
+
+   match res {
+      Ok(()) => Some(0),
+      Err(e) => {
+         eprintln!("{}", e);
+         Some(1)
+      }
+   }
+}
+
+/// The keyfile given with `--keyfile` when starting the app
+pub fn keyfile_arg(args: &[String]) -> Option<String> {
+   match args {
+      [flag, path] if flag == "--keyfile" => Some(path.clone()),
+      _ => None,
+   }
+}
+
+fn merge(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+   let mut files = Vec::new();
+   let mut out = None;
+   let mut prefer = Prefer::Newer;
+
+   let mut args = args.iter();
+   while let Some(arg) = args.next() {
+      match arg.as_str() {
+         "--out" => out = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+         "--prefer" => {
+            prefer = match args.next().map(|s| s.as_str()) {
+               Some("newer") => Prefer::Newer,
+               Some("ours") => Prefer::Ours,
+               Some("theirs") => Prefer::Theirs,
+               _ => return Err(USAGE.into()),
+            }
+         }
+         _ => files.push(PathBuf::from(arg)),
+      }
+   }
+
+   let [ours_path, theirs_path] = files.as_slice() else {
+      return Err(USAGE.into());
+   };
+
+   let ours = load_data_file(ours_path)?;
+   let theirs = load_data_file(theirs_path)?;
+   let mut plan = MergePlan::new(&ours, &theirs)?;
+
+   println!(
+      "{} new, {} updated, {} conflicts",
+      plan.added,
+      plan.updated,
+      plan.conflicts.len()
+   );
+
+   for conflict in plan.conflicts.iter_mut() {
+      match prefer {
+         Prefer::Newer => {}
+         Prefer::Ours => conflict.pick = Side::Ours,
+         Prefer::Theirs => conflict.pick = Side::Theirs,
+      }
+
+      let kept = match conflict.pick {
+         Side::Ours => &conflict.ours,
+         Side::Theirs => &conflict.theirs,
+      };
+      println!(
+         "Index {}: kept {}",
+         conflict.index,
+         kept.summary().replace('\n', ", ")
+      );
+   }
+
+   let scheme_version = plan.scheme_version;
+   let (index_map, tombstones) = plan.resolve();
+   let mut merged = AppData {
+      index_map,
+      tombstones,
+      scheme_version,
+      ..Default::default()
+   };
+   // Writing back to the first file still refuses to overwrite changes made meanwhile
+   match out {
+      Some(out) => merged.data_file = out,
+      None => {
+         merged.data_file = ours.data_file;
+         merged.file_hash = ours.file_hash;
+      }
+   }
+   merged.save_index_map_to_file()?;
+
+   println!("Saved to {}", merged.data_file.display());
+   Ok(())
+}
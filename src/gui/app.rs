@@ -1,35 +1,110 @@
-use super::SHARED_GUI;
+use super::{
+   GUI, SHARED_GUI,
+   settings::{Settings, set_style},
+   watch,
+};
 use eframe::{
    CreationContext,
    egui::{self, Frame},
 };
-use passwd_derive::PasswordDeriver;
+use passwd_derive::{PasswordDeriver, Scheme};
 use secure_types::SecureString;
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 use std::{
    collections::HashMap,
-   sync::{Arc, RwLock},
+   path::{Path, PathBuf},
+   sync::{Arc, Mutex, MutexGuard, RwLock},
 };
-use zeus_theme::{Theme, ThemeKind};
 
 #[derive(Clone, Default)]
-pub struct AppCtx(Arc<RwLock<AppData>>);
+pub struct AppCtx {
+   data: Arc<RwLock<AppData>>,
+   /// Held while the data file is written or reloaded, so saves land in order and a reload
+   /// never runs between a write and the hash update that follows it
+   file_lock: Arc<Mutex<()>>,
+}
 
 impl AppCtx {
    pub fn read<R>(&self, reader: impl FnOnce(&AppData) -> R) -> R {
-      reader(&self.0.read().unwrap())
+      reader(&self.data.read().unwrap())
    }
 
    pub fn write<R>(&self, writer: impl FnOnce(&mut AppData) -> R) -> R {
-      writer(&mut self.0.write().unwrap())
+      writer(&mut self.data.write().unwrap())
+   }
+
+   /// Keep other threads from writing or reloading the data file until the guard is dropped
+   pub fn lock_file(&self) -> MutexGuard<'_, ()> {
+      self.file_lock.lock().unwrap_or_else(|e| e.into_inner())
    }
 
    pub fn load_index_map_from_file(&self) -> Result<(), Box<dyn std::error::Error>> {
+      let _guard = self.lock_file();
       self.write(|app| app.load_index_map_from_file())
    }
 
+   /// Save a snapshot of the entries, the app stays usable while the file is written
    pub fn save_index_map_to_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-      self.read(|app| app.save_index_map_to_file())
+      let _guard = self.lock_file();
+      let (path, data, file_hash) = self.read(|app| {
+         serde_json::to_vec(app).map(|data| (app.data_file.clone(), data, app.file_hash))
+      })?;
+
+      let hash = write_data_file(&path, &data, file_hash, DATA_FILE_RELOAD_HINT)?;
+      self.write(|app| {
+         if app.data_file == path {
+            app.file_hash = Some(hash);
+         }
+      });
+      Ok(())
+   }
+
+   /// Use another data file, loading its entries or moving the current ones there if it
+   /// does not exist yet
+   ///
+   /// Nothing changes if the new file can't be read or written, or if it uses another scheme
+   /// than the unlocked seed.
+   pub fn switch_data_file(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+      let mut temp = AppData {
+         data_file: path.to_path_buf(),
+         ..Default::default()
+      };
+
+      if path.exists() {
+         temp.load_index_map_from_file()?;
+         let scheme = temp.scheme()?;
+         let unlocked = self.read(|app| app.passwd_derive.is_some().then(|| app.scheme()));
+         if let Some(current) = unlocked.transpose()?
+            && current != scheme
+         {
+            return Err(
+               format!(
+                  "It uses the {} derivation scheme and you are logged in with {}, \
+                  lock the app and log in again to use it",
+                  scheme.to_str(),
+                  current.to_str()
+               )
+               .into(),
+            );
+         }
+      } else {
+         self.read(|app| {
+            temp.index_map = app.index_map.clone();
+            temp.tombstones = app.tombstones.clone();
+            temp.scheme_version = app.scheme_version;
+         });
+         temp.save_index_map_to_file()?;
+      }
+
+      self.write(|app| {
+         app.data_file = temp.data_file;
+         app.index_map = temp.index_map;
+         app.tombstones = temp.tombstones;
+         app.scheme_version = temp.scheme_version;
+         app.file_hash = temp.file_hash;
+      });
+      Ok(())
    }
 
    pub fn get_index(&self, index: u32) -> Option<IndexData> {
@@ -48,6 +123,76 @@ impl AppCtx {
       });
    }
 
+   /// Update the last used timestamp of the entry at this index
+   pub fn mark_used(&self, index: u32) -> Result<(), Box<dyn std::error::Error>> {
+      let exists = self.write(|app| match app.index_map.get_mut(&index) {
+         Some(data) => {
+            data.last_used_at = unix_now();
+            true
+         }
+         None => false,
+      });
+
+      if exists {
+         self.save_index_map_to_file()?;
+      }
+
+      Ok(())
+   }
+
+   /// Mark the entry at this index as reviewed in the audit, undone if it can't be saved
+   pub fn mark_reviewed(&self, index: u32) -> Result<(), Box<dyn std::error::Error>> {
+      let previous = self.write(|app| {
+         let data = app.index_map.get_mut(&index)?;
+         Some(std::mem::replace(
+            &mut data.reviewed_at,
+            unix_now(),
+         ))
+      });
+
+      let Some(previous) = previous else {
+         return Ok(());
+      };
+
+      if let Err(e) = self.save_index_map_to_file() {
+         self.write(|app| {
+            if let Some(data) = app.index_map.get_mut(&index) {
+               data.reviewed_at = previous;
+            }
+         });
+         return Err(e);
+      }
+
+      Ok(())
+   }
+
+   /// Unlock with `deriver` and record its `scheme` in the data file
+   ///
+   /// The file is only saved when it recorded no or another scheme. If that fails the app
+   /// stays locked, so the entries never belong to a scheme the file does not name.
+   pub fn set_deriver(
+      &self,
+      deriver: PasswordDeriver,
+      scheme: Scheme,
+   ) -> Result<(), Box<dyn std::error::Error>> {
+      let previous = self.write(|app| {
+         app.passwd_derive = Some(deriver);
+         app.scheme_version.replace(scheme.version())
+      });
+      if previous == Some(scheme.version()) {
+         return Ok(());
+      }
+
+      if let Err(e) = self.save_index_map_to_file() {
+         self.write(|app| {
+            app.passwd_derive = None;
+            app.scheme_version = previous;
+         });
+         return Err(e);
+      }
+      Ok(())
+   }
+
    pub fn derive_at(&self, index: u32) -> Result<SecureString, Box<dyn std::error::Error>> {
       self.read(|app| {
          if let Some(deriver) = &app.passwd_derive {
@@ -64,32 +209,323 @@ pub struct AppData {
    #[serde(skip)]
    pub passwd_derive: Option<PasswordDeriver>,
    pub index_map: HashMap<u32, IndexData>,
+   /// Deleted entries, their indices are never handed out again
+   #[serde(default)]
+   pub tombstones: HashMap<u32, Tombstone>,
+   /// Where the index map is loaded from and saved to, see [Settings::data_file_path]
+   #[serde(skip)]
+   pub data_file: PathBuf,
+   /// Hash of the data file as we last loaded or saved it, `None` if we never did
+   #[serde(skip)]
+   pub file_hash: Option<[u8; 32]>,
+   /// Version of the seed derivation the entries belong to, see [AppData::scheme]
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub scheme_version: Option<u32>,
 }
 
 impl AppData {
    pub fn load_index_map_from_file(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-      let dir = std::env::current_dir()?;
-      let path = dir.join("NoPassPlz.json");
-      let data = std::fs::read(&path)?;
+      let data = std::fs::read(&self.data_file)?;
       let temp: AppData = serde_json::from_slice(&data)?;
       self.index_map = temp.index_map;
+      self.tombstones = temp.tombstones;
+      self.scheme_version = temp.scheme_version;
+      self.file_hash = Some(content_hash(&data));
       Ok(())
    }
 
-   pub fn save_index_map_to_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-      let dir = std::env::current_dir()?;
-      let path = dir.join("NoPassPlz.json");
-      let data = serde_json::to_string(self)?;
-      std::fs::write(path, data)?;
+   /// The seed derivation to log in with
+   ///
+   /// Data files written before the version was recorded are v1. Without a data file it's
+   /// v1 too: a user whose file went missing must get the same passwords as before, a new
+   /// user picks v2 explicitly.
+   pub fn scheme(&self) -> Result<Scheme, Box<dyn std::error::Error>> {
+      match self.scheme_version {
+         Some(version) => Scheme::from_version(version),
+         None => Ok(Scheme::V1),
+      }
+   }
+
+   /// Fails instead of overwriting changes another program made to the file since we loaded it
+   pub fn save_index_map_to_file(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+      let data = serde_json::to_vec(self)?;
+      self.file_hash = Some(write_data_file(
+         &self.data_file,
+         &data,
+         self.file_hash,
+         DATA_FILE_RELOAD_HINT,
+      )?);
+      Ok(())
+   }
+
+   /// The highest index that has an entry or a tombstone
+   pub fn highest_used_index(&self) -> Option<u32> {
+      self.index_map.keys().chain(self.tombstones.keys()).max().copied()
+   }
+
+   /// True if this index has neither an entry nor a tombstone
+   pub fn is_free(&self, index: u32) -> bool {
+      !self.index_map.contains_key(&index) && !self.tombstones.contains_key(&index)
+   }
+
+   /// The lowest index that has no entry and was never deleted
+   pub fn lowest_free_index(&self) -> u32 {
+      (0..u32::MAX).find(|i| self.is_free(*i)).unwrap_or(u32::MAX)
+   }
+
+   /// The index right after the highest used one
+   pub fn index_after_highest(&self) -> u32 {
+      self.highest_used_index().map_or(0, |i| i.saturating_add(1))
+   }
+
+   /// True if this free index sits below an index that is in use,
+   /// meaning it may have been used before and then removed
+   pub fn is_gap(&self, index: u32) -> bool {
+      self.is_free(index) && self.highest_used_index().is_some_and(|h| index < h)
+   }
+
+   /// Move the entry at this index to the trash
+   ///
+   /// Returns false if there is no entry at this index
+   pub fn delete_index(&mut self, index: u32) -> bool {
+      let Some(entry) = self.index_map.remove(&index) else {
+         return false;
+      };
+
+      let tombstone = Tombstone {
+         deleted_at: unix_now(),
+         entry,
+      };
+      self.tombstones.insert(index, tombstone);
+      true
+   }
+
+   /// Move the entry to the first index after the highest used one so it gets a new password
+   ///
+   /// The old index goes to the trash. Returns the new index.
+   pub fn rotate_index(&mut self, index: u32) -> Result<u32, Box<dyn std::error::Error>> {
+      let mut entry = self.index_map.get(&index).cloned().ok_or("No entry at this index")?;
+      let new_index = self.index_after_highest();
+      self.delete_index(index);
+
+      let now = unix_now();
+      entry.exposed = false;
+      entry.rotated_at = now;
+      entry.modified_at = now;
+      self.index_map.insert(new_index, entry);
+      Ok(new_index)
+   }
+
+   /// Bring back a deleted entry from the trash
+   pub fn restore_index(&mut self, index: u32) -> Result<(), Box<dyn std::error::Error>> {
+      if self.index_map.contains_key(&index) {
+         return Err(format!("Index {} is already in use", index).into());
+      }
+
+      let tombstone = self.tombstones.remove(&index).ok_or("No deleted entry at this index")?;
+      self.index_map.insert(index, tombstone.entry);
       Ok(())
    }
 }
 
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct IndexData {
    pub exposed: bool,
    pub title: String,
    pub description: String,
+   /// The login/username of the account this password belongs to
+   pub username: String,
+   pub urls: Vec<String>,
+   pub tags: Vec<String>,
+   /// Unix timestamps in seconds, 0 means unknown
+   pub created_at: u64,
+   pub modified_at: u64,
+   pub last_used_at: u64,
+   /// When the entry was last moved to a fresh index, see [AppData::rotate_index]
+   pub rotated_at: u64,
+   /// When the user last marked the entry as reviewed in the audit
+   pub reviewed_at: u64,
+   pub custom_fields: Vec<CustomField>,
+}
+
+impl IndexData {
+   /// The first URL of this entry if any
+   pub fn primary_url(&self) -> Option<&str> {
+      self.urls.first().map(|url| url.as_str())
+   }
+}
+
+/// Builder of the entries used by the tests of every module
+#[cfg(test)]
+impl IndexData {
+   pub fn titled(title: &str) -> Self {
+      Self {
+         title: title.to_string(),
+         ..Default::default()
+      }
+   }
+
+   pub fn with_username(mut self, username: &str) -> Self {
+      self.username = username.to_string();
+      self
+   }
+
+   pub fn with_url(mut self, url: &str) -> Self {
+      self.urls.push(url.to_string());
+      self
+   }
+
+   pub fn with_tags(mut self, tags: &[&str]) -> Self {
+      self.tags = tags.iter().map(|tag| tag.to_string()).collect();
+      self
+   }
+
+   pub fn with_notes(mut self, notes: &str) -> Self {
+      self.description = notes.to_string();
+      self
+   }
+
+   pub fn with_custom_field(mut self, key: &str, value: &str) -> Self {
+      self.custom_fields.push(CustomField {
+         key: key.to_string(),
+         value: value.to_string(),
+      });
+      self
+   }
+
+   pub fn with_exposed(mut self, exposed: bool) -> Self {
+      self.exposed = exposed;
+      self
+   }
+
+   /// Sets both the creation and modification time
+   pub fn created_at(mut self, created_at: u64) -> Self {
+      self.created_at = created_at;
+      self.modified_at = created_at;
+      self
+   }
+
+   pub fn modified_at(mut self, modified_at: u64) -> Self {
+      self.modified_at = modified_at;
+      self
+   }
+}
+
+/// Record of a deleted entry
+///
+/// Kept in the data file so the index is not reassigned to a new entry
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tombstone {
+   /// Unix timestamp in seconds
+   pub deleted_at: u64,
+   /// The entry as it was before deletion
+   pub entry: IndexData,
+}
+
+/// An arbitrary key/value pair attached to an [IndexData]
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CustomField {
+   pub key: String,
+   pub value: String,
+}
+
+/// SHA3-256 of the data file content
+pub fn content_hash(data: &[u8]) -> [u8; 32] {
+   Sha3_256::digest(data).into()
+}
+
+/// True if the file no longer has the content hashed as `file_hash`
+fn changed_on_disk(
+   path: &Path,
+   file_hash: Option<[u8; 32]>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+   let Some(hash) = file_hash else {
+      return Ok(false);
+   };
+
+   match std::fs::read(path) {
+      Ok(data) => Ok(content_hash(&data) != hash),
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+      Err(e) => Err(e.into()),
+   }
+}
+
+/// The watcher loads a data file changed by another program by itself
+const DATA_FILE_RELOAD_HINT: &str = "The file is reloaded automatically, try again in a moment.";
+
+/// Replace the data file with `data` unless another program changed it since `file_hash`
+///
+/// The content goes to a temporary file next to it first and is renamed over the data file,
+/// so readers never see a half written file and the check races only with the rename.
+/// Returns the hash of the new content, `reload_hint` tells how to get the other change.
+pub fn write_data_file(
+   path: &Path,
+   data: &[u8],
+   file_hash: Option<[u8; 32]>,
+   reload_hint: &str,
+) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+   let mut temp = path.as_os_str().to_owned();
+   temp.push(".tmp");
+   let temp = PathBuf::from(temp);
+   std::fs::write(&temp, data)?;
+
+   if changed_on_disk(path, file_hash)? {
+      let _ = std::fs::remove_file(&temp);
+      return Err(
+         format!(
+            "{} was changed by another program, your change was not saved. {}",
+            path.display(),
+            reload_hint
+         )
+         .into(),
+      );
+   }
+
+   if let Err(e) = std::fs::rename(&temp, path) {
+      let _ = std::fs::remove_file(&temp);
+      return Err(e.into());
+   }
+   Ok(content_hash(data))
+}
+
+/// Current unix time in seconds
+pub fn unix_now() -> u64 {
+   std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0)
+}
+
+/// Format a unix timestamp as `YYYY-MM-DD HH:MM` (UTC)
+pub fn format_timestamp(timestamp: u64) -> String {
+   if timestamp == 0 {
+      return String::from("Never");
+   }
+
+   let days = (timestamp / 86_400) as i64;
+   let secs_of_day = timestamp % 86_400;
+
+   // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+   let z = days + 719_468;
+   let era = z.div_euclid(146_097);
+   let doe = z - era * 146_097;
+   let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+   let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+   let mp = (5 * doy + 2) / 153;
+   let day = doy - (153 * mp + 2) / 5 + 1;
+   let month = if mp < 10 { mp + 3 } else { mp - 9 };
+   let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+   format!(
+      "{:04}-{:02}-{:02} {:02}:{:02}",
+      year,
+      month,
+      day,
+      secs_of_day / 3_600,
+      (secs_of_day % 3_600) / 60
+   )
 }
 
 pub struct App {
@@ -100,14 +536,31 @@ pub struct App {
 impl App {
    pub fn new(cc: &CreationContext) -> Self {
       let egui_ctx = cc.egui_ctx.clone();
-      let theme = Theme::new(ThemeKind::Dark);
-      egui_ctx.set_style(theme.style.clone());
+      let app_ctx = AppCtx::default();
+
+      let (settings, settings_error) = match Settings::load() {
+         Ok(settings) => (settings, None),
+         Err(e) => (Settings::default(), Some(e.to_string())),
+      };
+
+      match settings.data_file_path() {
+         Ok(path) => app_ctx.write(|app| app.data_file = path),
+         Err(e) => eprintln!("Failed to resolve the data file {}", e),
+      }
 
       SHARED_GUI.write(|gui| {
-         gui.egui_ctx = egui_ctx;
-      });
+         gui.egui_ctx = egui_ctx.clone();
+         gui.apply_settings(&settings);
+         gui.settings = settings;
 
-      let app_ctx = AppCtx::default();
+         if let Some(e) = settings_error {
+            let msg = format!(
+               "Failed to load the settings, using the defaults: {}",
+               e
+            );
+            gui.msg_window.open("Error", msg);
+         }
+      });
 
       match app_ctx.load_index_map_from_file() {
          Ok(_) => {}
@@ -116,14 +569,17 @@ impl App {
          }
       };
 
+      watch::spawn(app_ctx.clone());
+
       Self {
          style_has_been_set: false,
          app_ctx,
       }
    }
 
-   fn on_shutdown(&mut self, ctx: &egui::Context) {
+   fn on_shutdown(&mut self, ctx: &egui::Context, gui: &mut GUI) {
       if ctx.input(|i| i.viewport().close_requested()) {
+         gui.home.clear_clipboard(ctx);
          self.app_ctx.write(|app| {
             if let Some(mut deriver) = app.passwd_derive.take() {
                deriver.erase();
@@ -140,12 +596,13 @@ impl eframe::App for App {
 
    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
       SHARED_GUI.write(|gui| {
-         self.on_shutdown(ctx);
+         self.on_shutdown(ctx, gui);
+         gui.lock_if_needed(self.app_ctx.clone(), ctx);
+         gui.follow_system_theme(ctx);
 
          // This is needed for Windows
          if !self.style_has_been_set {
-            let style = gui.theme.style.clone();
-            ctx.set_style(style);
+            set_style(ctx, &gui.theme);
             self.style_has_been_set = true;
          }
 
@@ -161,7 +618,7 @@ impl eframe::App for App {
             .show_separator_line(false)
             .frame(top_frame)
             .show(ctx, |ui| {
-               gui.show_top_panel(ui);
+               gui.show_top_panel(self.app_ctx.clone(), ui);
             });
 
          egui::CentralPanel::default().frame(panel_frame).show(ctx, |ui| {
@@ -172,3 +629,99 @@ impl eframe::App for App {
       });
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use argon2_rs::Argon2;
+   use passwd_derive::random_seed;
+
+   #[test]
+   fn test_scheme_version() {
+      let path = std::env::temp_dir().join(format!("scheme-test-{}.json", std::process::id()));
+      assert_eq!(AppData::default().scheme().unwrap(), Scheme::V1);
+
+      let load = || {
+         let mut data = AppData {
+            data_file: path.clone(),
+            ..Default::default()
+         };
+         data.load_index_map_from_file().map(|_| data)
+      };
+
+      // Files from before the version was recorded are v1
+      std::fs::write(&path, r#"{"index_map":{}}"#).unwrap();
+      let mut data = load().unwrap();
+      assert_eq!(data.scheme().unwrap(), Scheme::V1);
+
+      data.scheme_version = Some(2);
+      data.save_index_map_to_file().unwrap();
+      assert_eq!(load().unwrap().scheme().unwrap(), Scheme::V2);
+
+      std::fs::write(&path, r#"{"index_map":{},"scheme_version":9}"#).unwrap();
+      assert!(load().unwrap().scheme().is_err());
+
+      std::fs::remove_file(&path).unwrap();
+   }
+
+   #[test]
+   fn test_mark_reviewed_rolls_back() {
+      let app = AppCtx::default();
+      app.write(|app| app.data_file = PathBuf::from("/nonexistent/NoPassPlz.json"));
+      app.set_index(0, IndexData::titled("Mail"));
+
+      assert!(app.mark_reviewed(0).is_err());
+      assert_eq!(app.get_index(0).unwrap().reviewed_at, 0);
+   }
+
+   #[test]
+   fn test_switch_refuses_other_scheme() {
+      let path = std::env::temp_dir().join(format!("switch-test-{}.json", std::process::id()));
+      std::fs::write(&path, r#"{"index_map":{},"scheme_version":2}"#).unwrap();
+
+      let app = AppCtx::default();
+      let deriver = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      app.write(|app| app.passwd_derive = Some(deriver));
+      app.set_index(0, IndexData::titled("Mail"));
+
+      assert!(app.switch_data_file(&path).is_err());
+      assert_eq!(app.read(|app| app.index_map.len()), 1);
+
+      // Once locked the file is used with its scheme
+      app.write(|app| app.passwd_derive = None);
+      app.switch_data_file(&path).unwrap();
+      assert_eq!(app.read(|app| app.scheme().unwrap()), Scheme::V2);
+
+      std::fs::remove_file(&path).unwrap();
+   }
+
+   #[test]
+   fn test_set_deriver_records_scheme() {
+      let deriver =
+         || PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      let app = AppCtx::default();
+      app.write(|app| app.data_file = PathBuf::from("/nonexistent/NoPassPlz.json"));
+
+      // The scheme can't be recorded, the app stays locked
+      assert!(app.set_deriver(deriver(), Scheme::V2).is_err());
+      assert!(app.read(|app| app.passwd_derive.is_none()));
+      assert_eq!(app.read(|app| app.scheme_version), None);
+
+      let path = std::env::temp_dir().join(format!(
+         "deriver-test-{}.json",
+         std::process::id()
+      ));
+      app.write(|app| app.data_file = path.clone());
+      app.set_deriver(deriver(), Scheme::V2).unwrap();
+      assert!(app.read(|app| app.passwd_derive.is_some()));
+
+      let mut data = AppData {
+         data_file: path.clone(),
+         ..Default::default()
+      };
+      data.load_index_map_from_file().unwrap();
+      assert_eq!(data.scheme().unwrap(), Scheme::V2);
+
+      std::fs::remove_file(&path).unwrap();
+   }
+}
@@ -0,0 +1,452 @@
+use super::{
+   AppCtx, SHARED_GUI,
+   app::{IndexData, unix_now},
+};
+use eframe::egui::{Align2, Button, ComboBox, RichText, ScrollArea, Ui, Window, vec2};
+use std::collections::HashMap;
+use zeus_theme::Theme;
+
+/// Months without rotation the user can choose from
+pub const STALE_MONTHS: [u64; 4] = [3, 6, 12, 24];
+
+/// Size of the HMAC-SHA3-512 output every password is derived from
+pub const MAX_ENTROPY_BITS: f64 = 512.0;
+
+const SECS_PER_MONTH: u64 = 30 * 86_400;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditIssue {
+   Exposed,
+   Stale,
+   DuplicateTitle,
+   DuplicateUrl,
+   NoMetadata,
+}
+
+impl AuditIssue {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         AuditIssue::Exposed => "Exposed",
+         AuditIssue::Stale => "Not rotated recently",
+         AuditIssue::DuplicateTitle => "Duplicate titles",
+         AuditIssue::DuplicateUrl => "Duplicate URLs",
+         AuditIssue::NoMetadata => "No metadata",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![
+         Self::Exposed,
+         Self::Stale,
+         Self::DuplicateTitle,
+         Self::DuplicateUrl,
+         Self::NoMetadata,
+      ]
+   }
+}
+
+/// One problem with one entry
+#[derive(Clone, Debug)]
+pub struct Finding {
+   pub index: u32,
+   pub title: String,
+   pub issue: AuditIssue,
+   pub detail: String,
+}
+
+/// Entropy of a password in bits, from its length and the character classes it uses
+///
+/// Capped at [MAX_ENTROPY_BITS] since no output can hold more than the HMAC it comes from.
+pub fn output_entropy_bits(password: &str) -> f64 {
+   let mut pool = 0;
+   if password.chars().any(|c| c.is_ascii_lowercase()) {
+      pool += 26;
+   }
+   if password.chars().any(|c| c.is_ascii_uppercase()) {
+      pool += 26;
+   }
+   if password.chars().any(|c| c.is_ascii_digit()) {
+      pool += 10;
+   }
+   if password.chars().any(|c| !c.is_ascii_alphanumeric()) {
+      pool += 33;
+   }
+
+   if pool == 0 {
+      return 0.0;
+   }
+
+   let bits = password.chars().count() as f64 * (pool as f64).log2();
+   bits.min(MAX_ENTROPY_BITS)
+}
+
+/// Describe the output format of a password and its entropy, like `128 characters, 512 bits`
+pub fn output_format_text(password: &str) -> String {
+   format!(
+      "Output: {} characters, {:.0} bits of entropy",
+      password.chars().count(),
+      output_entropy_bits(password)
+   )
+}
+
+/// True if the user reviewed the entry after its last change
+fn is_reviewed(data: &IndexData) -> bool {
+   data.reviewed_at > 0 && data.reviewed_at >= data.modified_at
+}
+
+/// Lowercase host and path without the scheme, `www.` and trailing slash
+fn normalize_url(url: &str) -> String {
+   let url = url.trim().to_lowercase();
+   let url = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+   let url = url.strip_prefix("www.").unwrap_or(url);
+   url.trim_end_matches('/').to_string()
+}
+
+/// Find the problems of every entry
+///
+/// Reviewed entries are left out, except exposed ones which always need rotating.
+pub fn audit(index_map: &HashMap<u32, IndexData>, now: u64, stale_months: u64) -> Vec<Finding> {
+   let mut entries: Vec<(&u32, &IndexData)> = index_map.iter().collect();
+   entries.sort_by_key(|(index, _)| **index);
+
+   let mut findings = Vec::new();
+   let mut push = |index: u32, data: &IndexData, issue, detail: String| {
+      findings.push(Finding {
+         index,
+         title: data.title.clone(),
+         issue,
+         detail,
+      });
+   };
+
+   let mut titles: HashMap<String, Vec<u32>> = HashMap::new();
+   let mut urls: HashMap<String, Vec<u32>> = HashMap::new();
+   for (index, data) in &entries {
+      titles.entry(data.title.trim().to_lowercase()).or_default().push(**index);
+      for url in &data.urls {
+         let list = urls.entry(normalize_url(url)).or_default();
+         if !list.contains(index) {
+            list.push(**index);
+         }
+      }
+   }
+
+   let others = |list: &[u32], index: u32| -> String {
+      let others: Vec<String> =
+         list.iter().filter(|i| **i != index).map(|i| i.to_string()).collect();
+      others.join(", ")
+   };
+
+   for (index, data) in entries {
+      let index = *index;
+
+      if data.exposed {
+         push(
+            index,
+            data,
+            AuditIssue::Exposed,
+            String::from("Marked as exposed"),
+         );
+      }
+
+      if is_reviewed(data) {
+         continue;
+      }
+
+      let last_change = data.created_at.max(data.rotated_at).max(data.reviewed_at);
+      if last_change > 0 && now.saturating_sub(last_change) > stale_months * SECS_PER_MONTH {
+         let months = now.saturating_sub(last_change) / SECS_PER_MONTH;
+         let detail = format!("Not rotated for {} months", months);
+         push(index, data, AuditIssue::Stale, detail);
+      }
+
+      let key = data.title.trim().to_lowercase();
+      if let Some(list) = titles.get(&key)
+         && list.len() > 1
+      {
+         let detail = format!("Same title as {}", others(list, index));
+         push(index, data, AuditIssue::DuplicateTitle, detail);
+      }
+
+      for url in &data.urls {
+         if let Some(list) = urls.get(&normalize_url(url))
+            && list.len() > 1
+         {
+            let detail = format!("{} is also used by {}", url, others(list, index));
+            push(index, data, AuditIssue::DuplicateUrl, detail);
+            break;
+         }
+      }
+
+      let has_metadata = !data.username.is_empty()
+         || !data.urls.is_empty()
+         || !data.description.is_empty()
+         || !data.tags.is_empty()
+         || !data.custom_fields.is_empty();
+      if !has_metadata {
+         let detail = String::from("Only a title, no username, URL or notes");
+         push(index, data, AuditIssue::NoMetadata, detail);
+      }
+   }
+
+   findings
+}
+
+/// What the user clicked on a finding
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+   Rotate(u32),
+   Edit(u32),
+   MarkReviewed(u32),
+}
+
+/// Summary of the [audit] with one-click actions
+pub struct AuditWindow {
+   open: bool,
+   stale_months: u64,
+   findings: Vec<Finding>,
+   /// Run the audit again on the next frame
+   outdated: bool,
+}
+
+impl AuditWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         stale_months: 12,
+         findings: Vec::new(),
+         outdated: true,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+      self.outdated = true;
+   }
+
+   /// Run the audit again, call this after the entries change
+   pub fn invalidate(&mut self) {
+      self.outdated = true;
+   }
+
+   fn refresh(&mut self, app: AppCtx) {
+      let index_map = app.read(|app| app.index_map.clone());
+      self.findings = audit(&index_map, unix_now(), self.stale_months);
+      self.outdated = false;
+   }
+
+   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) -> Option<AuditAction> {
+      if !self.open {
+         return None;
+      }
+
+      if self.outdated {
+         self.refresh(app.clone());
+      }
+
+      let mut action = None;
+
+      Window::new("Security audit")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(450.0);
+
+               let text = RichText::new("Security audit").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               ui.horizontal(|ui| {
+                  ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+
+                  let text = RichText::new("Rotate after").size(theme.text_sizes.normal);
+                  ui.label(text);
+
+                  let previous = self.stale_months;
+                  ComboBox::from_id_salt("audit_stale_months")
+                     .selected_text(format!("{} months", self.stale_months))
+                     .show_ui(ui, |ui| {
+                        for months in STALE_MONTHS {
+                           let text = format!("{} months", months);
+                           ui.selectable_value(&mut self.stale_months, months, text);
+                        }
+                     });
+
+                  if previous != self.stale_months {
+                     self.outdated = true;
+                  }
+
+                  let text = RichText::new("Refresh").size(theme.text_sizes.normal);
+                  if ui.button(text).clicked() {
+                     self.outdated = true;
+                  }
+               });
+
+               ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                  for issue in AuditIssue::to_vec() {
+                     if let Some(a) = self.show_issue(issue, theme, ui) {
+                        action = Some(a);
+                     }
+                  }
+               });
+
+               let text = RichText::new("Close").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  self.open = false;
+               }
+            });
+         });
+
+      action
+   }
+
+   fn show_issue(&self, issue: AuditIssue, theme: &Theme, ui: &mut Ui) -> Option<AuditAction> {
+      let findings: Vec<&Finding> = self.findings.iter().filter(|f| f.issue == issue).collect();
+
+      let color = match findings.is_empty() {
+         true => theme.colors.success,
+         false => theme.colors.error,
+      };
+      let text = format!("{} ({})", issue.to_str(), findings.len());
+      ui.label(RichText::new(text).size(theme.text_sizes.large).color(color));
+
+      let mut action = None;
+      for finding in findings {
+         theme.frame2.outer_margin(0).show(ui, |ui| {
+            ui.set_width(ui.available_width());
+
+            let text = format!("{}. {}", finding.index, finding.title);
+            ui.label(RichText::new(text).size(theme.text_sizes.normal));
+
+            let text = RichText::new(&finding.detail).size(theme.text_sizes.small);
+            ui.label(text.color(theme.colors.text_muted));
+
+            ui.horizontal(|ui| {
+               let size = theme.text_sizes.small;
+
+               if ui.button(RichText::new("Rotate").size(size)).clicked() {
+                  action = Some(AuditAction::Rotate(finding.index));
+               }
+
+               if ui.button(RichText::new("Edit").size(size)).clicked() {
+                  action = Some(AuditAction::Edit(finding.index));
+               }
+
+               if issue != AuditIssue::Exposed {
+                  let text = RichText::new("Mark reviewed").size(size);
+                  if ui.button(text).clicked() {
+                     action = Some(AuditAction::MarkReviewed(finding.index));
+                  }
+               }
+            });
+         });
+      }
+
+      action
+   }
+}
+
+/// Move the entry to a new index and tell the user to update the password on the website
+pub fn rotate_and_save(app: AppCtx, index: u32) {
+   let (index_map, tombstones) = app.read(|app| (app.index_map.clone(), app.tombstones.clone()));
+
+   let res = app.write(|app| app.rotate_index(index));
+   let new_index = match res {
+      Ok(new_index) => new_index,
+      Err(err) => {
+         SHARED_GUI.write(|gui| gui.msg_window.open("Error", err.to_string()));
+         return;
+      }
+   };
+
+   if let Err(err) = app.save_index_map_to_file() {
+      app.write(|app| {
+         app.index_map = index_map;
+         app.tombstones = tombstones;
+      });
+      SHARED_GUI.write(|gui| gui.msg_window.open("Error", err.to_string()));
+      return;
+   }
+
+   SHARED_GUI.write(|gui| {
+      let msg = format!(
+         "The entry moved from index {} to {}, change the password on the website to the new one",
+         index, new_index
+      );
+      gui.msg_window.open("Success", msg);
+      gui.home.audit.invalidate();
+      gui.request_repaint();
+   });
+}
+
+/// Mark the entry as reviewed and save, run it from a background thread
+pub fn review_and_save(app: AppCtx, index: u32) {
+   if let Err(err) = app.mark_reviewed(index) {
+      SHARED_GUI.write(|gui| gui.msg_window.open("Error", err.to_string()));
+   }
+
+   SHARED_GUI.write(|gui| {
+      gui.home.audit.invalidate();
+      gui.request_repaint();
+   });
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_audit() {
+      let now = 100 * SECS_PER_MONTH;
+      let mut map = HashMap::new();
+      map.insert(
+         0,
+         IndexData::titled("Mail")
+            .with_url("https://mail.example.com")
+            .created_at(now - 13 * SECS_PER_MONTH),
+      );
+      map.insert(
+         1,
+         IndexData::titled("mail ").with_url("mail.example.com/").created_at(now),
+      );
+      map.insert(
+         2,
+         IndexData::titled("Bare").with_exposed(true).created_at(now),
+      );
+
+      let findings = audit(&map, now, 12);
+      let has = |index: u32, issue: AuditIssue| {
+         findings.iter().any(|f| f.index == index && f.issue == issue)
+      };
+
+      assert!(has(0, AuditIssue::Stale));
+      assert!(!has(1, AuditIssue::Stale));
+      assert!(has(0, AuditIssue::DuplicateTitle) && has(1, AuditIssue::DuplicateTitle));
+      assert!(has(0, AuditIssue::DuplicateUrl) && has(1, AuditIssue::DuplicateUrl));
+      assert!(has(2, AuditIssue::Exposed) && has(2, AuditIssue::NoMetadata));
+
+      // Reviewing hides everything but the exposed flag
+      map.get_mut(&0).unwrap().reviewed_at = now;
+      map.get_mut(&2).unwrap().reviewed_at = now;
+      let findings = audit(&map, now, 12);
+      assert!(!findings.iter().any(|f| f.index == 0));
+      assert_eq!(
+         findings.iter().filter(|f| f.index == 2).count(),
+         1
+      );
+   }
+
+   #[test]
+   fn test_output_entropy_bits() {
+      assert_eq!(output_entropy_bits(""), 0.0);
+      assert!((output_entropy_bits("abcdefgh") - 8.0 * 26f64.log2()).abs() < 1e-9);
+      assert_eq!(
+         output_entropy_bits(&"a1".repeat(64)),
+         MAX_ENTROPY_BITS
+      );
+   }
+}
@@ -1,13 +1,21 @@
 #![allow(dead_code)]
 
 use argon2_rs::Argon2;
-use eframe::egui::{Button, FontId, Margin, RichText, Sense, Ui, vec2};
-use passwd_derive::{PasswordDeriver, fast, normal, slow, very_slow};
+use eframe::egui::{
+   Button, ComboBox, FontId, Margin, ProgressBar, RichText, Sense, TextEdit, Ui, vec2,
+};
+use passwd_derive::{Keyfile, PasswordDeriver, Scheme, fast, normal, slow, very_slow};
 use secure_types::SecureString;
+use std::path::Path;
 use zeus_theme::{Theme, utils::frame_it};
 use zeus_widgets::SecureTextEdit;
 
-use super::{AppCtx, SHARED_GUI};
+use super::{
+   AppCtx, SHARED_GUI,
+   recovery::{RecoveryForm, recover},
+   shortcuts::{Action, Keymap},
+   strength::{Strength, estimate},
+};
 
 pub struct CredentialsForm {
    open: bool,
@@ -15,6 +23,8 @@ pub struct CredentialsForm {
    username: SecureString,
    password: SecureString,
    confirm_password: SecureString,
+   /// Strength of the password, only estimated again when the credentials are edited
+   strength: Option<Strength>,
 }
 
 impl CredentialsForm {
@@ -25,6 +35,7 @@ impl CredentialsForm {
          username: SecureString::new_with_capacity(50).unwrap(),
          password: SecureString::new_with_capacity(50).unwrap(),
          confirm_password: SecureString::new_with_capacity(50).unwrap(),
+         strength: None,
       }
    }
 
@@ -36,17 +47,35 @@ impl CredentialsForm {
       self.open = true;
    }
 
+   /// Strength of the password, None while it's empty
+   pub fn strength(&self) -> Option<&Strength> {
+      self.strength.as_ref()
+   }
+
+   fn update_strength(&mut self) {
+      self.strength = self.password.unlock_str(|password| {
+         if password.is_empty() {
+            return None;
+         }
+         Some(self.username.unlock_str(|username| estimate(password, username)))
+      });
+   }
+
    pub fn erase(&mut self) {
       self.username.erase();
       self.password.erase();
       self.confirm_password.erase();
+      self.strength = None;
    }
 
-   pub fn show(&mut self, theme: &Theme, ui: &mut Ui) {
+   /// Returns true if the username or password was edited
+   pub fn show(&mut self, theme: &Theme, ui: &mut Ui) -> bool {
       if !self.open {
-         return;
+         return false;
       }
 
+      let mut edited = false;
+
       ui.vertical_centered(|ui| {
          ui.spacing_mut().item_spacing = vec2(10.0, 15.0);
 
@@ -61,7 +90,7 @@ impl CredentialsForm {
                .margin(Margin::same(10))
                .password(false)
                .font(FontId::proportional(theme.text_sizes.normal));
-            ui.add(text_edit);
+            edited |= ui.add(text_edit).changed();
          });
 
          // Password Field
@@ -72,9 +101,14 @@ impl CredentialsForm {
                .margin(Margin::same(10))
                .font(FontId::proportional(theme.text_sizes.normal))
                .password(true);
-            ui.add(text_edit);
+            edited |= ui.add(text_edit).changed();
          });
 
+         if edited {
+            self.update_strength();
+         }
+         self.show_strength(text_edit_size.x, theme, ui);
+
          // Confirm Password Field
          if self.with_confirm_password {
             ui.label(RichText::new("Confirm Password").size(theme.text_sizes.large));
@@ -93,6 +127,36 @@ impl CredentialsForm {
             });
          }
       });
+
+      edited
+   }
+}
+
+impl CredentialsForm {
+   fn show_strength(&self, width: f32, theme: &Theme, ui: &mut Ui) {
+      let Some(strength) = self.strength() else {
+         return;
+      };
+
+      let color = match strength.score() {
+         0 | 1 => theme.colors.error,
+         2 => theme.colors.warning,
+         _ => theme.colors.success,
+      };
+
+      let text = format!(
+         "{} (~{:.0} bits)",
+         strength.label(),
+         strength.bits
+      );
+      let progress = (strength.score() + 1) as f32 / 5.0;
+      let bar = ProgressBar::new(progress).desired_width(width).fill(color).text(text);
+      ui.add(bar);
+
+      for weakness in &strength.weaknesses {
+         let text = RichText::new(weakness.to_str()).size(theme.text_sizes.small);
+         ui.label(text.color(theme.colors.warning));
+      }
    }
 }
 
@@ -101,6 +165,17 @@ pub struct Auth {
    show_argon2_selection: bool,
    credentials_form: CredentialsForm,
    argon2: Argon2,
+   /// True while the seed is being computed, so it's not started twice
+   deriving: bool,
+   /// Set after the user was told the password is weak, submitting again goes ahead
+   weak_warning: bool,
+   /// Unlocking with recovery shares instead of the credentials
+   recovering: bool,
+   recovery_form: RecoveryForm,
+   /// Optional keyfile mixed into the seed, empty for none
+   keyfile_path: String,
+   /// Derivation scheme picked by the user instead of the one in the data file
+   scheme: Option<Scheme>,
 }
 
 impl Auth {
@@ -110,23 +185,48 @@ impl Auth {
          show_argon2_selection: true,
          credentials_form: CredentialsForm::new(),
          argon2: slow(),
+         deriving: false,
+         weak_warning: false,
+         recovering: false,
+         recovery_form: RecoveryForm::new(),
+         keyfile_path: String::new(),
+         scheme: None,
       }
    }
 
+   /// The Argon2 parameters used to derive the seed
+   pub fn set_argon2(&mut self, argon2: Argon2) {
+      self.argon2 = argon2;
+   }
+
+   pub fn keyfile_path(&self) -> &str {
+      &self.keyfile_path
+   }
+
+   /// Preselect the keyfile, like the `--keyfile` argument does
+   pub fn set_keyfile_path(&mut self, path: String) {
+      self.keyfile_path = path;
+   }
+
    pub fn close(&mut self) {
       self.open = false;
    }
 
    pub fn erase(&mut self) {
       self.credentials_form.erase();
+      self.recovery_form.erase();
    }
 
-   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+   pub fn show(&mut self, app: AppCtx, keymap: &Keymap, theme: &Theme, ui: &mut Ui) {
       if !self.open {
          return;
       }
 
-      self.show_credentials_input(app, theme, ui);
+      if self.recovering {
+         self.show_recovery_input(app, keymap, theme, ui);
+      } else {
+         self.show_credentials_input(app, keymap, theme, ui);
+      }
    }
 
    pub fn _show_argon2_selection(&mut self, theme: &Theme, ui: &mut Ui) {
@@ -144,8 +244,8 @@ impl Auth {
             ui.spacing_mut().item_spacing = vec2(5.0, 3.0);
             ui.spacing_mut().button_padding = vec2(8.0, 8.0);
 
-            let params = vec![fast(), normal(), slow(), very_slow()];
-            let est_times = vec!["17 secs", "35 secs", "1:11 min", "2:17 mins"];
+            let params = [fast(), normal(), slow(), very_slow()];
+            let est_times = ["17 secs", "35 secs", "1:11 min", "2:17 mins"];
 
             let mut frame = theme.frame2;
             let visuals = theme.frame2_visuals;
@@ -201,7 +301,13 @@ impl Auth {
       });
    }
 
-   pub fn show_credentials_input(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+   pub fn show_credentials_input(
+      &mut self,
+      app: AppCtx,
+      keymap: &Keymap,
+      theme: &Theme,
+      ui: &mut Ui,
+   ) {
       if !self.credentials_form.is_open() {
          return;
       }
@@ -210,13 +316,35 @@ impl Auth {
          ui.spacing_mut().item_spacing = vec2(10.0, 15.0);
          ui.spacing_mut().button_padding = vec2(8.0, 8.0);
 
-         self.credentials_form.show(theme, ui);
+         if self.credentials_form.show(theme, ui) {
+            self.weak_warning = false;
+         }
+         self.show_keyfile_input(theme, ui);
+         self.show_scheme_selection(app.clone(), theme, ui);
 
          let text = RichText::new("OK").size(theme.text_sizes.normal);
          let button = Button::new(text).min_size(vec2(100.0, 25.0));
 
-         if ui.add(button).clicked() {
-            self.init_deriver(app.clone());
+         let submit = keymap.pressed(ui.ctx(), Action::SubmitCredentials);
+         if ui.add(button).clicked() || submit {
+            let weak = self.credentials_form.strength().is_none_or(|s| s.score() < 2);
+            if weak && !self.weak_warning {
+               self.weak_warning = true;
+            } else {
+               self.init_deriver(app.clone());
+            }
+         }
+
+         if self.weak_warning {
+            let text = "This master password is weak and everything depends on it. \
+               Press OK again to use it anyway.";
+            let text = RichText::new(text).size(theme.text_sizes.normal);
+            ui.label(text.color(theme.colors.error));
+         }
+
+         let text = RichText::new("Recover from shares").size(theme.text_sizes.small);
+         if ui.link(text).clicked() {
+            self.recovering = true;
          }
 
          #[cfg(feature = "dev")]
@@ -238,39 +366,210 @@ impl Auth {
       });
    }
 
-   pub fn init_deriver(&self, app: AppCtx) {
+   /// Path of the keyfile, a file dropped on the window is picked too
+   fn show_keyfile_input(&mut self, theme: &Theme, ui: &mut Ui) {
+      let dropped = ui.ctx().input(|i| i.raw.dropped_files.first().and_then(|f| f.path.clone()));
+      if let Some(path) = dropped {
+         self.keyfile_path = path.display().to_string();
+      }
+
+      ui.label(RichText::new("Keyfile (optional)").size(theme.text_sizes.large));
+      let text_edit = TextEdit::singleline(&mut self.keyfile_path)
+         .min_size(vec2(ui.available_width() * 0.6, 20.0))
+         .margin(Margin::same(10))
+         .font(FontId::proportional(theme.text_sizes.normal))
+         .hint_text("Drop a file here or type its path");
+      ui.add(text_edit);
+   }
+
+   /// The scheme recorded in the data file, it only needs changing without the data file
+   ///
+   /// Without a data file v1 stays selected, v2 is only for new credentials and must be
+   /// picked on purpose.
+   fn show_scheme_selection(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      let (recorded, has_file) = app.read(|app| (app.scheme(), app.file_hash.is_some()));
+      let mut selected = self.scheme.unwrap_or(*recorded.as_ref().unwrap_or(&Scheme::V1));
+
+      ui.horizontal(|ui| {
+         ui.add_space(ui.available_width() / 2.0 - 120.0);
+
+         let text = RichText::new("Derivation scheme").size(theme.text_sizes.normal);
+         ui.label(text);
+         ComboBox::from_id_salt("auth_scheme")
+            .selected_text(selected.to_str())
+            .show_ui(ui, |ui| {
+               for scheme in Scheme::to_vec() {
+                  ui.selectable_value(&mut selected, scheme, scheme.to_str());
+               }
+            });
+      });
+
+      self.scheme = (recorded.as_ref().ok() != Some(&selected)).then_some(selected);
+
+      match recorded {
+         Err(e) => {
+            let text = RichText::new(e.to_string()).size(theme.text_sizes.small);
+            ui.label(text.color(theme.colors.error));
+         }
+         Ok(_) if !has_file => {
+            let text = format!(
+               "No data file was found. Keep {} if you used NoPassPlz before, \
+               choose {} only for new credentials.",
+               Scheme::V1.to_str(),
+               Scheme::LATEST.to_str()
+            );
+            let text = RichText::new(text).size(theme.text_sizes.small);
+            ui.label(text.color(theme.colors.warning));
+         }
+         Ok(recorded) if recorded != selected => {
+            let text = format!(
+               "Your data file was made with {}, other passwords will be derived",
+               recorded.to_str()
+            );
+            let text = RichText::new(text).size(theme.text_sizes.small);
+            ui.label(text.color(theme.colors.warning));
+         }
+         Ok(_) => {}
+      }
+   }
+
+   fn show_recovery_input(&mut self, app: AppCtx, keymap: &Keymap, theme: &Theme, ui: &mut Ui) {
+      ui.vertical_centered(|ui| {
+         ui.spacing_mut().item_spacing = vec2(10.0, 15.0);
+         ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+
+         self.recovery_form.show(theme, ui);
+
+         ui.horizontal(|ui| {
+            ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+            let text = RichText::new("Back").size(theme.text_sizes.normal);
+            let button = Button::new(text).min_size(vec2(100.0, 25.0));
+            if ui.add(button).clicked() {
+               self.recovery_form.erase();
+               self.recovery_form = RecoveryForm::new();
+               self.recovering = false;
+            }
+
+            let text = RichText::new("OK").size(theme.text_sizes.normal);
+            let button = Button::new(text).min_size(vec2(100.0, 25.0));
+            let submit = keymap.pressed(ui.ctx(), Action::SubmitCredentials);
+            if ui.add(button).clicked() || submit {
+               self.recover_deriver(app);
+            }
+         });
+      });
+   }
+
+   /// Rebuild the seed from the recovery shares, no Argon2 is needed so it's quick
+   fn recover_deriver(&mut self, app: AppCtx) {
+      if self.deriving {
+         return;
+      }
+      self.deriving = true;
+
+      let shares = self.recovery_form.shares.clone();
+      let argon2 = self.argon2.clone();
+
+      std::thread::spawn(move || {
+         let deriver = match recover(&shares, argon2) {
+            Ok(deriver) => deriver,
+            Err(err) => {
+               SHARED_GUI.write(|gui| {
+                  gui.msg_window.open("Error", err);
+                  gui.auth.deriving = false;
+                  gui.request_repaint();
+               });
+               return;
+            }
+         };
+
+         SHARED_GUI.write(|gui| {
+            gui.auth.close();
+            gui.auth.erase();
+            gui.auto_lock.reset();
+            gui.home.open();
+            gui.request_repaint();
+         });
+
+         app.write(|app| {
+            app.passwd_derive = Some(deriver);
+         });
+      });
+   }
+
+   pub fn init_deriver(&mut self, app: AppCtx) {
+      if self.deriving {
+         return;
+      }
+      self.deriving = true;
+
       let username = self.credentials_form.username.clone();
       let password = self.credentials_form.password.clone();
       let confirm_password = self.credentials_form.confirm_password.clone();
       let argon2 = self.argon2.clone();
+      let keyfile_path = self.keyfile_path.trim().to_string();
+      let scheme = self.scheme;
 
       std::thread::spawn(move || {
          SHARED_GUI.write(|gui| {
             gui.loading_window.open("Please wait... this may take a minute or two");
          });
 
-         let deriver = match PasswordDeriver::new(username, password, confirm_password, argon2) {
-            Ok(deriver) => deriver,
+         let scheme = match scheme {
+            Some(scheme) => Ok(scheme),
+            None => app.read(|app| app.scheme()),
+         };
+         let keyfile = match keyfile_path.is_empty() {
+            true => Ok(None),
+            false => Keyfile::read(Path::new(&keyfile_path)).map(Some),
+         };
+         let res = scheme.and_then(|scheme| {
+            let keyfile = keyfile?;
+            let deriver = PasswordDeriver::new(
+               username,
+               password,
+               confirm_password,
+               argon2,
+               keyfile.as_ref(),
+               scheme,
+            )?;
+            Ok((deriver, scheme))
+         });
+
+         let (deriver, scheme) = match res {
+            Ok(res) => res,
             Err(err) => {
                SHARED_GUI.write(|gui| {
                   gui.msg_window.open("Error", err.to_string());
                   gui.loading_window.reset();
+                  gui.auth.deriving = false;
                });
                return;
             }
          };
 
+         if let Err(e) = app.set_deriver(deriver, scheme) {
+            SHARED_GUI.write(|gui| {
+               let msg = format!(
+                  "Failed to record the scheme in the data file: {}",
+                  e
+               );
+               gui.msg_window.open("Error", msg);
+               gui.loading_window.reset();
+               gui.auth.deriving = false;
+            });
+            return;
+         }
+
          SHARED_GUI.write(|gui| {
             gui.loading_window.reset();
             gui.auth.close();
             gui.auth.erase();
+            gui.auto_lock.reset();
             gui.home.open();
             gui.request_repaint();
          });
-
-         app.write(|app| {
-            app.passwd_derive = Some(deriver);
-         });
       });
    }
 }
@@ -0,0 +1,455 @@
+use super::{AppCtx, SHARED_GUI};
+use eframe::egui::{Align2, Button, FontId, RichText, ScrollArea, TextEdit, Ui, Window, vec2};
+use secure_types::SecureString;
+use sha1::{Digest, Sha1};
+use std::{
+   io::{Read, Write},
+   net::TcpStream,
+   path::PathBuf,
+   time::Duration,
+};
+use zeus_theme::Theme;
+
+/// Where the Pwned Passwords ranges are looked up
+///
+/// Only the first 5 hex characters of the SHA-1 hash ever leave this module (k-anonymity),
+/// and only to a folder or a server the user chose.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RangeSource {
+   /// A folder of range files named `{PREFIX}.txt`, as written by the HIBP downloader
+   Directory(PathBuf),
+   /// The base url of a local server implementing `GET /range/{PREFIX}`
+   Server(String),
+}
+
+impl RangeSource {
+   pub fn parse(input: &str) -> Result<Self, String> {
+      let input = input.trim();
+      if input.is_empty() {
+         return Err(String::from(
+            "Choose a folder of range files or a local server url",
+         ));
+      }
+
+      if input.starts_with("https://") {
+         return Err(String::from(
+            "Only plain http servers on your own machine or network are supported",
+         ));
+      }
+
+      if input.starts_with("http://") {
+         return Ok(Self::Server(
+            input.trim_end_matches('/').to_string(),
+         ));
+      }
+
+      let path = PathBuf::from(input);
+      if !path.is_dir() {
+         return Err(format!("{} is not a folder", path.display()));
+      }
+
+      Ok(Self::Directory(path))
+   }
+
+   /// The range for this 5 characters hash prefix, one `SUFFIX:COUNT` per line
+   pub fn fetch_range(&self, prefix: &str) -> Result<String, Box<dyn std::error::Error>> {
+      match self {
+         Self::Directory(dir) => {
+            let path = dir.join(format!("{}.txt", prefix));
+            std::fs::read_to_string(&path)
+               .map_err(|e| format!("Failed to read {}: {}", path.display(), e).into())
+         }
+         Self::Server(url) => http_get(&format!("{}/range/{}", url, prefix)),
+      }
+   }
+
+   /// How many times this password appears in the dataset, 0 if never
+   pub fn times_pwned(&self, password: &SecureString) -> Result<u64, Box<dyn std::error::Error>> {
+      let hash = password.unlock_str(sha1_hex);
+      let (prefix, suffix) = hash.split_at(5);
+      let range = self.fetch_range(prefix)?;
+      Ok(count_in_range(&range, suffix))
+   }
+}
+
+/// Uppercase hex SHA-1, as used by the Pwned Passwords dataset
+pub fn sha1_hex(password: &str) -> String {
+   hex::encode_upper(Sha1::digest(password.as_bytes()))
+}
+
+/// Find the hash suffix in a range, padding entries have a count of 0
+pub fn count_in_range(range: &str, suffix: &str) -> u64 {
+   range
+      .lines()
+      .filter_map(|line| line.trim().split_once(':'))
+      .find(|(s, _)| s.eq_ignore_ascii_case(suffix))
+      .and_then(|(_, count)| count.trim().parse().ok())
+      .unwrap_or(0)
+}
+
+/// Minimal HTTP/1.0 GET, enough for a local range server
+fn http_get(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+   let rest = url.strip_prefix("http://").ok_or("Only http urls are supported")?;
+   let (host, path) = match rest.find('/') {
+      Some(i) => (&rest[..i], &rest[i..]),
+      None => (rest, "/"),
+   };
+   let address = match host.contains(':') {
+      true => host.to_string(),
+      false => format!("{}:80", host),
+   };
+
+   let mut stream = TcpStream::connect(&address)?;
+   stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+   stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+
+   let request = format!(
+      "GET {} HTTP/1.0\r\nHost: {}\r\nAdd-Padding: true\r\n\r\n",
+      path, host
+   );
+   stream.write_all(request.as_bytes())?;
+
+   let mut response = Vec::new();
+   stream.read_to_end(&mut response)?;
+   let response = String::from_utf8(response)?;
+
+   let (head, body) =
+      response.split_once("\r\n\r\n").ok_or("Malformed response from the server")?;
+   let status = head.lines().next().unwrap_or_default();
+   if status.split_whitespace().nth(1) != Some("200") {
+      return Err(format!("The server replied {}", status).into());
+   }
+
+   Ok(body.to_string())
+}
+
+/// Entries found in the dataset
+#[derive(Clone, Default)]
+pub struct BreachReport {
+   pub checked: usize,
+   /// Index, title and how many times the password was seen
+   pub breached: Vec<(u32, String, u64)>,
+   /// Index, title and why the entry could not be checked
+   pub failed: Vec<(u32, String, String)>,
+   /// The app was locked before every entry was checked
+   pub locked: bool,
+}
+
+/// Check the password of every entry and mark the breached ones as exposed
+///
+/// An entry whose range can't be read is reported in [BreachReport::failed] and the check
+/// goes on with the next one. If the app locks meanwhile the check stops, see
+/// [BreachReport::locked].
+pub fn check_all(
+   app: AppCtx,
+   source: &RangeSource,
+) -> Result<BreachReport, Box<dyn std::error::Error>> {
+   let mut entries: Vec<(u32, String)> =
+      app.read(|app| app.index_map.iter().map(|(i, data)| (*i, data.title.clone())).collect());
+   entries.sort_by_key(|(index, _)| *index);
+
+   let mut report = BreachReport::default();
+   for (index, title) in entries {
+      let Ok(mut password) = app.derive_at(index) else {
+         report.locked = true;
+         break;
+      };
+      let res = source.times_pwned(&password);
+      password.erase();
+
+      match res {
+         Ok(count) => {
+            report.checked += 1;
+            if count > 0 {
+               report.breached.push((index, title, count));
+            }
+         }
+         Err(e) => report.failed.push((index, title, e.to_string())),
+      }
+   }
+
+   let newly_exposed = app.write(|app| {
+      let mut changed = false;
+      for (index, _, _) in &report.breached {
+         if let Some(data) = app.index_map.get_mut(index)
+            && !data.exposed
+         {
+            data.exposed = true;
+            changed = true;
+         }
+      }
+      changed
+   });
+
+   if newly_exposed {
+      app.save_index_map_to_file()?;
+   }
+
+   Ok(report)
+}
+
+/// Runs [check_all] against a range source chosen by the user
+pub struct BreachWindow {
+   open: bool,
+   source: String,
+   checking: bool,
+   report: Option<BreachReport>,
+   error: Option<String>,
+}
+
+impl BreachWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         source: String::new(),
+         checking: false,
+         report: None,
+         error: None,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+      self.error = None;
+   }
+
+   /// Called from the background thread once the check is over
+   pub fn finish(&mut self, res: Result<BreachReport, String>) {
+      self.checking = false;
+      match res {
+         Ok(report) => self.report = Some(report),
+         Err(e) => self.error = Some(e),
+      }
+   }
+
+   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      if !self.open {
+         return;
+      }
+
+      Window::new("Breach check")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(400.0);
+
+               let text = RichText::new("Breach check").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               let text = "Looks up your passwords in a downloaded copy of the Have I Been Pwned \
+               Pwned Passwords dataset, or a server on your network that serves it. \
+               Breached entries are marked as exposed.";
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               let text_edit = TextEdit::singleline(&mut self.source)
+                  .font(FontId::proportional(theme.text_sizes.normal))
+                  .desired_width(ui.available_width() * 0.9)
+                  .hint_text("Folder of range files or http://127.0.0.1:8000");
+               ui.add(text_edit);
+
+               if let Some(error) = &self.error {
+                  let text = RichText::new(error).size(theme.text_sizes.normal);
+                  ui.label(text.color(theme.colors.error));
+               }
+
+               if self.checking {
+                  ui.spinner();
+               }
+
+               if let Some(report) = &self.report {
+                  self.show_report(report, theme, ui);
+               }
+
+               ui.horizontal(|ui| {
+                  ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+                  let text = RichText::new("Check").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add_enabled(!self.checking, button).clicked() {
+                     self.start(app.clone());
+                  }
+
+                  let text = RichText::new("Close").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     self.open = false;
+                  }
+               });
+            });
+         });
+   }
+
+   fn start(&mut self, app: AppCtx) {
+      let source = match RangeSource::parse(&self.source) {
+         Ok(source) => source,
+         Err(e) => {
+            self.error = Some(e);
+            return;
+         }
+      };
+
+      self.checking = true;
+      self.error = None;
+      self.report = None;
+
+      std::thread::spawn(move || {
+         let res = check_all(app, &source).map_err(|e| e.to_string());
+         SHARED_GUI.write(|gui| {
+            gui.home.breach.finish(res);
+            gui.request_repaint();
+         });
+      });
+   }
+
+   fn show_report(&self, report: &BreachReport, theme: &Theme, ui: &mut Ui) {
+      let text = format!(
+         "Checked {} entries, {} found in breaches",
+         report.checked,
+         report.breached.len()
+      );
+      let color = match report.breached.is_empty() {
+         true => theme.colors.success,
+         false => theme.colors.error,
+      };
+      ui.label(RichText::new(text).size(theme.text_sizes.normal).color(color));
+
+      if report.locked {
+         let text = "The app was locked, the check stopped before the last entries";
+         let text = RichText::new(text).size(theme.text_sizes.normal);
+         ui.label(text.color(theme.colors.warning));
+      }
+
+      if !report.failed.is_empty() {
+         let text = format!(
+            "{} entries could not be checked",
+            report.failed.len()
+         );
+         let text = RichText::new(text).size(theme.text_sizes.normal);
+         ui.label(text.color(theme.colors.warning));
+      }
+
+      ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+         for (index, title, count) in &report.breached {
+            let text = format!("{}. {} (seen {} times)", index, title, count);
+            ui.label(RichText::new(text).size(theme.text_sizes.small));
+         }
+
+         for (index, title, error) in &report.failed {
+            let text = format!("{}. {} ({})", index, title, error);
+            let text = RichText::new(text).size(theme.text_sizes.small);
+            ui.label(text.color(theme.colors.warning));
+         }
+      });
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::gui::app::IndexData;
+   use argon2_rs::Argon2;
+   use passwd_derive::{PasswordDeriver, random_seed};
+   use std::net::TcpListener;
+
+   // SHA-1 of "password" is 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+   const RANGE: &str = "1D2DA4053E34E76F6576ED1DA63134B5E2A:2\r\n\
+      1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n\
+      1E51E7B5D0D3B9C9FE3B0E52A86A3C37A44:0\r\n";
+
+   #[test]
+   fn test_directory_source() {
+      assert_eq!(
+         sha1_hex("password"),
+         "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+      );
+
+      let dir = std::env::temp_dir().join(format!("hibp-test-{}", std::process::id()));
+      std::fs::create_dir_all(&dir).unwrap();
+      std::fs::write(dir.join("5BAA6.txt"), RANGE).unwrap();
+
+      let source = RangeSource::parse(dir.to_str().unwrap()).unwrap();
+      let pwned = source.times_pwned(&SecureString::from("password")).unwrap();
+      assert_eq!(pwned, 9_659_365);
+
+      // Padding entries don't count
+      assert_eq!(
+         count_in_range(RANGE, "1E51E7B5D0D3B9C9FE3B0E52A86A3C37A44"),
+         0
+      );
+      assert!(source.times_pwned(&SecureString::from("not in the fixture")).is_err());
+
+      std::fs::remove_dir_all(&dir).unwrap();
+   }
+
+   #[test]
+   fn test_server_source() {
+      let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+      let url = format!("http://{}", listener.local_addr().unwrap());
+
+      let server = std::thread::spawn(move || {
+         let (mut stream, _) = listener.accept().unwrap();
+         let mut request = [0u8; 1024];
+         let n = stream.read(&mut request).unwrap();
+         let request = String::from_utf8_lossy(&request[..n]).to_string();
+
+         let response = format!("HTTP/1.0 200 OK\r\n\r\n{}", RANGE);
+         stream.write_all(response.as_bytes()).unwrap();
+         request
+      });
+
+      let source = RangeSource::parse(&url).unwrap();
+      let pwned = source.times_pwned(&SecureString::from("password")).unwrap();
+      assert_eq!(pwned, 9_659_365);
+
+      let request = server.join().unwrap();
+      assert!(request.starts_with("GET /range/5BAA6 "));
+   }
+
+   #[test]
+   fn test_check_all_continues_past_failures() {
+      let dir = std::env::temp_dir().join(format!("hibp-check-all-{}", std::process::id()));
+      std::fs::create_dir_all(&dir).unwrap();
+
+      let app = AppCtx::default();
+      let deriver = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      app.write(|app| {
+         app.passwd_derive = Some(deriver);
+         app.data_file = dir.join("NoPassPlz.json");
+         app.index_map.insert(0, IndexData::titled("Mail"));
+         app.index_map.insert(1, IndexData::titled("Bank"));
+      });
+
+      // Only the range of the second entry is there
+      let hash = app.derive_at(1).unwrap().unlock_str(sha1_hex);
+      let (prefix, suffix) = hash.split_at(5);
+      std::fs::write(
+         dir.join(format!("{}.txt", prefix)),
+         format!("{}:3\r\n", suffix),
+      )
+      .unwrap();
+
+      let source = RangeSource::Directory(dir.clone());
+      let report = check_all(app.clone(), &source).unwrap();
+      assert_eq!(report.checked, 1);
+      assert_eq!(
+         report.breached,
+         vec![(1, String::from("Bank"), 3)]
+      );
+      assert_eq!(report.failed.len(), 1);
+      assert_eq!(report.failed[0].0, 0);
+      assert!(app.get_index(1).unwrap().exposed);
+
+      // Locking stops the check without an error
+      app.write(|app| app.passwd_derive = None);
+      let report = check_all(app.clone(), &source).unwrap();
+      assert!(report.locked);
+      assert_eq!(report.checked, 0);
+
+      std::fs::remove_dir_all(&dir).unwrap();
+   }
+}
@@ -0,0 +1,130 @@
+use arboard::Clipboard;
+use eframe::egui::Context;
+use secure_types::{SecureString, Zeroize};
+use std::time::{Duration, Instant};
+
+/// Clipboard timeouts in seconds the user can choose from, 0 means never clear
+pub const CLEAR_TIMEOUTS: [u64; 6] = [0, 10, 20, 30, 60, 120];
+
+/// Copies passwords to the system clipboard and clears them after a timeout
+///
+/// The copied text is flagged as sensitive so clipboard managers don't keep it in their history
+/// (`x-kde-passwordManagerHint` on X11/Wayland, the equivalent flags on Windows and macOS).
+pub struct SecureClipboard {
+   /// `None` if the system clipboard is not available, in that case we go through egui
+   clipboard: Option<Clipboard>,
+   /// What we put on the clipboard, so we only clear it if it's still ours
+   copied: Option<SecureString>,
+   copied_at: Option<Instant>,
+   pub timeout: Duration,
+}
+
+impl SecureClipboard {
+   pub fn new() -> Self {
+      let clipboard = match Clipboard::new() {
+         Ok(clipboard) => Some(clipboard),
+         Err(e) => {
+            eprintln!("Failed to access the system clipboard {}", e);
+            None
+         }
+      };
+
+      Self {
+         clipboard,
+         copied: None,
+         copied_at: None,
+         timeout: Duration::from_secs(20),
+      }
+   }
+
+   /// Copy this sensitive text to the clipboard
+   pub fn copy(&mut self, ctx: &Context, text: SecureString) {
+      let copied = match &mut self.clipboard {
+         Some(clipboard) => text.unlock_str(|s| set_sensitive_text(clipboard, s)),
+         None => false,
+      };
+
+      // Fallback, the text is still cleared after the timeout
+      if !copied {
+         text.unlock_str(|s| ctx.copy_text(s.to_string()));
+      }
+
+      self.copied = Some(text);
+      self.copied_at = Some(Instant::now());
+   }
+
+   /// Time left until the clipboard is cleared
+   pub fn remaining(&self) -> Option<Duration> {
+      if self.timeout.is_zero() {
+         return None;
+      }
+
+      let copied_at = self.copied_at?;
+      Some(self.timeout.saturating_sub(copied_at.elapsed()))
+   }
+
+   /// Clear the clipboard if the timeout has passed, call this every frame
+   pub fn tick(&mut self, ctx: &Context) {
+      let Some(remaining) = self.remaining() else {
+         return;
+      };
+
+      if remaining.is_zero() {
+         self.clear(ctx);
+      } else {
+         ctx.request_repaint_after(Duration::from_secs(1).min(remaining));
+      }
+   }
+
+   /// Clear the clipboard now if it still holds the text we copied
+   pub fn clear(&mut self, ctx: &Context) {
+      let Some(mut copied) = self.copied.take() else {
+         return;
+      };
+      self.copied_at = None;
+
+      match &mut self.clipboard {
+         Some(clipboard) => {
+            let still_ours = match clipboard.get_text() {
+               Ok(mut current) => {
+                  let ours = copied.unlock_str(|s| s == current);
+                  current.zeroize();
+                  ours
+               }
+               Err(_) => false,
+            };
+
+            if still_ours && let Err(e) = clipboard.clear() {
+               eprintln!("Failed to clear the clipboard {}", e);
+            }
+         }
+         // We can't read the clipboard through egui, so overwrite it anyway
+         None => ctx.copy_text(String::new()),
+      }
+
+      copied.erase();
+   }
+}
+
+#[cfg(target_os = "linux")]
+fn set_sensitive_text(clipboard: &mut Clipboard, text: &str) -> bool {
+   use arboard::SetExtLinux;
+   clipboard.set().exclude_from_history().text(text).is_ok()
+}
+
+#[cfg(target_os = "windows")]
+fn set_sensitive_text(clipboard: &mut Clipboard, text: &str) -> bool {
+   use arboard::SetExtWindows;
+   clipboard.set().exclude_from_history().exclude_from_cloud().text(text).is_ok()
+}
+
+#[cfg(target_os = "macos")]
+fn set_sensitive_text(clipboard: &mut Clipboard, text: &str) -> bool {
+   use arboard::SetExtApple;
+   clipboard.set().exclude_from_history().text(text).is_ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn set_sensitive_text(clipboard: &mut Clipboard, text: &str) -> bool {
+   clipboard.set_text(text).is_ok()
+}
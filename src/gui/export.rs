@@ -0,0 +1,689 @@
+use super::{
+   AppCtx, SHARED_GUI,
+   app::{IndexData, format_timestamp, unix_now},
+   misc::SecretBuf,
+};
+use eframe::egui::{Align2, Button, ComboBox, FontId, RichText, TextEdit, Ui, Window, vec2};
+use secure_types::SecureString;
+use serde::{Serialize, Serializer};
+use std::{borrow::Cow, io::Write, path::PathBuf};
+use zeus_theme::Theme;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+   BitwardenJson,
+   KeePassCsv,
+   Html,
+   Markdown,
+}
+
+impl ExportFormat {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         ExportFormat::BitwardenJson => "Bitwarden (JSON)",
+         ExportFormat::KeePassCsv => "KeePass (CSV)",
+         ExportFormat::Html => "Emergency sheet (HTML)",
+         ExportFormat::Markdown => "Emergency sheet (Markdown)",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![
+         Self::BitwardenJson,
+         Self::KeePassCsv,
+         Self::Html,
+         Self::Markdown,
+      ]
+   }
+
+   pub fn extension(self) -> &'static str {
+      match self {
+         ExportFormat::BitwardenJson => "json",
+         ExportFormat::KeePassCsv => "csv",
+         ExportFormat::Html => "html",
+         ExportFormat::Markdown => "md",
+      }
+   }
+}
+
+/// An entry as written to the export
+pub struct ExportEntry {
+   pub index: u32,
+   pub title: String,
+   pub username: String,
+   pub urls: Vec<String>,
+   pub notes: String,
+   pub tags: Vec<String>,
+   pub custom_fields: Vec<(String, String)>,
+   /// Only set when the user asked for the derived passwords
+   pub password: Option<SecureString>,
+}
+
+impl ExportEntry {
+   pub fn new(index: u32, data: &IndexData) -> Self {
+      Self {
+         index,
+         title: data.title.clone(),
+         username: data.username.clone(),
+         urls: data.urls.clone(),
+         notes: data.description.clone(),
+         tags: data.tags.clone(),
+         custom_fields: data
+            .custom_fields
+            .iter()
+            .map(|f| (f.key.clone(), f.value.clone()))
+            .collect(),
+         password: None,
+      }
+   }
+}
+
+/// Collect the entries by index, deriving their passwords if asked to
+pub fn collect_entries(
+   app: AppCtx,
+   include_passwords: bool,
+) -> Result<Vec<ExportEntry>, Box<dyn std::error::Error>> {
+   let mut entries: Vec<ExportEntry> = app.read(|app| {
+      app.index_map
+         .iter()
+         .map(|(index, data)| ExportEntry::new(*index, data))
+         .collect()
+   });
+   entries.sort_by_key(|entry| entry.index);
+
+   if include_passwords {
+      for entry in entries.iter_mut() {
+         entry.password = Some(app.derive_at(entry.index)?);
+      }
+   }
+
+   Ok(entries)
+}
+
+/// Renders the export straight into a [SecretBuf], the passwords are never copied elsewhere
+pub fn render(
+   format: ExportFormat,
+   entries: &[ExportEntry],
+) -> Result<SecretBuf, Box<dyn std::error::Error>> {
+   match format {
+      ExportFormat::BitwardenJson => bitwarden_json(entries),
+      ExportFormat::KeePassCsv => keepass_csv(entries),
+      ExportFormat::Html => html_sheet(entries, unix_now()),
+      ExportFormat::Markdown => markdown_sheet(entries, unix_now()),
+   }
+}
+
+/// Serializes the password from its locked memory into the output
+struct Password<'a>(&'a SecureString);
+
+impl Serialize for Password<'_> {
+   fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+      self.0.unlock_str(|password| serializer.serialize_str(password))
+   }
+}
+
+#[derive(Serialize)]
+struct BitwardenExport<'a> {
+   encrypted: bool,
+   folders: Vec<BitwardenFolder<'a>>,
+   items: Vec<BitwardenItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct BitwardenFolder<'a> {
+   id: String,
+   name: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem<'a> {
+   #[serde(rename = "type")]
+   kind: u8,
+   name: &'a str,
+   notes: Option<&'a str>,
+   favorite: bool,
+   folder_id: Option<String>,
+   fields: Vec<BitwardenField<'a>>,
+   login: BitwardenLogin<'a>,
+}
+
+#[derive(Serialize)]
+struct BitwardenField<'a> {
+   name: &'a str,
+   value: Cow<'a, str>,
+   #[serde(rename = "type")]
+   kind: u8,
+}
+
+#[derive(Serialize)]
+struct BitwardenLogin<'a> {
+   username: &'a str,
+   password: Option<Password<'a>>,
+   uris: Vec<BitwardenUri<'a>>,
+   totp: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct BitwardenUri<'a> {
+   #[serde(rename = "match")]
+   kind: Option<u8>,
+   uri: &'a str,
+}
+
+/// Unencrypted Bitwarden export, the first tag of an entry becomes its folder
+pub fn bitwarden_json(entries: &[ExportEntry]) -> Result<SecretBuf, Box<dyn std::error::Error>> {
+   let mut folders: Vec<&str> =
+      entries.iter().filter_map(|e| e.tags.first().map(|t| t.as_str())).collect();
+   folders.sort();
+   folders.dedup();
+
+   let items = entries
+      .iter()
+      .map(|entry| {
+         let folder_id = entry
+            .tags
+            .first()
+            .and_then(|tag| folders.iter().position(|f| f == tag))
+            .map(|i| format!("folder-{}", i));
+
+         let mut fields = vec![BitwardenField {
+            name: "Index",
+            value: Cow::Owned(entry.index.to_string()),
+            kind: 0,
+         }];
+         for (key, value) in &entry.custom_fields {
+            fields.push(BitwardenField {
+               name: key,
+               value: Cow::Borrowed(value),
+               kind: 0,
+            });
+         }
+
+         BitwardenItem {
+            kind: 1,
+            name: &entry.title,
+            notes: Some(entry.notes.as_str()).filter(|notes| !notes.is_empty()),
+            favorite: false,
+            folder_id,
+            fields,
+            login: BitwardenLogin {
+               username: &entry.username,
+               password: entry.password.as_ref().map(Password),
+               uris: entry.urls.iter().map(|uri| BitwardenUri { kind: None, uri }).collect(),
+               totp: None,
+            },
+         }
+      })
+      .collect();
+
+   let folders = folders
+      .iter()
+      .enumerate()
+      .map(|(i, name)| BitwardenFolder {
+         id: format!("folder-{}", i),
+         name,
+      })
+      .collect();
+
+   let export = BitwardenExport {
+      encrypted: false,
+      folders,
+      items,
+   };
+
+   let mut output = SecretBuf::new();
+   serde_json::to_writer_pretty(&mut output, &export)?;
+   Ok(output)
+}
+
+/// Write `text` with the characters `escape` replaces swapped for their escape sequence
+fn push_escaped(output: &mut SecretBuf, text: &str, escape: fn(char) -> Option<&'static str>) {
+   let mut start = 0;
+   for (i, c) in text.char_indices() {
+      if let Some(escaped) = escape(c) {
+         output.push_str(&text[start..i]);
+         output.push_str(escaped);
+         start = i + c.len_utf8();
+      }
+   }
+   output.push_str(&text[start..]);
+}
+
+fn escape_csv(c: char) -> Option<&'static str> {
+   (c == '"').then_some("\"\"")
+}
+
+/// Line breaks become `<br>` since the text goes into table cells
+fn escape_html(c: char) -> Option<&'static str> {
+   match c {
+      '&' => Some("&amp;"),
+      '<' => Some("&lt;"),
+      '>' => Some("&gt;"),
+      '"' => Some("&quot;"),
+      '\'' => Some("&#39;"),
+      '\n' => Some("<br>"),
+      _ => None,
+   }
+}
+
+fn escape_markdown(c: char) -> Option<&'static str> {
+   match c {
+      '\\' => Some("\\\\"),
+      '|' => Some("\\|"),
+      // Markdown renders inline HTML, a title like `Mail <work>` would vanish as a tag
+      '&' => Some("&amp;"),
+      '<' => Some("&lt;"),
+      '>' => Some("&gt;"),
+      '\n' => Some("<br>"),
+      _ => None,
+   }
+}
+
+fn push_csv_record(output: &mut SecretBuf, fields: &[&str]) {
+   for (i, field) in fields.iter().enumerate() {
+      if i > 0 {
+         output.push_str(",");
+      }
+      output.push_str("\"");
+      push_escaped(output, field, escape_csv);
+      output.push_str("\"");
+   }
+   output.push_str("\n");
+}
+
+/// The CSV layout KeePassXC imports by default, every field quoted
+pub fn keepass_csv(entries: &[ExportEntry]) -> Result<SecretBuf, Box<dyn std::error::Error>> {
+   let mut output = SecretBuf::new();
+   push_csv_record(
+      &mut output,
+      &["Group", "Title", "Username", "Password", "URL", "Notes"],
+   );
+
+   for entry in entries {
+      let notes = match entry.notes.is_empty() {
+         true => format!("NoPassPlz index {}", entry.index),
+         false => format!("{}\nNoPassPlz index {}", entry.notes, entry.index),
+      };
+
+      let mut record = |password: &str| {
+         let fields = [
+            entry.tags.first().map_or("Root", |t| t.as_str()),
+            &entry.title,
+            &entry.username,
+            password,
+            entry.urls.first().map_or("", |u| u.as_str()),
+            &notes,
+         ];
+         push_csv_record(&mut output, &fields);
+      };
+      match &entry.password {
+         Some(password) => password.unlock_str(&mut record),
+         None => record(""),
+      }
+   }
+
+   Ok(output)
+}
+
+/// A printable page meant to be kept somewhere safe for emergency access
+pub fn html_sheet(
+   entries: &[ExportEntry],
+   now: u64,
+) -> Result<SecretBuf, Box<dyn std::error::Error>> {
+   let with_passwords = entries.iter().any(|e| e.password.is_some());
+
+   let mut html = SecretBuf::new();
+   html.push_str(
+      "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+      <title>NoPassPlz emergency sheet</title>\n<style>\n\
+      body { font-family: sans-serif; }\n\
+      table { border-collapse: collapse; width: 100%; }\n\
+      th, td { border: 1px solid #888; padding: 4px 6px; text-align: left; vertical-align: top; }\n\
+      td.password { font-family: monospace; word-break: break-all; }\n\
+      </style>\n</head>\n<body>\n<h1>NoPassPlz emergency sheet</h1>\n",
+   );
+
+   html.push_str("<p>Printed on ");
+   push_escaped(&mut html, &format_timestamp(now), escape_html);
+   html.push_str(
+      ". Every password can be derived again from the master credentials and the index.</p>\n",
+   );
+
+   html.push_str(
+      "<table>\n<tr><th>Index</th><th>Title</th><th>Username</th><th>URL</th><th>Notes</th>",
+   );
+   if with_passwords {
+      html.push_str("<th>Password</th>");
+   }
+   html.push_str("</tr>\n");
+
+   for entry in entries {
+      write!(html, "<tr><td>{}</td>", entry.index)?;
+      for text in [
+         &entry.title,
+         &entry.username,
+         &entry.urls.join("\n"),
+         &entry.notes,
+      ] {
+         html.push_str("<td>");
+         push_escaped(&mut html, text, escape_html);
+         html.push_str("</td>");
+      }
+      if with_passwords {
+         html.push_str("<td class=\"password\">");
+         if let Some(password) = &entry.password {
+            password.unlock_str(|password| push_escaped(&mut html, password, escape_html));
+         }
+         html.push_str("</td>");
+      }
+      html.push_str("</tr>\n");
+   }
+
+   html.push_str("</table>\n</body>\n</html>\n");
+   Ok(html)
+}
+
+/// The same sheet as [html_sheet] for people who keep their notes in Markdown
+pub fn markdown_sheet(
+   entries: &[ExportEntry],
+   now: u64,
+) -> Result<SecretBuf, Box<dyn std::error::Error>> {
+   let with_passwords = entries.iter().any(|e| e.password.is_some());
+
+   let mut md = SecretBuf::new();
+   write!(
+      md,
+      "# NoPassPlz emergency sheet\n\nPrinted on {}. Every password can be derived again \
+      from the master credentials and the index.\n\n",
+      format_timestamp(now)
+   )?;
+
+   match with_passwords {
+      true => md.push_str(
+         "| Index | Title | Username | URL | Notes | Password |\n|---|---|---|---|---|---|\n",
+      ),
+      false => md.push_str("| Index | Title | Username | URL | Notes |\n|---|---|---|---|---|\n"),
+   }
+
+   for entry in entries {
+      write!(md, "| {} |", entry.index)?;
+      for text in [
+         &entry.title,
+         &entry.username,
+         &entry.urls.join("\n"),
+         &entry.notes,
+      ] {
+         md.push_str(" ");
+         push_escaped(&mut md, text, escape_markdown);
+         md.push_str(" |");
+      }
+      if with_passwords {
+         md.push_str(" `");
+         if let Some(password) = &entry.password {
+            password.unlock_str(|password| md.push_str(password));
+         }
+         md.push_str("` |");
+      }
+      md.push_str("\n");
+   }
+
+   Ok(md)
+}
+
+/// Write the export, readable by the current user only, and erase the derived passwords
+/// from memory
+pub fn export_to_file(
+   app: AppCtx,
+   format: ExportFormat,
+   include_passwords: bool,
+   path: PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+   let mut entries = collect_entries(app, include_passwords)?;
+   let res = render(format, &entries);
+
+   for entry in entries.iter_mut() {
+      if let Some(password) = entry.password.as_mut() {
+         password.erase();
+      }
+   }
+
+   let output = res?;
+   output
+      .write_private(&path)
+      .map_err(|e| format!("Failed to write {}: {}", path.display(), e).into())
+}
+
+/// Pick a format and a file, optionally with the derived passwords
+pub struct ExportWindow {
+   open: bool,
+   format: ExportFormat,
+   path: String,
+   include_passwords: bool,
+   /// The user confirmed the file will hold their passwords in plain text
+   confirmed: bool,
+   exporting: bool,
+}
+
+impl ExportWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         format: ExportFormat::BitwardenJson,
+         path: String::new(),
+         include_passwords: false,
+         confirmed: false,
+         exporting: false,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+      self.include_passwords = false;
+      self.confirmed = false;
+      if self.path.is_empty() {
+         self.path = format!("NoPassPlz-export.{}", self.format.extension());
+      }
+   }
+
+   /// Called from the background thread once the file is written
+   pub fn finish(&mut self) {
+      self.exporting = false;
+      self.include_passwords = false;
+      self.confirmed = false;
+   }
+
+   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      if !self.open {
+         return;
+      }
+
+      Window::new("Export")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(400.0);
+
+               let text = RichText::new("Export").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               let text = "Write your entries to another password manager's format or to a \
+               printable emergency sheet. The index of each entry is kept so its password \
+               can always be derived again.";
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               let previous = self.format;
+               ComboBox::from_id_salt("export_format")
+                  .selected_text(self.format.to_str())
+                  .show_ui(ui, |ui| {
+                     for format in ExportFormat::to_vec() {
+                        ui.selectable_value(&mut self.format, format, format.to_str());
+                     }
+                  });
+
+               // Keep the extension in line with the format
+               if previous != self.format
+                  && let Some(stem) = self.path.strip_suffix(previous.extension())
+               {
+                  self.path = format!("{}{}", stem, self.format.extension());
+               }
+
+               let text_edit = TextEdit::singleline(&mut self.path)
+                  .font(FontId::proportional(theme.text_sizes.normal))
+                  .desired_width(ui.available_width() * 0.9)
+                  .hint_text("Path of the export file");
+               ui.add(text_edit);
+
+               let text =
+                  RichText::new("Include the derived passwords").size(theme.text_sizes.normal);
+               if ui.checkbox(&mut self.include_passwords, text).changed() {
+                  self.confirmed = false;
+               }
+
+               if self.include_passwords {
+                  let text = "The file will hold every password in plain text. Anyone who \
+                  reads it can log into your accounts, store it offline and delete it once \
+                  you are done.";
+                  let text = RichText::new(text).size(theme.text_sizes.small);
+                  ui.label(text.color(theme.colors.error));
+
+                  let text = RichText::new("I understand").size(theme.text_sizes.normal);
+                  ui.checkbox(&mut self.confirmed, text);
+               }
+
+               if self.exporting {
+                  ui.spinner();
+               }
+
+               ui.horizontal(|ui| {
+                  ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+                  let ready = !self.exporting
+                     && !self.path.trim().is_empty()
+                     && (!self.include_passwords || self.confirmed);
+
+                  let text = RichText::new("Export").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add_enabled(ready, button).clicked() {
+                     self.start(app.clone());
+                  }
+
+                  let text = RichText::new("Close").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     self.open = false;
+                     self.include_passwords = false;
+                     self.confirmed = false;
+                  }
+               });
+            });
+         });
+   }
+
+   fn start(&mut self, app: AppCtx) {
+      self.exporting = true;
+      let format = self.format;
+      let include_passwords = self.include_passwords;
+      let path = PathBuf::from(self.path.trim());
+
+      std::thread::spawn(move || {
+         let res = export_to_file(app, format, include_passwords, path.clone());
+         SHARED_GUI.write(|gui| {
+            gui.home.export.finish();
+            match res {
+               Ok(()) => gui.msg_window.open(
+                  "Success",
+                  format!("Exported to {}", path.display()),
+               ),
+               Err(e) => gui.msg_window.open("Error", e.to_string()),
+            }
+            gui.request_repaint();
+         });
+      });
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::gui::import::parse_bitwarden_json;
+
+   #[test]
+   fn test_bitwarden_json_round_trip() {
+      let data = IndexData::titled("Mail <work>")
+         .with_username("alice")
+         .with_url("https://mail.example.com")
+         .with_notes("a|b")
+         .with_tags(&["Work"])
+         .with_custom_field("PIN hint", "birthday");
+      let mut entry = ExportEntry::new(3, &data);
+      entry.password = Some(SecureString::from("0a1b2c"));
+
+      let json = bitwarden_json(&[entry]).unwrap();
+      let table = parse_bitwarden_json(json.as_str()).unwrap();
+      assert_eq!(
+         table.rows[0],
+         vec![
+            "Mail <work>",
+            "alice",
+            "https://mail.example.com",
+            "a|b",
+            "Work"
+         ]
+      );
+
+      let value: serde_json::Value = serde_json::from_str(json.as_str()).unwrap();
+      assert_eq!(value["items"][0]["login"]["password"], "0a1b2c");
+      assert_eq!(value["items"][0]["fields"][0]["value"], "3");
+   }
+
+   #[test]
+   fn test_sheets_without_passwords() {
+      let data = IndexData::titled("Mail <work>")
+         .with_username("alice")
+         .with_url("https://mail.example.com")
+         .with_notes("a|b")
+         .with_tags(&["Work"]);
+      let mut entries = [ExportEntry::new(3, &data)];
+
+      let csv = keepass_csv(&entries).unwrap();
+      assert!(csv.as_str().starts_with("\"Group\",\"Title\",\"Username\",\"Password\""));
+      assert!(csv.as_str().contains("\"Work\",\"Mail <work>\",\"alice\",\"\""));
+
+      let html = html_sheet(&entries, 0).unwrap();
+      assert!(html.as_str().contains("<td>Mail &lt;work&gt;</td>"));
+      assert!(!html.as_str().contains("Password"));
+
+      let md = markdown_sheet(&entries, 0).unwrap();
+      assert!(
+         md.as_str()
+            .contains("| 3 | Mail &lt;work&gt; | alice | https://mail.example.com | a\\|b |\n")
+      );
+
+      entries[0].password = Some(SecureString::from("0a1b2c"));
+      let md = markdown_sheet(&entries, 0).unwrap();
+      assert!(md.as_str().contains("`0a1b2c` |"));
+   }
+
+   #[cfg(unix)]
+   #[test]
+   fn test_export_file_is_private() {
+      use std::os::unix::fs::PermissionsExt;
+
+      let path = std::env::temp_dir().join(format!("export-test-{}.csv", std::process::id()));
+      std::fs::write(&path, "old").unwrap();
+
+      let entries = [ExportEntry::new(0, &IndexData::titled("Mail"))];
+      keepass_csv(&entries).unwrap().write_private(&path).unwrap();
+
+      let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+      assert_eq!(mode & 0o777, 0o600);
+      assert!(std::fs::read_to_string(&path).unwrap().contains("\"Mail\""));
+      std::fs::remove_file(&path).unwrap();
+   }
+}
@@ -1,18 +1,70 @@
-use super::{AppCtx, SHARED_GUI, app::IndexData};
+use super::{
+   AppCtx, SHARED_GUI,
+   app::{CustomField, IndexData, format_timestamp, unix_now},
+   audit::{AuditAction, AuditWindow, output_format_text, review_and_save, rotate_and_save},
+   breach::BreachWindow,
+   clipboard::{CLEAR_TIMEOUTS, SecureClipboard},
+   export::ExportWindow,
+   import::ImportWindow,
+   kit::KitWindow,
+   merge::MergeWindow,
+   recovery::SharesWindow,
+   reveal::RevealWindow,
+   search::{EntryFilter, EntryQuery, SortOrder, all_tags},
+   settings::Settings,
+   shortcuts::{Action, Keymap},
+   sync::{SyncConfig, sync_in_background},
+   team::TeamWindow,
+   trash::{Trash, restore_and_save},
+};
 use eframe::egui::{
-   Align2, Button, FontId, RichText, ScrollArea, Stroke, TextEdit, Ui, Window, vec2,
+   Align2, Button, ComboBox, Context, FontId, Grid, Id, Key, Response, RichText, ScrollArea,
+   Stroke, TextEdit, Ui, Window, vec2,
 };
+use std::time::Duration;
 use zeus_theme::Theme;
 use zeus_widgets::{Label, MultiLabel};
 
+pub const PAGE_SIZES: [u32; 4] = [10, 25, 50, 100];
+
+const SEARCH_ID: &str = "home_search";
+
 /// Main Ui
 pub struct Home {
    open: bool,
    edit_window: bool,
    index_to_edit: u32,
+   /// Shown in the edit window when the index may have been used before
+   index_warning: Option<String>,
    edited_index: IndexData,
+   /// Comma separated tags of the entry being edited
+   edited_tags: String,
    current_page: u32,
    items_per_page: u32,
+   /// Show the entries as a table, one line per entry
+   compact: bool,
+   jump_to_index: String,
+   /// Browse at least this many indices, so a jumped to index past the used ones is listed
+   browse_at_least: u32,
+   query: EntryQuery,
+   /// Index waiting for the user to confirm its deletion
+   delete_confirm: Option<u32>,
+   /// Entry the keyboard shortcuts act on
+   selected: Option<u32>,
+   scroll_to_selected: bool,
+   trash: Trash,
+   pub breach: BreachWindow,
+   pub audit: AuditWindow,
+   pub import: ImportWindow,
+   pub export: ExportWindow,
+   merge: MergeWindow,
+   team: TeamWindow,
+   shares: SharesWindow,
+   pub kit: KitWindow,
+   clipboard: SecureClipboard,
+   reveal: RevealWindow,
+   /// None if no git remote is set, see [Settings::git_remote]
+   git_sync: Option<SyncConfig>,
 }
 
 impl Home {
@@ -21,9 +73,30 @@ impl Home {
          open: false,
          edit_window: false,
          index_to_edit: 0,
+         index_warning: None,
          edited_index: IndexData::default(),
+         edited_tags: String::new(),
          current_page: 0,
          items_per_page: 10,
+         compact: false,
+         jump_to_index: String::new(),
+         browse_at_least: 0,
+         query: EntryQuery::default(),
+         delete_confirm: None,
+         selected: None,
+         scroll_to_selected: false,
+         trash: Trash::new(),
+         breach: BreachWindow::new(),
+         audit: AuditWindow::new(),
+         import: ImportWindow::new(),
+         export: ExportWindow::new(),
+         merge: MergeWindow::new(),
+         team: TeamWindow::new(),
+         shares: SharesWindow::new(),
+         kit: KitWindow::new(),
+         clipboard: SecureClipboard::new(),
+         reveal: RevealWindow::new(),
+         git_sync: None,
       }
    }
 
@@ -31,53 +104,676 @@ impl Home {
       self.open = true;
    }
 
-   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+   /// Open the edit window for a new entry at the lowest unused index
+   pub fn new_entry(&mut self, app: AppCtx) {
+      let index = app.read(|app| app.lowest_free_index());
+      self.edit_entry(&app, index, IndexData::default());
+   }
+
+   /// Open the edit window, warning if the index may have belonged to a deleted entry
+   fn edit_entry(&mut self, app: &AppCtx, index: u32, data: IndexData) {
+      self.edit_window = true;
+      self.index_to_edit = index;
+      self.edited_tags = data.tags.join(", ");
+      self.edited_index = data;
+      self.index_warning = gap_warning(app, index);
+   }
+
+   pub fn show(&mut self, app: AppCtx, keymap: &Keymap, theme: &Theme, ui: &mut Ui) {
       if !self.open {
          return;
       }
 
       self.show_edit_window(app.clone(), theme, ui);
+      self.show_delete_window(app.clone(), theme, ui);
+      self.trash.show(app.clone(), theme, ui);
+      self.breach.show(app.clone(), theme, ui);
+      self.import.show(app.clone(), theme, ui);
+      self.export.show(app.clone(), theme, ui);
+      self.merge.show(app.clone(), theme, ui);
+      if self.team.is_open() {
+         let deriver = app.read(|app| app.passwd_derive.clone());
+         if let Some(password) = self.team.show(deriver, theme, ui) {
+            self.clipboard.copy(ui.ctx(), password);
+         }
+      }
+      if self.shares.is_open() {
+         let deriver = app.read(|app| app.passwd_derive.clone());
+         if let Some(share) = self.shares.show(deriver, theme, ui) {
+            self.kit.open(Some(share));
+         }
+      }
+      self.kit.show(app.clone(), theme, ui);
+      if let Some(action) = self.audit.show(app.clone(), theme, ui) {
+         self.handle_audit_action(app.clone(), action);
+      }
+      self.reveal.show(theme, ui);
+      self.clipboard.tick(ui.ctx());
 
       ui.vertical_centered(|ui| {
          ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
          ui.spacing_mut().button_padding = vec2(6.0, 6.0);
 
-         let items_per_page = self.items_per_page;
-         let start = self.current_page * items_per_page;
-         let end = start + items_per_page;
-
          ui.horizontal(|ui| {
-            ui.add_space(135.0);
-            ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
-            ui.spacing_mut().button_padding = vec2(4.0, 4.0);
-
-            let current_page_text = format!("Showing {}-{} entries", start, end);
-            let current_page_text = RichText::new(current_page_text).size(theme.text_sizes.large);
-            ui.label(current_page_text);
+            ui.add_space(ui.available_width() / 2.0 - 255.0);
 
-            let text = RichText::new("Prev").size(theme.text_sizes.normal);
-            let button = Button::new(text);
+            let text = RichText::new("New entry").size(theme.text_sizes.normal);
+            let button = Button::new(text).min_size(vec2(100.0, 25.0));
+            if ui.add(button).clicked() {
+               self.new_entry(app.clone());
+            }
 
-            if ui.add_enabled(self.current_page > 0, button).clicked() {
-               self.current_page -= 1;
+            let deleted = app.read(|app| app.tombstones.len());
+            let text = RichText::new(format!("Trash ({})", deleted)).size(theme.text_sizes.normal);
+            let button = Button::new(text).min_size(vec2(100.0, 25.0));
+            if ui.add(button).clicked() {
+               self.trash.open();
             }
 
-            let text = RichText::new("Next").size(theme.text_sizes.normal);
-            let button = Button::new(text);
+            let text = RichText::new("Breach check").size(theme.text_sizes.normal);
+            let button = Button::new(text).min_size(vec2(100.0, 25.0));
+            if ui.add(button).clicked() {
+               self.breach.open();
+            }
 
+            let text = RichText::new("Audit").size(theme.text_sizes.normal);
+            let button = Button::new(text).min_size(vec2(100.0, 25.0));
             if ui.add(button).clicked() {
-               self.current_page += 1;
+               self.audit.open();
             }
+
+            let text = RichText::new("Data").size(theme.text_sizes.normal);
+            ui.menu_button(text, |ui| {
+               ui.spacing_mut().button_padding = vec2(4.0, 4.0);
+
+               let text = RichText::new("Import").size(theme.text_sizes.normal);
+               if ui.button(text).clicked() {
+                  self.import.open();
+               }
+
+               let text = RichText::new("Export").size(theme.text_sizes.normal);
+               if ui.button(text).clicked() {
+                  self.export.open();
+               }
+
+               let text = RichText::new("Merge").size(theme.text_sizes.normal);
+               if ui.button(text).clicked() {
+                  self.merge.open();
+               }
+
+               let text = RichText::new("Sync with git").size(theme.text_sizes.normal);
+               let button = Button::new(text);
+               let res = ui.add_enabled(self.git_sync.is_some(), button);
+               if res.on_disabled_hover_text("Set a git remote in the settings").clicked()
+                  && let Some(config) = &self.git_sync
+               {
+                  sync_in_background(app.clone(), config.clone());
+               }
+
+               let text = RichText::new("Team vault").size(theme.text_sizes.normal);
+               if ui.button(text).clicked() {
+                  self.team.open();
+               }
+
+               let text = RichText::new("Recovery shares").size(theme.text_sizes.normal);
+               if ui.button(text).clicked() {
+                  self.shares.open();
+               }
+
+               let text = RichText::new("Recovery kit").size(theme.text_sizes.normal);
+               if ui.button(text).clicked() {
+                  self.kit.open(None);
+               }
+            });
          });
 
+         self.show_clipboard_status(theme, ui);
+         self.show_search_bar(app.clone(), theme, ui);
+
+         let items_per_page = self.items_per_page;
+         let start = self.current_page.saturating_mul(items_per_page);
+
+         // Entries to render and how many entries there are to page through, if known
+         let (rows, total): (Vec<(u32, Option<IndexData>)>, Option<u32>) =
+            if self.query.is_browsing() {
+               // Every index up to the first free one after the highest used
+               let total = app.read(|app| app.index_after_highest()).saturating_add(1);
+               let total = total.max(self.browse_at_least);
+               let end = start.saturating_add(items_per_page).min(total);
+               let rows = (start..end).map(|i| (i, app.get_index(i))).collect();
+               (rows, Some(total))
+            } else if self.query.filter == EntryFilter::Empty {
+               let rows = app.read(|app| {
+                  (0..u32::MAX)
+                     .filter(|i| app.is_free(*i))
+                     .skip(start as usize)
+                     .take(items_per_page as usize)
+                     .map(|i| (i, None))
+                     .collect()
+               });
+               (rows, None)
+            } else {
+               let matches = app.read(|app| self.query.apply(&app.index_map));
+               let total = matches.len() as u32;
+               let rows = matches
+                  .into_iter()
+                  .skip(start as usize)
+                  .take(items_per_page as usize)
+                  .map(|(i, data)| (i, Some(data)))
+                  .collect();
+               (rows, Some(total))
+            };
+
+         self.show_pagination(start, rows.len() as u32, total, theme, ui);
+
+         let has_next_page = match total {
+            Some(total) => start.saturating_add(items_per_page) < total,
+            None => rows.len() as u32 == items_per_page,
+         };
+         self.handle_shortcuts(app.clone(), keymap, &rows, has_next_page, ui);
+
          ScrollArea::vertical().show(ui, |ui| {
             ui.set_width(ui.available_width());
-            for i in start..end {
-               let index_data = app.get_index(i);
-               self.show_item(app.clone(), i, index_data, theme, ui);
+
+            if rows.is_empty() {
+               let text = RichText::new("No matching entries").size(theme.text_sizes.normal);
+               ui.label(text);
+            }
+
+            if self.compact {
+               self.show_table(app.clone(), rows, theme, ui);
+            } else {
+               for (i, index_data) in rows {
+                  self.show_item(app.clone(), i, index_data, theme, ui);
+               }
+            }
+         });
+      });
+   }
+
+   fn show_pagination(
+      &mut self,
+      start: u32,
+      shown: u32,
+      total: Option<u32>,
+      theme: &Theme,
+      ui: &mut Ui,
+   ) {
+      let browsing = self.query.is_browsing();
+
+      let range_text = if shown == 0 {
+         String::from("Nothing to show")
+      } else if browsing {
+         // Indices start at 0
+         let last = start + shown - 1;
+         format!(
+            "Indices {}-{} of {}",
+            start,
+            last,
+            total.unwrap_or(0)
+         )
+      } else {
+         let last = start + shown;
+         match total {
+            Some(total) => format!("Showing {}-{} of {}", start + 1, last, total),
+            None => format!("Showing {}-{}", start + 1, last),
+         }
+      };
+
+      ui.horizontal(|ui| {
+         ui.add_space(60.0);
+         ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+         ui.spacing_mut().button_padding = vec2(4.0, 4.0);
+
+         let text = RichText::new(range_text).size(theme.text_sizes.large);
+         ui.label(text);
+
+         let text = RichText::new("Prev").size(theme.text_sizes.normal);
+         let button = Button::new(text);
+
+         if ui.add_enabled(self.current_page > 0, button).clicked() {
+            self.current_page -= 1;
+         }
+
+         let text = RichText::new("Next").size(theme.text_sizes.normal);
+         let button = Button::new(text);
+         let end = start + shown;
+         let has_next = match total {
+            Some(total) => end < total,
+            None => shown == self.items_per_page,
+         };
+
+         if ui.add_enabled(has_next, button).clicked() {
+            self.current_page += 1;
+         }
+      });
+
+      ui.horizontal(|ui| {
+         ui.add_space(60.0);
+         ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+         ui.spacing_mut().button_padding = vec2(4.0, 4.0);
+
+         let text = RichText::new("Per page").size(theme.text_sizes.normal);
+         ui.label(text);
+
+         let mut items_per_page = self.items_per_page;
+         ComboBox::from_id_salt("items_per_page")
+            .selected_text(items_per_page.to_string())
+            .width(50.0)
+            .show_ui(ui, |ui| {
+               for size in PAGE_SIZES {
+                  ui.selectable_value(&mut items_per_page, size, size.to_string());
+               }
+            });
+
+         if items_per_page != self.items_per_page {
+            // Keep the first visible entry on screen
+            self.current_page = start / items_per_page;
+            self.items_per_page = items_per_page;
+         }
+
+         let text_edit = TextEdit::singleline(&mut self.jump_to_index)
+            .font(FontId::proportional(theme.text_sizes.normal))
+            .desired_width(60.0)
+            .hint_text("Index");
+         let res = ui.add(text_edit);
+
+         let text = RichText::new("Go").size(theme.text_sizes.normal);
+         let enter = res.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+         if ui.button(text).clicked() || enter {
+            match self.jump_to_index.trim().parse::<u32>() {
+               Ok(index) => self.jump_to(index),
+               Err(_) => {
+                  SHARED_GUI.write(|gui| {
+                     gui.msg_window.open("Error", "Index must be a positive number");
+                  });
+               }
+            }
+         }
+
+         let text = RichText::new("Compact").size(theme.text_sizes.normal);
+         ui.checkbox(&mut self.compact, text);
+      });
+   }
+
+   /// Act on the keyboard shortcuts, skipped while a dialog is open
+   fn handle_shortcuts(
+      &mut self,
+      app: AppCtx,
+      keymap: &Keymap,
+      rows: &[(u32, Option<IndexData>)],
+      has_next_page: bool,
+      ui: &Ui,
+   ) {
+      let ctx = ui.ctx();
+
+      // Keys typed into a text field are not shortcuts
+      if self.edit_window || self.delete_confirm.is_some() || ctx.wants_keyboard_input() {
+         return;
+      }
+
+      if keymap.pressed(ctx, Action::FocusSearch) {
+         ctx.memory_mut(|mem| mem.request_focus(Id::new(SEARCH_ID)));
+      }
+
+      if keymap.pressed(ctx, Action::NewEntry) {
+         self.new_entry(app.clone());
+         return;
+      }
+
+      let position = rows.iter().position(|(i, _)| Some(*i) == self.selected);
+
+      if keymap.pressed(ctx, Action::SelectNext) {
+         match position {
+            Some(pos) if pos + 1 < rows.len() => self.select(rows[pos + 1].0),
+            Some(_) if has_next_page => {
+               self.current_page += 1;
+               self.selected = None;
+            }
+            Some(_) => {}
+            None => {
+               if let Some((index, _)) = rows.first() {
+                  self.select(*index);
+               }
+            }
+         }
+      }
+
+      if keymap.pressed(ctx, Action::SelectPrevious) {
+         match position {
+            Some(pos) if pos > 0 => self.select(rows[pos - 1].0),
+            Some(_) if self.current_page > 0 => {
+               self.current_page -= 1;
+               self.selected = None;
+            }
+            Some(_) => {}
+            None => {
+               if let Some((index, _)) = rows.last() {
+                  self.select(*index);
+               }
+            }
+         }
+      }
+
+      // The rest act on the selected entry
+      let Some(pos) = position else {
+         return;
+      };
+
+      let (index, data) = rows[pos].clone();
+      let deleted = data.is_none() && app.read(|app| app.tombstones.contains_key(&index));
+      if deleted {
+         return;
+      }
+
+      if keymap.pressed(ctx, Action::CopyPassword) {
+         self.copy_password(app.clone(), index, ui);
+      }
+
+      if keymap.pressed(ctx, Action::Edit) {
+         self.edit_entry(&app, index, data.clone().unwrap_or_default());
+      }
+
+      let Some(data) = data else {
+         return;
+      };
+
+      if keymap.pressed(ctx, Action::CopyUsername) && !data.username.is_empty() {
+         ctx.copy_text(data.username.clone());
+      }
+
+      if keymap.pressed(ctx, Action::ShowPassword) {
+         self.reveal_password(app, index, data.title.clone());
+      }
+
+      if keymap.pressed(ctx, Action::Delete) {
+         self.delete_confirm = Some(index);
+      }
+   }
+
+   fn select(&mut self, index: u32) {
+      self.selected = Some(index);
+      self.scroll_to_selected = true;
+   }
+
+   /// Scroll to the selected entry once after it was selected
+   fn mark_if_selected(&mut self, index: u32, response: &Response) {
+      if self.selected != Some(index) || !self.scroll_to_selected {
+         return;
+      }
+      response.scroll_to_me(None);
+      self.scroll_to_selected = false;
+   }
+
+   fn handle_audit_action(&mut self, app: AppCtx, action: AuditAction) {
+      match action {
+         AuditAction::Rotate(index) => {
+            std::thread::spawn(move || {
+               rotate_and_save(app, index);
+            });
+         }
+         AuditAction::Edit(index) => {
+            if let Some(data) = app.get_index(index) {
+               self.edit_entry(&app, index, data);
+            }
+         }
+         AuditAction::MarkReviewed(index) => {
+            std::thread::spawn(move || {
+               review_and_save(app, index);
+            });
+         }
+      }
+   }
+
+   fn copy_password(&mut self, app: AppCtx, index: u32, ui: &Ui) {
+      let password = app.derive_at(index).expect("Deriver instance not found");
+      self.clipboard.copy(ui.ctx(), password);
+
+      std::thread::spawn(move || {
+         if let Err(err) = app.mark_used(index) {
+            eprintln!("Failed to update last used time {}", err);
+         }
+      });
+   }
+
+   fn reveal_password(&mut self, app: AppCtx, index: u32, title: String) {
+      let password = app.derive_at(index).expect("Deriver instance not found");
+      self.reveal.open(index, title, password);
+   }
+
+   pub fn apply_settings(&mut self, settings: &Settings) {
+      self.items_per_page = settings.items_per_page;
+      self.compact = settings.compact;
+      self.clipboard.timeout = Duration::from_secs(settings.clipboard_timeout);
+      self.git_sync = SyncConfig::from_settings(settings);
+      self.current_page = 0;
+   }
+
+   /// Write the preferences that can be changed from the Home screen
+   pub fn store_settings(&self, settings: &mut Settings) {
+      settings.items_per_page = self.items_per_page;
+      settings.compact = self.compact;
+      settings.clipboard_timeout = self.clipboard.timeout.as_secs();
+   }
+
+   /// Close the Home screen and forget anything sensitive it shows
+   pub fn lock(&mut self, ctx: &Context) {
+      self.open = false;
+      self.edit_window = false;
+      self.edited_index = IndexData::default();
+      self.edited_tags.clear();
+      self.index_warning = None;
+      self.delete_confirm = None;
+      self.selected = None;
+      self.query = EntryQuery::default();
+      self.browse_at_least = 0;
+      self.trash = Trash::new();
+      self.breach = BreachWindow::new();
+      self.audit = AuditWindow::new();
+      self.import = ImportWindow::new();
+      self.export = ExportWindow::new();
+      self.merge = MergeWindow::new();
+      self.team = TeamWindow::new();
+      self.shares.close();
+      self.kit = KitWindow::new();
+      self.reveal.hide();
+      self.clipboard.clear(ctx);
+   }
+
+   /// Clear the clipboard if it still holds a copied password
+   pub fn clear_clipboard(&mut self, ctx: &Context) {
+      self.clipboard.clear(ctx);
+   }
+
+   fn show_clipboard_status(&mut self, theme: &Theme, ui: &mut Ui) {
+      ui.horizontal(|ui| {
+         ui.add_space(60.0);
+         ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+         ui.spacing_mut().button_padding = vec2(4.0, 4.0);
+
+         let text = RichText::new("Clear clipboard after").size(theme.text_sizes.normal);
+         ui.label(text);
+
+         let mut timeout = self.clipboard.timeout.as_secs();
+         ComboBox::from_id_salt("clipboard_timeout")
+            .selected_text(timeout_to_str(timeout))
+            .width(60.0)
+            .show_ui(ui, |ui| {
+               for secs in CLEAR_TIMEOUTS {
+                  ui.selectable_value(&mut timeout, secs, timeout_to_str(secs));
+               }
+            });
+         self.clipboard.timeout = Duration::from_secs(timeout);
+
+         if let Some(remaining) = self.clipboard.remaining() {
+            // Round up so the countdown doesn't show 0 while the password is still there
+            let secs = remaining.as_millis().div_ceil(1_000);
+            let text = format!("Clearing in {}s", secs);
+            let text =
+               RichText::new(text).size(theme.text_sizes.normal).color(theme.colors.warning);
+            ui.label(text);
+
+            let text = RichText::new("Clear now").size(theme.text_sizes.normal);
+            if ui.button(text).clicked() {
+               self.clipboard.clear(ui.ctx());
+            }
+         }
+      });
+   }
+
+   /// Show the page that contains this index
+   pub fn jump_to(&mut self, index: u32) {
+      self.query = EntryQuery::default();
+      self.current_page = index / self.items_per_page;
+      self.browse_at_least = self.browse_at_least.max(index.saturating_add(1));
+   }
+
+   /// Compact view, one line per entry
+   fn show_table(
+      &mut self,
+      app: AppCtx,
+      rows: Vec<(u32, Option<IndexData>)>,
+      theme: &Theme,
+      ui: &mut Ui,
+   ) {
+      let muted = theme.colors.text_muted;
+      let size = theme.text_sizes.small;
+
+      Grid::new("entries_table")
+         .striped(true)
+         .spacing(vec2(12.0, 6.0))
+         .show(ui, |ui| {
+            for header in ["#", "Title", "Username", "URL", ""] {
+               ui.label(RichText::new(header).size(size).strong());
+            }
+            ui.end_row();
+
+            for (index, data) in rows {
+               let mut text = RichText::new(index.to_string()).size(size);
+               if self.selected == Some(index) {
+                  text = text.color(theme.colors.primary).strong();
+               }
+               let response = ui.label(text);
+               self.mark_if_selected(index, &response);
+
+               let Some(data) = data else {
+                  let tombstone = app.read(|app| app.tombstones.get(&index).cloned());
+                  match tombstone {
+                     Some(tombstone) => {
+                        let text = format!("Deleted: {}", tombstone.entry.title);
+                        ui.label(RichText::new(text).size(size).color(muted));
+                        ui.label("");
+                        ui.label("");
+                        let text = RichText::new("Restore").size(size);
+                        if ui.button(text).clicked() {
+                           let app = app.clone();
+                           std::thread::spawn(move || {
+                              restore_and_save(app, index);
+                           });
+                        }
+                     }
+                     None => {
+                        let text = RichText::new("No entry").size(size).color(theme.colors.warning);
+                        ui.label(text);
+                        ui.label("");
+                        ui.label("");
+                        if ui.button(RichText::new("Edit").size(size)).clicked() {
+                           self.edit_entry(&app, index, IndexData::default());
+                        }
+                     }
+                  }
+                  ui.end_row();
+                  continue;
+               };
+
+               let title_color = match data.exposed {
+                  true => theme.colors.error,
+                  false => theme.colors.success,
+               };
+               ui.label(RichText::new(&data.title).size(size).color(title_color));
+               ui.label(RichText::new(&data.username).size(size));
+               ui.label(
+                  RichText::new(data.primary_url().unwrap_or_default()).size(size).color(muted),
+               );
+
+               ui.horizontal(|ui| {
+                  if ui.button(RichText::new("Copy").size(size)).clicked() {
+                     self.copy_password(app.clone(), index, ui);
+                  }
+
+                  if ui.button(RichText::new("Show").size(size)).clicked() {
+                     self.reveal_password(app.clone(), index, data.title.clone());
+                  }
+
+                  if ui.button(RichText::new("Edit").size(size)).clicked() {
+                     self.edit_entry(&app, index, data.clone());
+                  }
+
+                  if ui.button(RichText::new("Delete").size(size)).clicked() {
+                     self.delete_confirm = Some(index);
+                  }
+               });
+               ui.end_row();
             }
          });
+   }
+
+   fn show_search_bar(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      let previous_query = self.query.clone();
+
+      let text_edit = TextEdit::singleline(&mut self.query.text)
+         .id(Id::new(SEARCH_ID))
+         .font(FontId::proportional(theme.text_sizes.normal))
+         .desired_width(ui.available_width() * 0.6)
+         .hint_text("Search title, description, tags or URL");
+      ui.add(text_edit);
+
+      let tags = app.read(|app| all_tags(&app.index_map));
+
+      ui.horizontal(|ui| {
+         ui.add_space(60.0);
+         ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+
+         let text = RichText::new("Show").size(theme.text_sizes.normal);
+         ui.label(text);
+
+         ComboBox::from_id_salt("entry_filter")
+            .selected_text(self.query.filter.to_str())
+            .show_ui(ui, |ui| {
+               for filter in EntryFilter::to_vec() {
+                  ui.selectable_value(&mut self.query.filter, filter, filter.to_str());
+               }
+            });
+
+         let text = RichText::new("Tag").size(theme.text_sizes.normal);
+         ui.label(text);
+
+         let selected_tag = self.query.tag.clone().unwrap_or_else(|| String::from("Any"));
+         ComboBox::from_id_salt("entry_tag")
+            .selected_text(selected_tag)
+            .show_ui(ui, |ui| {
+               ui.selectable_value(&mut self.query.tag, None, "Any");
+               for tag in tags {
+                  ui.selectable_value(&mut self.query.tag, Some(tag.clone()), tag);
+               }
+            });
+
+         let text = RichText::new("Sort").size(theme.text_sizes.normal);
+         ui.label(text);
+
+         ComboBox::from_id_salt("entry_sort")
+            .selected_text(self.query.sort.to_str())
+            .show_ui(ui, |ui| {
+               for sort in SortOrder::to_vec() {
+                  ui.selectable_value(&mut self.query.sort, sort, sort.to_str());
+               }
+            });
       });
+
+      if self.query != previous_query {
+         self.current_page = 0;
+         self.browse_at_least = 0;
+      }
    }
 
    pub fn show_item(
@@ -90,21 +786,30 @@ impl Home {
    ) {
       let frame_width = ui.available_width() * 0.6;
       let frame_height = 60.0;
+      let muted = theme.colors.text_muted;
 
       let error = theme.colors.error;
       let warning = theme.colors.warning;
       let success = theme.colors.success;
 
       let exists = data.is_some();
+      let tombstone = match exists {
+         true => None,
+         false => app.read(|app| app.tombstones.get(&index).cloned()),
+      };
+
       let index_data = if exists {
          data.as_ref().unwrap()
       } else {
          &IndexData::default()
       };
 
-      let stroke = match index_data.exposed {
-         true => Stroke::new(1.0, error),
-         false => Stroke::NONE,
+      let stroke = if self.selected == Some(index) {
+         Stroke::new(2.0, theme.colors.primary)
+      } else if index_data.exposed {
+         Stroke::new(1.0, error)
+      } else {
+         Stroke::NONE
       };
 
       let title_color = match index_data.exposed {
@@ -124,15 +829,22 @@ impl Home {
             false => title,
          };
          RichText::new(final_text).size(theme.text_sizes.normal).color(title_color)
+      } else if let Some(tombstone) = &tombstone {
+         let text = format!(
+            "Deleted: {} ({})",
+            tombstone.entry.title,
+            format_timestamp(tombstone.deleted_at)
+         );
+         RichText::new(text).size(theme.text_sizes.normal).color(muted)
       } else {
          no_entry_text
       };
 
       let title_label = Label::new(title_text, None);
 
-      frame.show(ui, |ui| {
+      let response = frame.show(ui, |ui| {
          ui.set_width(frame_width);
-         ui.set_height(frame_height);
+         ui.set_min_height(frame_height);
 
          ui.horizontal(|ui| {
             let text = format!("{}.", index);
@@ -142,25 +854,113 @@ impl Home {
             ui.add(multi_label);
          });
 
+         if exists {
+            let mut details = Vec::new();
+            if !index_data.username.is_empty() {
+               details.push(index_data.username.clone());
+            }
+            if let Some(url) = index_data.primary_url() {
+               details.push(url.to_string());
+            }
+
+            if !details.is_empty() {
+               let text =
+                  RichText::new(details.join("  |  ")).size(theme.text_sizes.small).color(muted);
+               ui.label(text);
+            }
+         }
+
+         if tombstone.is_some() {
+            let text = RichText::new("Restore").size(theme.text_sizes.small);
+            if ui.button(text).clicked() {
+               std::thread::spawn(move || {
+                  restore_and_save(app, index);
+               });
+            }
+            return;
+         }
+
          ui.horizontal(|ui| {
             let text = RichText::new("Copy Password").size(theme.text_sizes.small);
             let button = Button::new(text);
             if ui.add(button).clicked() {
-               let password = app.derive_at(index).expect("Deriver instance not found");
-               let pass_str = password.unlock_str(|s| String::from(s));
-               ui.ctx().copy_text(pass_str);
+               self.copy_password(app.clone(), index, ui);
+            }
+
+            if exists {
+               let text = RichText::new("Show").size(theme.text_sizes.small);
+               if ui.add(Button::new(text)).clicked() {
+                  self.reveal_password(app.clone(), index, index_data.title.clone());
+               }
+            }
+
+            if !index_data.username.is_empty() {
+               let text = RichText::new("Copy Username").size(theme.text_sizes.small);
+               let button = Button::new(text);
+               if ui.add(button).clicked() {
+                  ui.ctx().copy_text(index_data.username.clone());
+               }
             }
 
             let text = RichText::new("Edit").size(theme.text_sizes.small);
             let button = Button::new(text);
 
             if ui.add(button).clicked() {
-               self.edit_window = true;
-               self.index_to_edit = index;
-               self.edited_index = index_data.clone();
+               self.edit_entry(&app, index, index_data.clone());
+            }
+
+            if exists {
+               let text = RichText::new("Delete").size(theme.text_sizes.small);
+               if ui.add(Button::new(text)).clicked() {
+                  self.delete_confirm = Some(index);
+               }
             }
          });
       });
+      self.mark_if_selected(index, &response.response);
+   }
+
+   fn show_delete_window(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      let Some(index) = self.delete_confirm else {
+         return;
+      };
+
+      let title = app.get_index(index).map(|data| data.title).unwrap_or_default();
+
+      Window::new("Delete Entry")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(300.0);
+
+               let text = format!("Delete \"{}\" at index {}?", title, index);
+               let text = RichText::new(text).size(theme.text_sizes.large);
+               ui.label(text);
+
+               let text = "The entry is moved to the trash and its index will not be reused.";
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               let text = RichText::new("Delete").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  self.delete_confirm = None;
+                  std::thread::spawn(move || {
+                     delete_and_save(app, index);
+                  });
+               }
+
+               let text = RichText::new("Cancel").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  self.delete_confirm = None;
+               }
+            });
+         });
    }
 
    fn show_edit_window(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
@@ -177,32 +977,45 @@ impl Home {
                ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
                ui.spacing_mut().button_padding = vec2(8.0, 8.0);
 
-               let text = RichText::new("Title").size(theme.text_sizes.normal);
+               let text = format!("Entry at index {}", self.index_to_edit);
+               let text = RichText::new(text).size(theme.text_sizes.large);
                ui.label(text);
 
-               let text_edit = TextEdit::singleline(&mut self.edited_index.title)
-                  .font(FontId::proportional(theme.text_sizes.normal))
-                  .desired_width(ui.available_width() * 0.6)
-                  .hint_text("Title");
-               ui.add(text_edit);
+               if let Ok(mut password) = app.derive_at(self.index_to_edit) {
+                  let text = password.unlock_str(output_format_text);
+                  password.erase();
+                  let text = RichText::new(text).size(theme.text_sizes.small);
+                  ui.label(text.color(theme.colors.text_muted));
+               }
 
-               let text = RichText::new("Description").size(theme.text_sizes.normal);
-               ui.label(text);
+               let mut use_index = None;
+               if let Some(warning) = &self.index_warning {
+                  let text = RichText::new(warning).size(theme.text_sizes.small);
+                  ui.label(text.color(theme.colors.warning));
 
-               let text_edit = TextEdit::multiline(&mut self.edited_index.description)
-                  .font(FontId::proportional(theme.text_sizes.normal))
-                  .desired_width(ui.available_width() * 0.9)
-                  .hint_text("Description");
-               ui.add(text_edit);
+                  let after_highest = app.read(|app| app.index_after_highest());
+                  let text = format!("Use index {}", after_highest);
+                  let text = RichText::new(text).size(theme.text_sizes.small);
+                  if ui.button(text).clicked() {
+                     use_index = Some(after_highest);
+                  }
+               }
+
+               if let Some(index) = use_index {
+                  self.index_to_edit = index;
+                  self.index_warning = None;
+               }
 
-               let text = RichText::new("Exposed").size(theme.text_sizes.normal);
-               ui.checkbox(&mut self.edited_index.exposed, text);
+               ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                  self.show_edit_fields(theme, ui);
+               });
 
                let text = RichText::new("OK").size(theme.text_sizes.normal);
                let button = Button::new(text).min_size(vec2(100.0, 25.0));
 
                if ui.add(button).clicked() {
-                  let new_data = self.edited_index.clone();
+                  let mut new_data = self.edited_index.clone();
+                  new_data.tags = parse_tags(&self.edited_tags);
                   let index = self.index_to_edit;
                   std::thread::spawn(move || {
                      validate_and_save(app, index, new_data);
@@ -218,9 +1031,147 @@ impl Home {
             });
          });
    }
+
+   fn show_edit_fields(&mut self, theme: &Theme, ui: &mut Ui) {
+      let font = FontId::proportional(theme.text_sizes.normal);
+      let single_width = ui.available_width() * 0.6;
+
+      let text = RichText::new("Title").size(theme.text_sizes.normal);
+      ui.label(text);
+
+      let text_edit = TextEdit::singleline(&mut self.edited_index.title)
+         .font(font.clone())
+         .desired_width(single_width)
+         .hint_text("Title");
+      ui.add(text_edit);
+
+      let text = RichText::new("Username").size(theme.text_sizes.normal);
+      ui.label(text);
+
+      let text_edit = TextEdit::singleline(&mut self.edited_index.username)
+         .font(font.clone())
+         .desired_width(single_width)
+         .hint_text("Username or email");
+      ui.add(text_edit);
+
+      let text = RichText::new("URLs").size(theme.text_sizes.normal);
+      ui.label(text);
+
+      let mut url_to_remove = None;
+      for (i, url) in self.edited_index.urls.iter_mut().enumerate() {
+         ui.horizontal(|ui| {
+            let text_edit = TextEdit::singleline(url)
+               .font(font.clone())
+               .desired_width(single_width)
+               .hint_text("https://");
+            ui.add(text_edit);
+
+            let text = RichText::new("Remove").size(theme.text_sizes.small);
+            if ui.button(text).clicked() {
+               url_to_remove = Some(i);
+            }
+         });
+      }
+
+      if let Some(i) = url_to_remove {
+         self.edited_index.urls.remove(i);
+      }
+
+      let text = RichText::new("Add URL").size(theme.text_sizes.small);
+      if ui.button(text).clicked() {
+         self.edited_index.urls.push(String::new());
+      }
+
+      let text = RichText::new("Tags").size(theme.text_sizes.normal);
+      ui.label(text);
+
+      let text_edit = TextEdit::singleline(&mut self.edited_tags)
+         .font(font.clone())
+         .desired_width(single_width)
+         .hint_text("Comma separated, e.g. work, email");
+      ui.add(text_edit);
+
+      let text = RichText::new("Description").size(theme.text_sizes.normal);
+      ui.label(text);
+
+      let text_edit = TextEdit::multiline(&mut self.edited_index.description)
+         .font(font.clone())
+         .desired_width(ui.available_width() * 0.9)
+         .hint_text("Description");
+      ui.add(text_edit);
+
+      let text = RichText::new("Custom fields").size(theme.text_sizes.normal);
+      ui.label(text);
+
+      let field_width = ui.available_width() * 0.3;
+      let mut field_to_remove = None;
+      for (i, field) in self.edited_index.custom_fields.iter_mut().enumerate() {
+         ui.horizontal(|ui| {
+            let text_edit = TextEdit::singleline(&mut field.key)
+               .font(font.clone())
+               .desired_width(field_width)
+               .hint_text("Name");
+            ui.add(text_edit);
+
+            let text_edit = TextEdit::singleline(&mut field.value)
+               .font(font.clone())
+               .desired_width(field_width)
+               .hint_text("Value");
+            ui.add(text_edit);
+
+            let text = RichText::new("Remove").size(theme.text_sizes.small);
+            if ui.button(text).clicked() {
+               field_to_remove = Some(i);
+            }
+         });
+      }
+
+      if let Some(i) = field_to_remove {
+         self.edited_index.custom_fields.remove(i);
+      }
+
+      let text = RichText::new("Add field").size(theme.text_sizes.small);
+      if ui.button(text).clicked() {
+         self.edited_index.custom_fields.push(CustomField::default());
+      }
+
+      let text = RichText::new("Exposed").size(theme.text_sizes.normal);
+      ui.checkbox(&mut self.edited_index.exposed, text);
+
+      let timestamps = [
+         ("Created", self.edited_index.created_at),
+         ("Modified", self.edited_index.modified_at),
+         ("Last used", self.edited_index.last_used_at),
+         ("Rotated", self.edited_index.rotated_at),
+      ];
+
+      for (label, timestamp) in timestamps {
+         let text = format!("{}: {}", label, format_timestamp(timestamp));
+         let text = RichText::new(text).size(theme.text_sizes.small).color(theme.colors.text_muted);
+         ui.label(text);
+      }
+   }
 }
 
-fn validate_and_save(app: AppCtx, index: u32, data: IndexData) {
+pub fn timeout_to_str(secs: u64) -> String {
+   match secs {
+      0 => String::from("Never"),
+      secs => format!("{}s", secs),
+   }
+}
+
+fn parse_tags(input: &str) -> Vec<String> {
+   let mut tags: Vec<String> = Vec::new();
+   for tag in input.split(',') {
+      let tag = tag.trim();
+      if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+         tags.push(tag.to_string());
+      }
+   }
+   tags
+}
+
+fn validate_and_save(app: AppCtx, index: u32, mut data: IndexData) {
    if data.title.is_empty() {
       SHARED_GUI.write(|gui| {
          gui.msg_window.open("Error", "Title cannot be empty");
@@ -228,17 +1179,31 @@ fn validate_and_save(app: AppCtx, index: u32, data: IndexData) {
       return;
    }
 
-   app.set_index(index, data);
+   data.urls.retain(|url| !url.trim().is_empty());
+   data.custom_fields.retain(|field| !field.key.trim().is_empty());
+
+   let now = unix_now();
+   if data.created_at == 0 {
+      data.created_at = now;
+   }
+   data.modified_at = now;
+
+   let previous = app.write(|app| app.index_map.insert(index, data));
 
    match app.save_index_map_to_file() {
       Ok(_) => {
          SHARED_GUI.write(|gui| {
             gui.home.edit_window = false;
+            gui.home.audit.invalidate();
             gui.msg_window.open("Success", "Entry saved");
          });
       }
       Err(err) => {
-         app.remove_index(index);
+         // Put back the entry as it was, an edit that can't be saved must not delete it
+         match previous {
+            Some(previous) => app.set_index(index, previous),
+            None => app.remove_index(index),
+         }
          SHARED_GUI.write(|gui| {
             gui.home.edit_window = false;
             gui.msg_window.open("Error", err.to_string());
@@ -246,3 +1211,38 @@ fn validate_and_save(app: AppCtx, index: u32, data: IndexData) {
       }
    }
 }
+
+/// The warning shown when editing a free index that lies below other entries
+fn gap_warning(app: &AppCtx, index: u32) -> Option<String> {
+   let (is_gap, after_highest) = app.read(|app| (app.is_gap(index), app.index_after_highest()));
+   if !is_gap {
+      return None;
+   }
+
+   Some(format!(
+      "Index {} is unused but lies below other entries, it may have belonged to a deleted entry. \
+      Make sure you never used this password before or use index {} instead.",
+      index, after_highest
+   ))
+}
+
+fn delete_and_save(app: AppCtx, index: u32) {
+   if !app.write(|app| app.delete_index(index)) {
+      return;
+   }
+
+   match app.save_index_map_to_file() {
+      Ok(_) => {
+         SHARED_GUI.write(|gui| {
+            gui.home.audit.invalidate();
+            gui.msg_window.open("Success", "Entry moved to the trash");
+         });
+      }
+      Err(err) => {
+         let _ = app.write(|app| app.restore_index(index));
+         SHARED_GUI.write(|gui| {
+            gui.msg_window.open("Error", err.to_string());
+         });
+      }
+   }
+}
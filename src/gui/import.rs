@@ -0,0 +1,818 @@
+use super::{
+   AppCtx, SHARED_GUI,
+   app::{IndexData, unix_now},
+};
+use eframe::egui::{
+   Align2, Button, ComboBox, FontId, Grid, RichText, ScrollArea, TextEdit, Ui, Window, vec2,
+};
+use quick_xml::{Reader, events::Event};
+use serde::Deserialize;
+use zeus_theme::Theme;
+
+/// Rows shown in the preview
+const PREVIEW_ROWS: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+   BitwardenJson,
+   KeePassXml,
+   KeePassCsv,
+   OnePasswordCsv,
+   GenericCsv,
+}
+
+impl ImportFormat {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         ImportFormat::BitwardenJson => "Bitwarden (JSON)",
+         ImportFormat::KeePassXml => "KeePass (XML)",
+         ImportFormat::KeePassCsv => "KeePass (CSV)",
+         ImportFormat::OnePasswordCsv => "1Password (CSV)",
+         ImportFormat::GenericCsv => "Generic CSV",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![
+         Self::BitwardenJson,
+         Self::KeePassXml,
+         Self::KeePassCsv,
+         Self::OnePasswordCsv,
+         Self::GenericCsv,
+      ]
+   }
+}
+
+/// The exported entries as columns and rows, without any password
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportTable {
+   pub headers: Vec<String>,
+   pub rows: Vec<Vec<String>>,
+}
+
+/// The fields an entry gets, `None` leaves the field empty
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColumnMapping {
+   pub title: Option<usize>,
+   pub username: Option<usize>,
+   pub url: Option<usize>,
+   pub notes: Option<usize>,
+   /// Folder or group, imported as a tag
+   pub group: Option<usize>,
+}
+
+impl ColumnMapping {
+   /// Match the column names used by the common exports
+   pub fn guess(headers: &[String]) -> Self {
+      let find = |names: &[&str]| {
+         headers.iter().position(|h| names.contains(&h.trim().to_lowercase().as_str()))
+      };
+
+      Self {
+         title: find(&["title", "name"]),
+         username: find(&["username", "user name", "login_username", "login", "user"]),
+         url: find(&["url", "urls", "login_uri", "website", "uri", "web site"]),
+         notes: find(&["notes", "notesplain", "note", "extra", "comments"]),
+         group: find(&["group", "folder", "category", "type", "tags"]),
+      }
+   }
+}
+
+/// An entry of the export, with only what [IndexData] can hold
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportedEntry {
+   pub title: String,
+   pub username: String,
+   pub url: String,
+   pub notes: String,
+   pub group: String,
+}
+
+impl ImportedEntry {
+   fn into_index_data(self, now: u64) -> IndexData {
+      IndexData {
+         title: self.title,
+         username: self.username,
+         urls: match self.url.is_empty() {
+            true => Vec::new(),
+            false => vec![self.url],
+         },
+         description: self.notes,
+         tags: match self.group.is_empty() {
+            true => Vec::new(),
+            false => vec![self.group],
+         },
+         created_at: now,
+         modified_at: now,
+         ..Default::default()
+      }
+   }
+}
+
+/// True for columns that hold secrets, they are dropped while reading
+fn is_secret_column(header: &str) -> bool {
+   let header = header.trim().to_lowercase();
+   ["password", "totp", "otp", "secret", "otpauth"]
+      .iter()
+      .any(|s| header.contains(s))
+}
+
+pub fn parse(format: ImportFormat, data: &str) -> Result<ImportTable, Box<dyn std::error::Error>> {
+   match format {
+      ImportFormat::BitwardenJson => parse_bitwarden_json(data),
+      ImportFormat::KeePassXml => parse_keepass_xml(data),
+      ImportFormat::KeePassCsv | ImportFormat::OnePasswordCsv | ImportFormat::GenericCsv => {
+         parse_csv(data)
+      }
+   }
+}
+
+pub fn parse_csv(data: &str) -> Result<ImportTable, Box<dyn std::error::Error>> {
+   let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data.as_bytes());
+
+   let headers: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_string()).collect();
+   let secret: Vec<bool> = headers.iter().map(|h| is_secret_column(h)).collect();
+
+   let mut rows = Vec::new();
+   for record in reader.records() {
+      let record = record?;
+      let row = record
+         .iter()
+         .enumerate()
+         .map(|(i, value)| match secret.get(i) {
+            Some(false) => value.trim().to_string(),
+            _ => String::new(),
+         })
+         .collect();
+      rows.push(row);
+   }
+
+   Ok(ImportTable { headers, rows })
+}
+
+/// Headers of the tables built from the JSON and XML exports
+fn standard_headers() -> Vec<String> {
+   ["Title", "Username", "URL", "Notes", "Group"]
+      .iter()
+      .map(|h| h.to_string())
+      .collect()
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+   #[serde(default)]
+   folders: Vec<BitwardenFolder>,
+   #[serde(default)]
+   items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+   id: String,
+   name: String,
+}
+
+/// Only the fields we keep, the password is never deserialized
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+   #[serde(default)]
+   name: String,
+   #[serde(default)]
+   notes: Option<String>,
+   #[serde(default)]
+   folder_id: Option<String>,
+   #[serde(default)]
+   login: Option<BitwardenLogin>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+   #[serde(default)]
+   username: Option<String>,
+   #[serde(default)]
+   uris: Option<Vec<BitwardenUri>>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+   #[serde(default)]
+   uri: Option<String>,
+}
+
+pub fn parse_bitwarden_json(data: &str) -> Result<ImportTable, Box<dyn std::error::Error>> {
+   let export: BitwardenExport = serde_json::from_str(data)?;
+
+   let rows = export
+      .items
+      .into_iter()
+      .map(|item| {
+         let folder = item
+            .folder_id
+            .and_then(|id| export.folders.iter().find(|f| f.id == id))
+            .map(|f| f.name.clone())
+            .unwrap_or_default();
+
+         let (username, url) = match item.login {
+            Some(login) => {
+               let url = login
+                  .uris
+                  .unwrap_or_default()
+                  .into_iter()
+                  .find_map(|u| u.uri)
+                  .unwrap_or_default();
+               (login.username.unwrap_or_default(), url)
+            }
+            None => (String::new(), String::new()),
+         };
+
+         vec![
+            item.name,
+            username,
+            url,
+            item.notes.unwrap_or_default(),
+            folder,
+         ]
+      })
+      .collect();
+
+   Ok(ImportTable {
+      headers: standard_headers(),
+      rows,
+   })
+}
+
+/// KeePass 2 XML export, entries of the recycle bin and the history are skipped
+pub fn parse_keepass_xml(data: &str) -> Result<ImportTable, Box<dyn std::error::Error>> {
+   let mut reader = Reader::from_str(data);
+   reader.config_mut().trim_text(true);
+
+   let mut path: Vec<String> = Vec::new();
+   let mut groups: Vec<String> = Vec::new();
+   let mut rows = Vec::new();
+
+   let mut row: Vec<String> = Vec::new();
+   let mut key = String::new();
+   let mut text = String::new();
+
+   loop {
+      match reader.read_event()? {
+         Event::Start(e) => {
+            let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+            if name == "Entry" && !path.iter().any(|p| p == "History") {
+               row = vec![String::new(); 5];
+               row[4] = groups.last().cloned().unwrap_or_default();
+            }
+            if name == "Group" {
+               groups.push(String::new());
+            }
+            path.push(name);
+            text.clear();
+         }
+         Event::Text(e) => {
+            text = e.unescape()?.to_string();
+         }
+         Event::End(_) => {
+            let name = path.pop().unwrap_or_default();
+            let parent = path.last().map(|p| p.as_str());
+            let in_history = path.iter().any(|p| p == "History");
+
+            match (name.as_str(), parent) {
+               ("Name", Some("Group")) => {
+                  if let Some(group) = groups.last_mut() {
+                     *group = text.clone();
+                  }
+               }
+               ("Group", _) => {
+                  groups.pop();
+               }
+               ("Key", Some("String")) => key = text.clone(),
+               ("Value", Some("String")) if !in_history => {
+                  let column = match key.as_str() {
+                     "Title" => Some(0),
+                     "UserName" => Some(1),
+                     "URL" => Some(2),
+                     "Notes" => Some(3),
+                     _ => None,
+                  };
+                  if let Some(column) = column
+                     && row.len() == 5
+                  {
+                     row[column] = text.clone();
+                  }
+               }
+               ("Entry", _) if !in_history => {
+                  let in_recycle_bin = groups.iter().any(|g| g == "Recycle Bin");
+                  if !in_recycle_bin && row.len() == 5 {
+                     rows.push(std::mem::take(&mut row));
+                  }
+                  row.clear();
+               }
+               _ => {}
+            }
+            text.clear();
+         }
+         Event::Eof => break,
+         _ => {}
+      }
+   }
+
+   Ok(ImportTable {
+      headers: standard_headers(),
+      rows,
+   })
+}
+
+/// Turn the rows into entries, rows without a title use their URL or are skipped
+pub fn to_entries(table: &ImportTable, mapping: &ColumnMapping) -> Vec<ImportedEntry> {
+   let get = |row: &Vec<String>, column: Option<usize>| {
+      column
+         .and_then(|c| row.get(c))
+         .map(|v| v.trim().to_string())
+         .unwrap_or_default()
+   };
+
+   table
+      .rows
+      .iter()
+      .filter_map(|row| {
+         let mut entry = ImportedEntry {
+            title: get(row, mapping.title),
+            username: get(row, mapping.username),
+            url: get(row, mapping.url),
+            notes: get(row, mapping.notes),
+            group: get(row, mapping.group),
+         };
+
+         if entry.title.is_empty() {
+            entry.title = entry.url.clone();
+         }
+
+         match entry.title.is_empty() {
+            true => None,
+            false => Some(entry),
+         }
+      })
+      .collect()
+}
+
+/// Add the entries at fresh indices, after the highest used one
+///
+/// Returns the index each entry got.
+pub fn import_entries(
+   app: AppCtx,
+   entries: Vec<ImportedEntry>,
+) -> Result<Vec<(u32, ImportedEntry)>, Box<dyn std::error::Error>> {
+   let now = unix_now();
+
+   let imported = app.write(|app| {
+      let mut next = app.highest_used_index().map_or(Some(0), |i| i.checked_add(1));
+      let mut imported = Vec::new();
+      for entry in entries {
+         let Some(index) = next else {
+            for (index, _) in &imported {
+               app.index_map.remove(index);
+            }
+            return Err("There are not enough free indices after the highest one");
+         };
+         app.index_map.insert(index, entry.clone().into_index_data(now));
+         imported.push((index, entry));
+         next = index.checked_add(1);
+      }
+      Ok(imported)
+   })?;
+
+   if let Err(err) = app.save_index_map_to_file() {
+      app.write(|app| {
+         for (index, _) in &imported {
+            app.index_map.remove(index);
+         }
+      });
+      return Err(err);
+   }
+
+   Ok(imported)
+}
+
+/// The accounts whose password must now be changed to the derived one
+pub fn report_text(imported: &[(u32, ImportedEntry)]) -> String {
+   let mut lines = vec![format!(
+      "{} entries imported. Change the password of each account to the one derived at its index:",
+      imported.len()
+   )];
+
+   for (index, entry) in imported {
+      let mut line = format!("{}. {}", index, entry.title);
+      if !entry.username.is_empty() {
+         line.push_str(&format!(" ({})", entry.username));
+      }
+      if !entry.url.is_empty() {
+         line.push_str(&format!(" - {}", entry.url));
+      }
+      lines.push(line);
+   }
+
+   lines.join("\n")
+}
+
+/// Pick a file, map its columns, preview and import
+pub struct ImportWindow {
+   open: bool,
+   format: ImportFormat,
+   path: String,
+   table: Option<ImportTable>,
+   mapping: ColumnMapping,
+   /// Rows the user left out of the import
+   excluded: Vec<bool>,
+   error: Option<String>,
+   importing: bool,
+   /// Set once the import is done
+   pub report: Option<String>,
+}
+
+impl ImportWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         format: ImportFormat::BitwardenJson,
+         path: String::new(),
+         table: None,
+         mapping: ColumnMapping::default(),
+         excluded: Vec::new(),
+         error: None,
+         importing: false,
+         report: None,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+      self.table = None;
+      self.report = None;
+      self.error = None;
+   }
+
+   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      if !self.open {
+         return;
+      }
+
+      Window::new("Import")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(500.0);
+
+               let text = RichText::new("Import").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               if let Some(report) = &self.report {
+                  self.show_report(report.clone(), theme, ui);
+                  return;
+               }
+
+               let text = "Only titles, usernames, URLs, notes and folders are imported, \
+               passwords are never read. Every imported account needs its password changed \
+               to the derived one.";
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               self.show_source(theme, ui);
+
+               if let Some(error) = &self.error {
+                  let text = RichText::new(error).size(theme.text_sizes.normal);
+                  ui.label(text.color(theme.colors.error));
+               }
+
+               if self.table.is_some() {
+                  self.show_mapping(theme, ui);
+                  self.show_preview(theme, ui);
+               }
+
+               if self.importing {
+                  ui.spinner();
+               }
+
+               ui.horizontal(|ui| {
+                  ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+                  let text = RichText::new("Import").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  let ready = self.table.is_some() && !self.importing;
+                  if ui.add_enabled(ready, button).clicked() {
+                     self.import(app.clone());
+                  }
+
+                  let text = RichText::new("Close").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     *self = Self::new();
+                  }
+               });
+            });
+         });
+   }
+
+   fn show_source(&mut self, theme: &Theme, ui: &mut Ui) {
+      ui.horizontal(|ui| {
+         ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+
+         ComboBox::from_id_salt("import_format")
+            .selected_text(self.format.to_str())
+            .show_ui(ui, |ui| {
+               for format in ImportFormat::to_vec() {
+                  ui.selectable_value(&mut self.format, format, format.to_str());
+               }
+            });
+
+         let text_edit = TextEdit::singleline(&mut self.path)
+            .font(FontId::proportional(theme.text_sizes.normal))
+            .desired_width(250.0)
+            .hint_text("Path of the exported file");
+         ui.add(text_edit);
+
+         let text = RichText::new("Load").size(theme.text_sizes.normal);
+         if ui.button(text).clicked() {
+            self.load();
+         }
+      });
+   }
+
+   fn load(&mut self) {
+      self.error = None;
+      self.table = None;
+
+      let res = std::fs::read_to_string(self.path.trim())
+         .map_err(|e| e.into())
+         .and_then(|data| parse(self.format, &data));
+
+      match res {
+         Ok(table) => {
+            self.mapping = ColumnMapping::guess(&table.headers);
+            self.excluded = vec![false; table.rows.len()];
+            self.table = Some(table);
+         }
+         Err(e) => self.error = Some(format!("Failed to read the export: {}", e)),
+      }
+   }
+
+   fn show_mapping(&mut self, theme: &Theme, ui: &mut Ui) {
+      let Some(table) = &self.table else {
+         return;
+      };
+
+      let headers = table.headers.clone();
+      let size = theme.text_sizes.normal;
+
+      Grid::new("import_mapping").spacing(vec2(20.0, 6.0)).show(ui, |ui| {
+         let fields = [
+            ("Title", &mut self.mapping.title),
+            ("Username", &mut self.mapping.username),
+            ("URL", &mut self.mapping.url),
+            ("Notes", &mut self.mapping.notes),
+            ("Tag", &mut self.mapping.group),
+         ];
+
+         for (label, column) in fields {
+            ui.label(RichText::new(label).size(size));
+
+            let selected = column.and_then(|c| headers.get(c)).map_or("None", |h| h.as_str());
+            ComboBox::from_id_salt(format!("import_mapping_{}", label))
+               .selected_text(selected)
+               .show_ui(ui, |ui| {
+                  ui.selectable_value(column, None, "None");
+                  for (i, header) in headers.iter().enumerate() {
+                     if !is_secret_column(header) {
+                        ui.selectable_value(column, Some(i), header);
+                     }
+                  }
+               });
+            ui.end_row();
+         }
+      });
+   }
+
+   fn show_preview(&mut self, theme: &Theme, ui: &mut Ui) {
+      let Some(table) = &self.table else {
+         return;
+      };
+
+      let entries: Vec<Option<ImportedEntry>> = table
+         .rows
+         .iter()
+         .map(|row| {
+            let single = ImportTable {
+               headers: Vec::new(),
+               rows: vec![row.clone()],
+            };
+            to_entries(&single, &self.mapping).pop()
+         })
+         .collect();
+
+      let count = entries
+         .iter()
+         .zip(&self.excluded)
+         .filter(|(entry, excluded)| entry.is_some() && !**excluded)
+         .count();
+      let text = format!(
+         "{} of {} rows will be imported",
+         count,
+         entries.len()
+      );
+      ui.label(RichText::new(text).size(theme.text_sizes.normal));
+
+      let size = theme.text_sizes.small;
+      ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+         Grid::new("import_preview")
+            .striped(true)
+            .spacing(vec2(12.0, 4.0))
+            .show(ui, |ui| {
+               for header in ["", "Title", "Username", "URL", "Tag"] {
+                  ui.label(RichText::new(header).size(size).strong());
+               }
+               ui.end_row();
+
+               for (i, entry) in entries.iter().enumerate().take(PREVIEW_ROWS) {
+                  let Some(entry) = entry else {
+                     continue;
+                  };
+
+                  let mut included = !self.excluded[i];
+                  ui.checkbox(&mut included, "");
+                  self.excluded[i] = !included;
+
+                  ui.label(RichText::new(&entry.title).size(size));
+                  ui.label(RichText::new(&entry.username).size(size));
+                  ui.label(RichText::new(&entry.url).size(size));
+                  ui.label(RichText::new(&entry.group).size(size));
+                  ui.end_row();
+               }
+            });
+
+         if entries.len() > PREVIEW_ROWS {
+            let text = format!("And {} more", entries.len() - PREVIEW_ROWS);
+            ui.label(RichText::new(text).size(size).color(theme.colors.text_muted));
+         }
+      });
+   }
+
+   fn import(&mut self, app: AppCtx) {
+      let Some(table) = &self.table else {
+         return;
+      };
+
+      let rows = table
+         .rows
+         .iter()
+         .zip(&self.excluded)
+         .filter(|(_, excluded)| !**excluded)
+         .map(|(row, _)| row.clone())
+         .collect();
+      let included = ImportTable {
+         headers: table.headers.clone(),
+         rows,
+      };
+
+      let entries = to_entries(&included, &self.mapping);
+      if entries.is_empty() {
+         self.error = Some(String::from(
+            "Nothing to import, map a column to the title",
+         ));
+         return;
+      }
+
+      self.importing = true;
+      std::thread::spawn(move || {
+         let res = import_entries(app, entries);
+         SHARED_GUI.write(|gui| {
+            gui.home.import.importing = false;
+            match res {
+               Ok(imported) => gui.home.import.report = Some(report_text(&imported)),
+               Err(err) => gui.msg_window.open("Error", err.to_string()),
+            }
+            gui.request_repaint();
+         });
+      });
+   }
+
+   fn show_report(&mut self, report: String, theme: &Theme, ui: &mut Ui) {
+      ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+         let text = RichText::new(&report).size(theme.text_sizes.normal);
+         ui.label(text);
+      });
+
+      ui.horizontal(|ui| {
+         ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+         let text = RichText::new("Copy report").size(theme.text_sizes.normal);
+         let button = Button::new(text).min_size(vec2(100.0, 25.0));
+         if ui.add(button).clicked() {
+            ui.ctx().copy_text(report.clone());
+         }
+
+         let text = RichText::new("Close").size(theme.text_sizes.normal);
+         let button = Button::new(text).min_size(vec2(100.0, 25.0));
+         if ui.add(button).clicked() {
+            *self = Self::new();
+         }
+      });
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_parse_csv() {
+      let keepass = "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\"\n\
+         \"Root\",\"Mail\",\"alice\",\"hunter2\",\"https://mail.example.com\",\"work\"\n\
+         \"Root\",\"\",\"bob\",\"hunter3\",\"\",\"\"\n";
+      let table = parse_csv(keepass).unwrap();
+      assert!(table.rows.iter().flatten().all(|v| !v.starts_with("hunter")));
+
+      let entries = to_entries(&table, &ColumnMapping::guess(&table.headers));
+      assert_eq!(entries.len(), 1);
+      assert_eq!(entries[0].title, "Mail");
+      assert_eq!(entries[0].username, "alice");
+      assert_eq!(entries[0].url, "https://mail.example.com");
+      assert_eq!(entries[0].notes, "work");
+      assert_eq!(entries[0].group, "Root");
+
+      let one_password = "Title,Website,Username,Password,Notes,Type\n\
+         Bank,https://bank.example.com,carol,secret,,Login\n";
+      let table = parse_csv(one_password).unwrap();
+      let entries = to_entries(&table, &ColumnMapping::guess(&table.headers));
+      assert_eq!(entries[0].url, "https://bank.example.com");
+      assert_eq!(entries[0].group, "Login");
+   }
+
+   #[test]
+   fn test_parse_bitwarden_json() {
+      let export = r#"{
+         "folders": [{"id": "f1", "name": "Social"}],
+         "items": [{
+            "name": "Forum",
+            "notes": null,
+            "folderId": "f1",
+            "login": {
+               "username": "dave",
+               "password": "hunter2",
+               "uris": [{"match": null, "uri": "https://forum.example.com"}]
+            }
+         }, {"name": "Card", "type": 3}]
+      }"#;
+
+      let table = parse_bitwarden_json(export).unwrap();
+      let entries = to_entries(&table, &ColumnMapping::guess(&table.headers));
+      assert_eq!(entries.len(), 2);
+      assert_eq!(entries[0].username, "dave");
+      assert_eq!(entries[0].url, "https://forum.example.com");
+      assert_eq!(entries[0].group, "Social");
+      assert_eq!(entries[1].title, "Card");
+   }
+
+   #[test]
+   fn test_parse_keepass_xml() {
+      let export = r#"<KeePassFile><Root><Group><Name>Root</Name>
+         <Entry>
+            <String><Key>Title</Key><Value>Shop</Value></String>
+            <String><Key>UserName</Key><Value>erin</Value></String>
+            <String><Key>Password</Key><Value ProtectInMemory="True">hunter2</Value></String>
+            <String><Key>URL</Key><Value>https://shop.example.com</Value></String>
+            <History><Entry><String><Key>Title</Key><Value>Old</Value></String></Entry></History>
+         </Entry>
+         <Group><Name>Recycle Bin</Name>
+            <Entry><String><Key>Title</Key><Value>Gone</Value></String></Entry>
+         </Group>
+      </Group></Root></KeePassFile>"#;
+
+      let table = parse_keepass_xml(export).unwrap();
+      let entries = to_entries(&table, &ColumnMapping::guess(&table.headers));
+      assert_eq!(entries.len(), 1);
+      assert_eq!(entries[0].title, "Shop");
+      assert_eq!(entries[0].username, "erin");
+      assert_eq!(entries[0].url, "https://shop.example.com");
+      assert_eq!(entries[0].group, "Root");
+   }
+
+   #[test]
+   fn test_import_needs_free_indices() {
+      let app = AppCtx::default();
+      app.set_index(u32::MAX - 1, IndexData::titled("Mail"));
+      let entry = |title: &str| ImportedEntry {
+         title: title.to_string(),
+         ..Default::default()
+      };
+
+      // Only u32::MAX is left, nothing is imported and the entry stays
+      let entries = vec![entry("Bank"), entry("Forum")];
+      assert!(import_entries(app.clone(), entries).is_err());
+      assert_eq!(app.read(|app| app.index_map.len()), 1);
+      assert_eq!(app.get_index(u32::MAX - 1).unwrap().title, "Mail");
+   }
+}
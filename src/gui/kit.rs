@@ -0,0 +1,541 @@
+//! Printable recovery kit
+//!
+//! A few A4 pages as SVG with everything needed to get the passwords back with the app: the
+//! Argon2 parameters, the derivation scheme, the credential fingerprint, the index of every
+//! entry and optionally one recovery share. It holds no password, but a share is secret.
+
+use super::{AppCtx, SHARED_GUI, app::format_timestamp, app::unix_now, misc::SecretBuf};
+use argon2_rs::Argon2;
+use eframe::egui::{Align2, Button, FontId, RichText, TextEdit, Ui, Window, vec2};
+use passwd_derive::{Scheme, shamir::Share};
+use qrcode::{Color as QrColor, QrCode};
+use secure_types::SecureString;
+use std::{
+   io::Write,
+   ops::Range,
+   path::{Path, PathBuf},
+};
+use zeus_theme::Theme;
+
+/// A4 in millimeters
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const QR_SIZE: f32 = 60.0;
+
+/// Everything printed on the kit
+pub struct RecoveryKit {
+   pub argon2: Argon2,
+   pub scheme: Scheme,
+   pub fingerprint: String,
+   /// Index and title of every entry, sorted by index
+   pub entries: Vec<(u32, String)>,
+   /// Words of a recovery share, see [Share::to_words]
+   pub share: Option<SecureString>,
+   pub created_at: u64,
+}
+
+/// A line of the kit
+///
+/// The share is not copied into items, its QR code and lines are drawn from the words
+/// straight into the page when it's rendered.
+enum Item {
+   Heading(String),
+   Text(String),
+   Mono(String),
+   ShareQr,
+   /// Bytes of the share words on this line
+   ShareLine(Range<usize>),
+   Gap,
+}
+
+impl Item {
+   fn height(&self) -> f32 {
+      match self {
+         Item::Heading(_) => 10.0,
+         Item::Text(_) | Item::Mono(_) | Item::ShareLine(_) => 5.5,
+         Item::ShareQr => QR_SIZE + 4.0,
+         Item::Gap => 4.0,
+      }
+   }
+}
+
+/// Byte ranges of the lines of at most `width` characters `text` splits into at whitespace
+fn wrap_ranges(text: &str, width: usize) -> Vec<Range<usize>> {
+   let mut lines = Vec::new();
+   let mut line: Option<(Range<usize>, usize)> = None;
+   let mut word_start = None;
+   for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+      match (c.is_whitespace(), word_start) {
+         (false, None) => word_start = Some(i),
+         (true, Some(start)) => {
+            word_start = None;
+            let chars = text[start..i].chars().count();
+            line = match line.take() {
+               Some((range, count)) if count + 1 + chars <= width => {
+                  Some((range.start..i, count + 1 + chars))
+               }
+               Some((range, _)) => {
+                  lines.push(range);
+                  Some((start..i, chars))
+               }
+               None => Some((start..i, chars)),
+            };
+         }
+         _ => {}
+      }
+   }
+   lines.extend(line.map(|(range, _)| range));
+   lines
+}
+
+/// Split `text` in lines of at most `width` characters at spaces
+fn wrap(text: &str, width: usize) -> Vec<String> {
+   wrap_ranges(text, width)
+      .into_iter()
+      .map(|range| text[range].split_whitespace().collect::<Vec<_>>().join(" "))
+      .collect()
+}
+
+fn escape_xml(text: &str) -> String {
+   text
+      .replace('&', "&amp;")
+      .replace('<', "&lt;")
+      .replace('>', "&gt;")
+      .replace('"', "&quot;")
+}
+
+impl RecoveryKit {
+   fn items(&self) -> Result<Vec<Item>, Box<dyn std::error::Error>> {
+      let mut items = vec![
+         Item::Heading(String::from("NoPassPlz recovery kit")),
+         Item::Text(format!(
+            "Created on {}",
+            format_timestamp(self.created_at)
+         )),
+         Item::Gap,
+      ];
+      let text = "Every password can be derived again with the NoPassPlz app. Install it, choose \
+         the Argon2 parameters below in the settings and log in with the master username and \
+         password, or press Recover from shares and enter the needed recovery shares. Then \
+         open Data, Recovery kit and check that the fingerprint matches the one printed here. \
+         The password of each account is the one at its index. If you log in with a keyfile \
+         a copy of it is needed too.";
+      for line in wrap(text, 90) {
+         items.push(Item::Text(line));
+      }
+      items.push(Item::Gap);
+
+      items.push(Item::Heading(String::from("Derivation")));
+      let argon2 = &self.argon2;
+      items.extend([
+         Item::Mono(format!(
+            "Scheme version   {}",
+            self.scheme.version()
+         )),
+         Item::Mono(format!(
+            "Salt             {}",
+            self.scheme.salt_description()
+         )),
+         Item::Mono(format!("Memory cost      {} KiB", argon2.m_cost)),
+         Item::Mono(format!("Time cost        {}", argon2.t_cost)),
+         Item::Mono(format!("Parallelism      {}", argon2.p_cost)),
+         Item::Mono(format!(
+            "Hash length      {} bytes",
+            argon2.hash_length
+         )),
+         Item::Mono(format!("Fingerprint      {}", self.fingerprint)),
+         Item::Gap,
+      ]);
+
+      if let Some(words) = &self.share {
+         let share = words.unlock_str(Share::from_words)?;
+         items.push(Item::Heading(format!(
+            "Recovery share {}, {} are needed",
+            share.x, share.threshold
+         )));
+
+         items.push(Item::ShareQr);
+         let lines = words.unlock_str(|words| wrap_ranges(words, 72));
+         items.extend(lines.into_iter().map(Item::ShareLine));
+
+         let text = "Anyone holding the needed number of shares can derive every password, \
+            keep this page as safe as the other shares.";
+         for line in wrap(text, 90) {
+            items.push(Item::Text(line));
+         }
+         items.push(Item::Gap);
+      }
+
+      items.push(Item::Heading(format!(
+         "Entries ({})",
+         self.entries.len()
+      )));
+      for (index, title) in &self.entries {
+         let mut lines = wrap(title, 65).into_iter();
+         let first = lines.next().unwrap_or_default();
+         items.push(Item::Mono(format!("{:>10}   {}", index, first)));
+         for line in lines {
+            items.push(Item::Mono(format!("{:>10}   {}", "", line)));
+         }
+      }
+
+      Ok(items)
+   }
+
+   /// The kit as one SVG document per page
+   pub fn pages(&self) -> Result<Vec<SecretBuf>, Box<dyn std::error::Error>> {
+      let items = self.items()?;
+
+      // Where each item ends on its page
+      let mut layout: Vec<Vec<(f32, &Item)>> = vec![Vec::new()];
+      let mut y = MARGIN;
+      for item in &items {
+         if y + item.height() > PAGE_HEIGHT - MARGIN {
+            layout.push(Vec::new());
+            y = MARGIN;
+         }
+
+         y += item.height();
+         layout.last_mut().expect("a page").push((y, item));
+      }
+
+      let count = layout.len();
+      let mut pages = Vec::with_capacity(count);
+      for (i, page) in layout.iter().enumerate() {
+         pages.push(self.render_page(page, i + 1, count)?);
+      }
+      Ok(pages)
+   }
+
+   fn render_page(
+      &self,
+      items: &[(f32, &Item)],
+      page: usize,
+      count: usize,
+   ) -> Result<SecretBuf, Box<dyn std::error::Error>> {
+      let mut out = SecretBuf::new();
+      write!(
+         out,
+         "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}mm\" height=\"{h}mm\" \
+         viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n",
+         w = PAGE_WIDTH,
+         h = PAGE_HEIGHT,
+      )?;
+
+      for (y, item) in items {
+         let y = *y;
+         match item {
+            Item::Heading(text) => writeln!(
+               out,
+               "<text x=\"{}\" y=\"{:.1}\" font-size=\"6\" font-weight=\"bold\">{}</text>",
+               MARGIN,
+               y - 2.0,
+               escape_xml(text)
+            )?,
+            Item::Text(text) => writeln!(
+               out,
+               "<text x=\"{}\" y=\"{:.1}\" font-size=\"3.5\">{}</text>",
+               MARGIN,
+               y - 1.5,
+               escape_xml(text)
+            )?,
+            Item::Mono(text) => write_mono(&mut out, y, &escape_xml(text))?,
+            // Share words are plain lowercase words, nothing to escape
+            Item::ShareLine(range) => {
+               let share = self.share.as_ref().ok_or("No share on the kit")?;
+               share.unlock_str(|words| write_mono(&mut out, y, &words[range.clone()]))?;
+            }
+            Item::ShareQr => {
+               let share = self.share.as_ref().ok_or("No share on the kit")?;
+               let top = y - item.height();
+               share.unlock_str(|words| write_qr_path(&mut out, words.as_bytes(), MARGIN, top))?;
+            }
+            Item::Gap => {}
+         }
+      }
+
+      write!(
+         out,
+         "<text x=\"{x}\" y=\"{y}\" font-size=\"3\" text-anchor=\"end\">\
+         Page {page} of {count}</text>\n</svg>\n",
+         x = PAGE_WIDTH - MARGIN,
+         y = PAGE_HEIGHT - MARGIN / 2.0,
+      )?;
+      Ok(out)
+   }
+}
+
+fn write_mono(out: &mut SecretBuf, y: f32, text: &str) -> std::io::Result<()> {
+   writeln!(
+      out,
+      "<text x=\"{}\" y=\"{:.1}\" font-size=\"3.5\" font-family=\"monospace\" \
+      xml:space=\"preserve\">{}</text>",
+      MARGIN,
+      y - 1.5,
+      text
+   )
+}
+
+/// Dark modules of the QR code of `data` as one path, with a quiet zone of 4 modules
+///
+/// The modules are wiped once drawn, the buffers the qrcode crate uses while encoding
+/// are out of our reach.
+fn write_qr_path(
+   out: &mut SecretBuf,
+   data: &[u8],
+   x: f32,
+   y: f32,
+) -> Result<(), Box<dyn std::error::Error>> {
+   let code = QrCode::new(data)?;
+   let modules = code.width();
+   let module_size = QR_SIZE / (modules + 8) as f32;
+   let mut colors = code.into_colors();
+
+   let mut res = write!(out, "<path d=\"");
+   for (i, _) in colors.iter().enumerate().filter(|(_, color)| **color == QrColor::Dark) {
+      let column = (i % modules + 4) as f32 * module_size;
+      let row = (i / modules + 4) as f32 * module_size;
+      res = res.and_then(|_| {
+         write!(
+            out,
+            "M{:.3} {:.3}h{s:.3}v{s:.3}h-{s:.3}z",
+            x + column,
+            y + row,
+            s = module_size
+         )
+      });
+   }
+   colors.fill(QrColor::Light);
+   std::hint::black_box(&colors);
+
+   res?;
+   writeln!(out, "\" fill=\"black\"/>")?;
+   Ok(())
+}
+
+/// Write the pages to `path`, or to `path` with `-1`, `-2`.. added to the name if there are
+/// several, returns the files written
+pub fn save_kit(
+   kit: &RecoveryKit,
+   path: &Path,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+   let pages = kit.pages()?;
+
+   let paths: Vec<PathBuf> = if pages.len() == 1 {
+      vec![path.to_path_buf()]
+   } else {
+      let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("NoPassPlz-recovery-kit");
+      (1..=pages.len())
+         .map(|page| path.with_file_name(format!("{}-{}.svg", stem, page)))
+         .collect()
+   };
+
+   for (page, path) in pages.iter().zip(&paths) {
+      page
+         .write_private(path)
+         .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+   }
+   Ok(paths)
+}
+
+fn build_kit(
+   app: &AppCtx,
+   share: Option<SecureString>,
+) -> Result<RecoveryKit, Box<dyn std::error::Error>> {
+   let (deriver, mut entries) = app.read(|app| {
+      let entries: Vec<(u32, String)> =
+         app.index_map.iter().map(|(index, data)| (*index, data.title.clone())).collect();
+      (app.passwd_derive.clone(), entries)
+   });
+   let deriver = deriver.ok_or("Log in first")?;
+   let scheme = app.read(|app| app.scheme())?;
+   entries.sort_by_key(|(index, _)| *index);
+
+   Ok(RecoveryKit {
+      argon2: deriver.argon2.clone(),
+      scheme,
+      fingerprint: deriver.fingerprint()?,
+      entries,
+      share,
+      created_at: unix_now(),
+   })
+}
+
+/// Save the recovery kit, with a share when opened from the recovery shares
+pub struct KitWindow {
+   open: bool,
+   path: String,
+   share: Option<SecureString>,
+   fingerprint: Option<String>,
+   saving: bool,
+}
+
+impl KitWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         path: String::from("NoPassPlz-recovery-kit.svg"),
+         share: None,
+         fingerprint: None,
+         saving: false,
+      }
+   }
+
+   pub fn open(&mut self, share: Option<SecureString>) {
+      self.close();
+      self.open = true;
+      self.share = share;
+   }
+
+   /// Close and erase the share
+   pub fn close(&mut self) {
+      if let Some(share) = self.share.as_mut() {
+         share.erase();
+      }
+      self.share = None;
+      self.open = false;
+   }
+
+   /// Called from the background thread once the files are written
+   pub fn finish(&mut self) {
+      self.saving = false;
+      self.close();
+   }
+
+   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      if !self.open {
+         return;
+      }
+
+      if self.fingerprint.is_none() {
+         let deriver = app.read(|app| app.passwd_derive.clone());
+         self.fingerprint = deriver.and_then(|deriver| deriver.fingerprint().ok());
+      }
+
+      Window::new("Recovery kit")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(400.0);
+
+               let text = RichText::new("Recovery kit").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               let text = "Printable pages with the Argon2 parameters, the fingerprint of your \
+               credentials and the index of every entry, so someone you trust can get your \
+               accounts back with the app in an emergency. No password is printed.";
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               if let Some(fingerprint) = &self.fingerprint {
+                  let text = RichText::new(format!("Credential fingerprint {}", fingerprint))
+                     .font(FontId::monospace(theme.text_sizes.normal));
+                  ui.label(text);
+               }
+
+               if self.share.is_some() {
+                  let text = "The kit includes the recovery share, store it like the other \
+                  shares.";
+                  let text = RichText::new(text).size(theme.text_sizes.small);
+                  ui.label(text.color(theme.colors.warning));
+               }
+
+               let text_edit = TextEdit::singleline(&mut self.path)
+                  .font(FontId::proportional(theme.text_sizes.normal))
+                  .desired_width(ui.available_width() * 0.9)
+                  .hint_text("Path of the kit");
+               ui.add(text_edit);
+
+               if self.saving {
+                  ui.spinner();
+               }
+
+               ui.horizontal(|ui| {
+                  ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+                  let ready = !self.saving && !self.path.trim().is_empty();
+                  let text = RichText::new("Save").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add_enabled(ready, button).clicked() {
+                     self.start(app.clone());
+                  }
+
+                  let text = RichText::new("Close").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     self.close();
+                  }
+               });
+            });
+         });
+   }
+
+   fn start(&mut self, app: AppCtx) {
+      self.saving = true;
+      let share = self.share.clone();
+      let path = PathBuf::from(self.path.trim());
+
+      std::thread::spawn(move || {
+         let res = build_kit(&app, share).and_then(|kit| save_kit(&kit, &path));
+         SHARED_GUI.write(|gui| {
+            gui.home.kit.finish();
+            match res {
+               Ok(paths) => {
+                  let paths: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+                  gui.msg_window.open("Success", format!("Saved {}", paths.join(", ")));
+               }
+               Err(e) => gui.msg_window.open("Error", e.to_string()),
+            }
+            gui.request_repaint();
+         });
+      });
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use passwd_derive::{PasswordDeriver, random_seed};
+
+   #[test]
+   fn test_kit_pages() {
+      let deriver = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      let shares = deriver.split_seed(2, 3).unwrap();
+
+      let mut kit = RecoveryKit {
+         argon2: deriver.argon2.clone(),
+         scheme: Scheme::V2,
+         fingerprint: deriver.fingerprint().unwrap(),
+         entries: vec![(0, String::from("Mail <work> & co"))],
+         share: Some(SecureString::from(shares[1].to_words())),
+         created_at: 0,
+      };
+
+      let pages = kit.pages().unwrap();
+      assert_eq!(pages.len(), 1);
+      assert!(pages[0].as_str().contains("Mail &lt;work&gt; &amp; co"));
+      assert!(pages[0].as_str().contains(&kit.fingerprint));
+      assert!(pages[0].as_str().contains("Scheme version   2"));
+      assert!(pages[0].as_str().contains("Recovery share 2, 2 are needed"));
+      assert!(pages[0].as_str().contains("<path d=\"M"));
+      let words = shares[1].to_words();
+      for line in wrap(&words, 72) {
+         assert!(pages[0].as_str().contains(&line));
+      }
+
+      // The entries flow over to more pages
+      kit.share = None;
+      kit.entries = (0..200).map(|i| (i, format!("Account {}", i))).collect();
+      let pages = kit.pages().unwrap();
+      assert!(pages.len() > 2);
+      assert!(!pages[0].as_str().contains("<path"));
+      assert!(pages.last().unwrap().as_str().contains("Account 199"));
+   }
+}
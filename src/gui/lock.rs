@@ -0,0 +1,71 @@
+use eframe::egui::Context;
+use std::time::{Duration, Instant};
+
+/// Idle timeouts in minutes the user can choose from, 0 means never
+pub const IDLE_TIMEOUTS: [u64; 6] = [0, 1, 5, 10, 15, 30];
+
+pub fn idle_timeout_to_str(minutes: u64) -> String {
+   match minutes {
+      0 => String::from("Never"),
+      1 => String::from("1 minute"),
+      m => format!("{} minutes", m),
+   }
+}
+
+/// Decides when an unlocked app should lock itself
+pub struct AutoLock {
+   pub idle_timeout: Duration,
+   pub lock_on_minimize: bool,
+   pub lock_on_focus_loss: bool,
+   last_activity: Instant,
+}
+
+impl AutoLock {
+   pub fn new() -> Self {
+      Self {
+         idle_timeout: Duration::from_secs(10 * 60),
+         lock_on_minimize: false,
+         lock_on_focus_loss: false,
+         last_activity: Instant::now(),
+      }
+   }
+
+   /// Start counting the idle time from now
+   pub fn reset(&mut self) {
+      self.last_activity = Instant::now();
+   }
+
+   /// Returns true if the app should be locked, call this every frame while unlocked
+   pub fn should_lock(&mut self, ctx: &Context) -> bool {
+      let (active, minimized, focused) = ctx.input(|i| {
+         let active = !i.events.is_empty() || i.pointer.is_moving();
+         let viewport = i.viewport();
+         (active, viewport.minimized, viewport.focused)
+      });
+
+      if active {
+         self.last_activity = Instant::now();
+      }
+
+      if self.lock_on_minimize && minimized == Some(true) {
+         return true;
+      }
+
+      if self.lock_on_focus_loss && focused == Some(false) {
+         return true;
+      }
+
+      if self.idle_timeout.is_zero() {
+         return false;
+      }
+
+      let idle = self.last_activity.elapsed();
+      if idle >= self.idle_timeout {
+         return true;
+      }
+
+      // Make sure we wake up to lock even if nothing happens
+      ctx.request_repaint_after(self.idle_timeout - idle);
+      false
+   }
+}
@@ -0,0 +1,552 @@
+use super::{
+   AppCtx, SHARED_GUI,
+   app::{AppData, IndexData, Tombstone, format_timestamp},
+};
+use eframe::egui::{
+   Align2, Button, FontId, Grid, RichText, ScrollArea, TextEdit, Ui, Window, vec2,
+};
+use std::{collections::HashMap, path::Path};
+use zeus_theme::Theme;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+   Ours,
+   Theirs,
+}
+
+/// What one of the files holds at an index
+#[derive(Clone)]
+pub enum Slot {
+   Entry(IndexData),
+   Deleted(Tombstone),
+}
+
+impl Slot {
+   /// When this side last changed
+   fn changed_at(&self) -> u64 {
+      match self {
+         Slot::Entry(data) => data.modified_at.max(data.created_at),
+         Slot::Deleted(tombstone) => tombstone.deleted_at,
+      }
+   }
+
+   pub fn summary(&self) -> String {
+      match self {
+         Slot::Entry(data) => {
+            let mut text = data.title.clone();
+            if !data.username.is_empty() {
+               text.push_str(&format!(" ({})", data.username));
+            }
+            if data.exposed {
+               text.push_str(", exposed");
+            }
+            format!(
+               "{}\nModified {}",
+               text,
+               format_timestamp(data.modified_at)
+            )
+         }
+         Slot::Deleted(tombstone) => format!(
+            "{}\nDeleted {}",
+            tombstone.entry.title,
+            format_timestamp(tombstone.deleted_at)
+         ),
+      }
+   }
+}
+
+/// An index the two files disagree on
+#[derive(Clone)]
+pub struct Conflict {
+   pub index: u32,
+   pub ours: Slot,
+   pub theirs: Slot,
+   /// Defaults to the side modified last
+   pub pick: Side,
+}
+
+/// The union of two data files, with the conflicts left to resolve
+#[derive(Clone, Default)]
+pub struct MergePlan {
+   pub index_map: HashMap<u32, IndexData>,
+   pub tombstones: HashMap<u32, Tombstone>,
+   pub conflicts: Vec<Conflict>,
+   /// Indices only the other file had
+   pub added: usize,
+   /// Indices where the other file had the newer copy
+   pub updated: usize,
+   /// The derivation scheme both files use, see [AppData::scheme]
+   pub scheme_version: Option<u32>,
+}
+
+impl MergePlan {
+   /// Union `theirs` into `ours` by index
+   ///
+   /// Fails if the files use different derivation schemes, the same index derives a different
+   /// password in each.
+   pub fn new(ours: &AppData, theirs: &AppData) -> Result<Self, String> {
+      let scheme = ours.scheme().map_err(|e| e.to_string())?;
+      let their_scheme = theirs.scheme().map_err(|e| e.to_string())?;
+      if scheme != their_scheme {
+         return Err(format!(
+            "This data file uses the {} derivation scheme and the other one {}, \
+            the same index derives another password in each so they can't be merged",
+            scheme.to_str(),
+            their_scheme.to_str()
+         ));
+      }
+
+      let mut plan = Self {
+         scheme_version: Some(scheme.version()),
+         ..Default::default()
+      };
+
+      let mut indices: Vec<u32> = ours
+         .index_map
+         .keys()
+         .chain(ours.tombstones.keys())
+         .chain(theirs.index_map.keys())
+         .chain(theirs.tombstones.keys())
+         .copied()
+         .collect();
+      indices.sort();
+      indices.dedup();
+
+      for index in indices {
+         let ours = slot_at(ours, index);
+         let theirs = slot_at(theirs, index);
+
+         match (ours, theirs) {
+            (Some(ours), None) => plan.insert(index, ours),
+            (None, Some(theirs)) => {
+               plan.added += 1;
+               plan.insert(index, theirs);
+            }
+            (Some(Slot::Entry(ours)), Some(Slot::Entry(theirs))) => {
+               if ours.title != theirs.title || ours.exposed != theirs.exposed {
+                  plan.conflict(index, Slot::Entry(ours), Slot::Entry(theirs));
+                  continue;
+               }
+
+               if theirs.modified_at > ours.modified_at {
+                  plan.updated += 1;
+               }
+               plan.insert(index, Slot::Entry(merge_entries(ours, theirs)));
+            }
+            (Some(Slot::Deleted(ours)), Some(Slot::Deleted(theirs))) => {
+               let newest = match theirs.deleted_at > ours.deleted_at {
+                  true => theirs,
+                  false => ours,
+               };
+               plan.insert(index, Slot::Deleted(newest));
+            }
+            // Deleted on one side only
+            (Some(ours), Some(theirs)) => plan.conflict(index, ours, theirs),
+            (None, None) => {}
+         }
+      }
+
+      Ok(plan)
+   }
+
+   fn insert(&mut self, index: u32, slot: Slot) {
+      match slot {
+         Slot::Entry(data) => {
+            self.index_map.insert(index, data);
+         }
+         Slot::Deleted(tombstone) => {
+            self.tombstones.insert(index, tombstone);
+         }
+      }
+   }
+
+   fn conflict(&mut self, index: u32, ours: Slot, theirs: Slot) {
+      let pick = match theirs.changed_at() > ours.changed_at() {
+         true => Side::Theirs,
+         false => Side::Ours,
+      };
+
+      self.conflicts.push(Conflict {
+         index,
+         ours,
+         theirs,
+         pick,
+      });
+   }
+
+   /// Take the picks the user made on a plan computed earlier
+   ///
+   /// Fails if the conflicts are not the ones the user reviewed, because either file changed
+   /// since.
+   pub fn take_picks(&mut self, reviewed: &MergePlan) -> Result<(), String> {
+      let same = self.conflicts.len() == reviewed.conflicts.len()
+         && self.conflicts.iter().zip(&reviewed.conflicts).all(|(a, b)| {
+            a.index == b.index
+               && a.ours.changed_at() == b.ours.changed_at()
+               && a.theirs.changed_at() == b.theirs.changed_at()
+         });
+      if !same {
+         return Err(String::from(
+            "The entries changed since the other file was loaded, load it again",
+         ));
+      }
+
+      for (conflict, picked) in self.conflicts.iter_mut().zip(&reviewed.conflicts) {
+         conflict.pick = picked.pick;
+      }
+      Ok(())
+   }
+
+   /// The merged index map and tombstones, with every conflict resolved as picked
+   pub fn resolve(mut self) -> (HashMap<u32, IndexData>, HashMap<u32, Tombstone>) {
+      for conflict in std::mem::take(&mut self.conflicts) {
+         let slot = match conflict.pick {
+            Side::Ours => conflict.ours,
+            Side::Theirs => conflict.theirs,
+         };
+         self.insert(conflict.index, slot);
+      }
+
+      (self.index_map, self.tombstones)
+   }
+}
+
+fn slot_at(data: &AppData, index: u32) -> Option<Slot> {
+   if let Some(entry) = data.index_map.get(&index) {
+      return Some(Slot::Entry(entry.clone()));
+   }
+   data.tombstones.get(&index).cloned().map(Slot::Deleted)
+}
+
+/// Keep the newest copy, the usage and review timestamps come from both
+fn merge_entries(ours: IndexData, theirs: IndexData) -> IndexData {
+   let (mut newest, other) = match theirs.modified_at > ours.modified_at {
+      true => (theirs, ours),
+      false => (ours, theirs),
+   };
+
+   newest.created_at = match (newest.created_at, other.created_at) {
+      (0, created) | (created, 0) => created,
+      (a, b) => a.min(b),
+   };
+   newest.last_used_at = newest.last_used_at.max(other.last_used_at);
+   newest.rotated_at = newest.rotated_at.max(other.rotated_at);
+   newest.reviewed_at = newest.reviewed_at.max(other.reviewed_at);
+   newest
+}
+
+/// Read a data file without touching the one in use
+pub fn load_data_file(path: &Path) -> Result<AppData, Box<dyn std::error::Error>> {
+   let mut data = AppData {
+      data_file: path.to_path_buf(),
+      ..Default::default()
+   };
+   data
+      .load_index_map_from_file()
+      .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+   Ok(data)
+}
+
+/// Merges another data file into the one in use
+pub struct MergeWindow {
+   open: bool,
+   path: String,
+   /// The other file as it was loaded, the plan is computed again from it when merging
+   theirs: Option<AppData>,
+   plan: Option<MergePlan>,
+   error: Option<String>,
+}
+
+impl MergeWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         path: String::new(),
+         theirs: None,
+         plan: None,
+         error: None,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+      self.plan = None;
+      self.error = None;
+   }
+
+   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      if !self.open {
+         return;
+      }
+
+      Window::new("Merge")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(500.0);
+
+               let text = RichText::new("Merge").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               let text = "Brings the entries of another NoPassPlz.json, for example from \
+               another computer, into this one. Where both files changed the same index, \
+               pick the copy to keep.";
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               ui.horizontal(|ui| {
+                  ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+
+                  let text_edit = TextEdit::singleline(&mut self.path)
+                     .font(FontId::proportional(theme.text_sizes.normal))
+                     .desired_width(380.0)
+                     .hint_text("Path of the other data file");
+                  ui.add(text_edit);
+
+                  let text = RichText::new("Load").size(theme.text_sizes.normal);
+                  if ui.button(text).clicked() {
+                     self.load(app.clone());
+                  }
+               });
+
+               if let Some(error) = &self.error {
+                  let text = RichText::new(error).size(theme.text_sizes.normal);
+                  ui.label(text.color(theme.colors.error));
+               }
+
+               if let Some(plan) = &mut self.plan {
+                  Self::show_plan(plan, theme, ui);
+               }
+
+               ui.horizontal(|ui| {
+                  ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+                  let text = RichText::new("Merge").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add_enabled(self.plan.is_some(), button).clicked() {
+                     self.merge(app.clone());
+                  }
+
+                  let text = RichText::new("Close").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     *self = Self::new();
+                  }
+               });
+            });
+         });
+   }
+
+   fn load(&mut self, app: AppCtx) {
+      self.theirs = None;
+      self.plan = None;
+      self.error = None;
+
+      let path = Path::new(self.path.trim());
+      let same_file = app.read(
+         |app| match (path.canonicalize(), app.data_file.canonicalize()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+         },
+      );
+      if same_file {
+         self.error = Some(String::from(
+            "This is the data file already in use",
+         ));
+         return;
+      }
+
+      match load_data_file(path) {
+         Ok(theirs) => match app.read(|ours| MergePlan::new(ours, &theirs)) {
+            Ok(plan) => {
+               self.plan = Some(plan);
+               self.theirs = Some(theirs);
+            }
+            Err(e) => self.error = Some(e),
+         },
+         Err(e) => self.error = Some(e.to_string()),
+      }
+   }
+
+   fn show_plan(plan: &mut MergePlan, theme: &Theme, ui: &mut Ui) {
+      let text = format!(
+         "{} new, {} updated, {} conflicts",
+         plan.added,
+         plan.updated,
+         plan.conflicts.len()
+      );
+      ui.label(RichText::new(text).size(theme.text_sizes.normal));
+
+      if plan.conflicts.is_empty() {
+         return;
+      }
+
+      let size = theme.text_sizes.small;
+      ScrollArea::vertical().max_height(250.0).show(ui, |ui| {
+         Grid::new("merge_conflicts")
+            .striped(true)
+            .spacing(vec2(12.0, 6.0))
+            .show(ui, |ui| {
+               ui.label(RichText::new("Index").size(size).strong());
+               ui.label(RichText::new("This file").size(size).strong());
+               ui.label(RichText::new("Other file").size(size).strong());
+               ui.end_row();
+
+               for conflict in plan.conflicts.iter_mut() {
+                  ui.label(RichText::new(conflict.index.to_string()).size(size));
+
+                  let text = RichText::new(conflict.ours.summary()).size(size);
+                  ui.selectable_value(&mut conflict.pick, Side::Ours, text);
+
+                  let text = RichText::new(conflict.theirs.summary()).size(size);
+                  ui.selectable_value(&mut conflict.pick, Side::Theirs, text);
+                  ui.end_row();
+               }
+            });
+      });
+   }
+
+   fn merge(&mut self, app: AppCtx) {
+      let (Some(reviewed), Some(theirs)) = (self.plan.take(), self.theirs.take()) else {
+         return;
+      };
+      *self = Self::new();
+
+      std::thread::spawn(move || {
+         // Planned again under the lock so nothing changed since Load is lost
+         let res = app.write(|app| {
+            let mut plan = MergePlan::new(app, &theirs)?;
+            plan.take_picks(&reviewed)?;
+
+            let (index_map, tombstones) = plan.resolve();
+            let map = std::mem::replace(&mut app.index_map, index_map);
+            let tombs = std::mem::replace(&mut app.tombstones, tombstones);
+            Ok::<_, String>((map, tombs))
+         });
+
+         let previous = match res {
+            Ok(previous) => previous,
+            Err(e) => {
+               SHARED_GUI.write(|gui| gui.msg_window.open("Error", e));
+               return;
+            }
+         };
+
+         match app.save_index_map_to_file() {
+            Ok(()) => SHARED_GUI.write(|gui| {
+               gui.home.audit.invalidate();
+               gui.msg_window.open("Success", "The data files were merged");
+            }),
+            Err(e) => {
+               app.write(|app| (app.index_map, app.tombstones) = previous);
+               SHARED_GUI.write(|gui| gui.msg_window.open("Error", e.to_string()));
+            }
+         }
+      });
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_union_and_newest_wins() {
+      let mut ours = AppData::default();
+      ours.index_map.insert(0, IndexData::titled("Mail").modified_at(10));
+      ours.index_map.insert(1, IndexData::titled("Bank").modified_at(30));
+      ours.index_map.get_mut(&1).unwrap().last_used_at = 50;
+
+      let mut theirs = AppData::default();
+      theirs.index_map.insert(1, IndexData::titled("Bank").modified_at(40));
+      theirs.index_map.get_mut(&1).unwrap().description = String::from("new notes");
+      theirs.index_map.insert(2, IndexData::titled("Forum").modified_at(5));
+
+      let plan = MergePlan::new(&ours, &theirs).unwrap();
+      assert!(plan.conflicts.is_empty());
+      assert_eq!((plan.added, plan.updated), (1, 1));
+
+      let (map, _) = plan.resolve();
+      assert_eq!(map.len(), 3);
+      assert_eq!(map[&1].description, "new notes");
+      assert_eq!(map[&1].last_used_at, 50);
+   }
+
+   #[test]
+   fn test_conflicts() {
+      let mut ours = AppData::default();
+      ours.index_map.insert(0, IndexData::titled("Mail").modified_at(10));
+      ours.index_map.insert(1, IndexData::titled("Bank").modified_at(30));
+
+      let mut theirs = AppData::default();
+      theirs.index_map.insert(0, IndexData::titled("Work mail").modified_at(20));
+      theirs.tombstones.insert(
+         1,
+         Tombstone {
+            deleted_at: 20,
+            entry: IndexData::titled("Bank").modified_at(10),
+         },
+      );
+
+      let mut plan = MergePlan::new(&ours, &theirs).unwrap();
+      assert_eq!(plan.conflicts.len(), 2);
+      assert_eq!(plan.conflicts[0].pick, Side::Theirs);
+      assert_eq!(plan.conflicts[1].pick, Side::Ours);
+
+      plan.conflicts[1].pick = Side::Theirs;
+      let (map, tombstones) = plan.resolve();
+      assert_eq!(map[&0].title, "Work mail");
+      assert!(!map.contains_key(&1));
+      assert!(tombstones.contains_key(&1));
+   }
+
+   #[test]
+   fn test_take_picks() {
+      let mut ours = AppData::default();
+      ours.index_map.insert(0, IndexData::titled("Mail").modified_at(10));
+      let mut theirs = AppData::default();
+      theirs.index_map.insert(0, IndexData::titled("Work mail").modified_at(20));
+
+      let mut reviewed = MergePlan::new(&ours, &theirs).unwrap();
+      reviewed.conflicts[0].pick = Side::Ours;
+
+      // A change elsewhere keeps the pick
+      ours.index_map.insert(1, IndexData::titled("Bank").modified_at(30));
+      let mut plan = MergePlan::new(&ours, &theirs).unwrap();
+      plan.take_picks(&reviewed).unwrap();
+      let (map, _) = plan.resolve();
+      assert_eq!(map[&0].title, "Mail");
+      assert!(map.contains_key(&1));
+
+      // A change to the conflicting entry needs a new review
+      ours.index_map.get_mut(&0).unwrap().modified_at = 40;
+      let mut plan = MergePlan::new(&ours, &theirs).unwrap();
+      assert!(plan.take_picks(&reviewed).is_err());
+   }
+
+   #[test]
+   fn test_refuse_other_scheme() {
+      let ours = AppData {
+         scheme_version: Some(2),
+         ..Default::default()
+      };
+      let theirs = AppData {
+         scheme_version: Some(1),
+         ..Default::default()
+      };
+      assert!(MergePlan::new(&ours, &theirs).is_err());
+
+      let theirs = AppData {
+         scheme_version: Some(2),
+         ..Default::default()
+      };
+      assert_eq!(
+         MergePlan::new(&ours, &theirs).unwrap().scheme_version,
+         Some(2)
+      );
+   }
+}
@@ -1,7 +1,13 @@
 use eframe::egui::{
-   Align2, Button, Frame, Label, Order, RichText, Spinner, Ui, Vec2, Window, vec2,
+   Align2, Button, Color32, Frame, Label, Order, Rect, RichText, Sense, Spinner, Ui, Vec2, Window,
+   vec2,
+};
+use qrcode::{Color as QrColor, QrCode, types::QrError};
+use secure_types::Zeroize;
+use std::{
+   io::{self, Write},
+   path::Path,
 };
-
 use zeus_theme::Theme;
 
 pub struct LoadingWindow {
@@ -121,3 +127,103 @@ impl MsgWindow {
          });
    }
 }
+
+/// Paint `data` as a QR code, dark modules on a white background with a quiet zone
+///
+/// Nothing is written to disk, the code is built and painted on every call.
+pub fn show_qr_code(ui: &mut Ui, data: &[u8], size: f32) -> Result<(), QrError> {
+   let code = QrCode::new(data)?;
+   let modules = code.width();
+
+   // 4 modules of quiet zone on each side as the spec recommends
+   let module_size = size / (modules + 8) as f32;
+   let (rect, _) = ui.allocate_exact_size(vec2(size, size), Sense::hover());
+   let painter = ui.painter_at(rect);
+
+   painter.rect_filled(rect, 0.0, Color32::WHITE);
+
+   let origin = rect.min + vec2(module_size * 4.0, module_size * 4.0);
+   for (i, color) in code.to_colors().into_iter().enumerate() {
+      if color != QrColor::Dark {
+         continue;
+      }
+
+      let x = (i % modules) as f32 * module_size;
+      let y = (i / modules) as f32 * module_size;
+      let module = Rect::from_min_size(
+         origin + vec2(x, y),
+         vec2(module_size, module_size),
+      );
+      painter.rect_filled(module, 0.0, Color32::BLACK);
+   }
+
+   Ok(())
+}
+
+/// Output buffer for files that hold secrets
+///
+/// Growing it wipes the old allocation and dropping it wipes the content, so the secrets
+/// written into it are never left behind in freed memory.
+#[derive(Default)]
+pub struct SecretBuf(Vec<u8>);
+
+impl SecretBuf {
+   pub fn new() -> Self {
+      Self::default()
+   }
+
+   pub fn push_str(&mut self, text: &str) {
+      self.push_bytes(text.as_bytes());
+   }
+
+   fn push_bytes(&mut self, bytes: &[u8]) {
+      if self.0.capacity() - self.0.len() < bytes.len() {
+         let capacity = (self.0.len() + bytes.len()).max(self.0.capacity() * 2).max(4096);
+         let mut grown = Vec::with_capacity(capacity);
+         grown.extend_from_slice(&self.0);
+         self.0.zeroize();
+         self.0 = grown;
+      }
+      self.0.extend_from_slice(bytes);
+   }
+
+   /// The content as text, empty if something other than text was written
+   #[cfg(test)]
+   pub fn as_str(&self) -> &str {
+      std::str::from_utf8(&self.0).unwrap_or_default()
+   }
+
+   /// Write the content to a file only the current user can read
+   pub fn write_private(&self, path: &Path) -> io::Result<()> {
+      let mut options = std::fs::OpenOptions::new();
+      options.write(true).create(true).truncate(true);
+      #[cfg(unix)]
+      std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+      let mut file = options.open(path)?;
+      // The mode only applies to new files
+      #[cfg(unix)]
+      file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(
+         0o600,
+      ))?;
+      file.write_all(&self.0)?;
+      file.sync_all()
+   }
+}
+
+impl Write for SecretBuf {
+   fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.push_bytes(buf);
+      Ok(buf.len())
+   }
+
+   fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+   }
+}
+
+impl Drop for SecretBuf {
+   fn drop(&mut self) {
+      self.0.zeroize();
+   }
+}
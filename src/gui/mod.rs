@@ -1,19 +1,48 @@
 pub mod app;
+pub mod audit;
 pub mod auth;
+pub mod breach;
+pub mod clipboard;
+pub mod export;
 pub mod home;
+pub mod import;
+pub mod kit;
+pub mod lock;
+pub mod merge;
 pub mod misc;
+pub mod readout;
+pub mod recovery;
+pub mod reveal;
+pub mod search;
+pub mod settings;
+pub mod shortcuts;
+pub mod strength;
+pub mod sync;
+pub mod team;
+pub mod trash;
+pub mod watch;
 
 use app::AppCtx;
 
 use eframe::egui::{
-   Align2, Button, Context, MenuBar, OpenUrl, RichText, ScrollArea, Ui, Window, vec2,
+   Align2, Button, Context, Grid, MenuBar, OpenUrl, RichText, ScrollArea, Ui, Window, vec2,
 };
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 use lazy_static::lazy_static;
-use std::sync::{Arc, RwLock};
+use std::{
+   sync::{Arc, RwLock},
+   time::Duration,
+};
 use zeus_theme::{Theme, ThemeKind};
 
-use super::gui::{auth::*, home::Home, misc::*};
+use super::gui::{
+   auth::*,
+   home::Home,
+   lock::{AutoLock, IDLE_TIMEOUTS, idle_timeout_to_str},
+   misc::*,
+   settings::{Settings, SettingsWindow, ThemeChoice, scaled_theme, set_style},
+   shortcuts::{Action, Keymap},
+};
 
 lazy_static! {
    pub static ref SHARED_GUI: SharedGUI = SharedGUI::default();
@@ -40,6 +69,7 @@ impl Default for SharedGUI {
    }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct GUI {
    pub egui_ctx: Context,
    pub theme: Theme,
@@ -48,6 +78,11 @@ pub struct GUI {
    pub auth: Auth,
    pub msg_window: MsgWindow,
    pub loading_window: LoadingWindow,
+   pub auto_lock: AutoLock,
+   pub keymap: Keymap,
+   /// The settings as last saved to disk
+   pub settings: Settings,
+   pub settings_window: SettingsWindow,
 }
 
 impl Default for GUI {
@@ -60,6 +95,10 @@ impl Default for GUI {
          auth: Auth::new(),
          msg_window: MsgWindow::new(),
          loading_window: LoadingWindow::default(),
+         auto_lock: AutoLock::new(),
+         keymap: Keymap::default(),
+         settings: Settings::default(),
+         settings_window: SettingsWindow::new(),
       }
    }
 }
@@ -77,19 +116,155 @@ impl GUI {
       self.top_menu.show_how_it_works(theme, ui);
       self.top_menu.show_about(theme, ui);
 
-      self.auth.show(app.clone(), theme, ui);
-      self.home.show(app, theme, ui);
+      if self.keymap.pressed(ui.ctx(), Action::CheatSheet) {
+         self.top_menu.shortcuts_open = !self.top_menu.shortcuts_open;
+      }
+      self.top_menu.show_shortcuts(&self.keymap, theme, ui);
+
+      if std::mem::take(&mut self.top_menu.settings_requested) {
+         self.settings_window.open(self.current_settings());
+      }
+      self.settings_window.show(theme, ui);
+
+      self.auth.show(app.clone(), &self.keymap, theme, ui);
+      self.home.show(app.clone(), &self.keymap, theme, ui);
+
+      if let Some(settings) = self.settings_window.saved.take() {
+         self.save_settings(app, settings, ui.ctx());
+      } else {
+         self.persist_settings_changes();
+      }
+   }
+
+   /// The settings with the preferences changed outside of the settings window
+   pub fn current_settings(&self) -> Settings {
+      let mut settings = self.settings.clone();
+      self.home.store_settings(&mut settings);
+      settings.idle_timeout = self.auto_lock.idle_timeout.as_secs() / 60;
+      settings.lock_on_minimize = self.auto_lock.lock_on_minimize;
+      settings.lock_on_focus_loss = self.auto_lock.lock_on_focus_loss;
+      settings
    }
 
-   pub fn show_top_panel(&mut self, ui: &mut Ui) {
+   /// Apply the settings to the running app, does not save them
+   pub fn apply_settings(&mut self, settings: &Settings) {
+      let kind = settings.theme.kind(&self.egui_ctx);
+      self.theme = scaled_theme(kind, settings.text_scale);
+      set_style(&self.egui_ctx, &self.theme);
+      self.egui_ctx.set_zoom_factor(settings.ui_scale);
+
+      self.home.apply_settings(settings);
+      self.auto_lock.idle_timeout = Duration::from_secs(settings.idle_timeout * 60);
+      self.auto_lock.lock_on_minimize = settings.lock_on_minimize;
+      self.auto_lock.lock_on_focus_loss = settings.lock_on_focus_loss;
+      self.auth.set_argon2(settings.argon2_preset.params());
+      self.keymap = settings.keymap.clone();
+   }
+
+   /// Apply and save the settings from the settings window
+   fn save_settings(&mut self, app: AppCtx, settings: Settings, ctx: &Context) {
+      let old_path = self.settings.data_file_path().ok();
+      let new_path = match settings.data_file_path() {
+         Ok(path) => path,
+         Err(e) => {
+            self.msg_window.open("Error", e.to_string());
+            return;
+         }
+      };
+
+      if old_path.as_ref() != Some(&new_path) {
+         // Load the entries from the new file, or move the current ones there
+         if let Err(e) = app.switch_data_file(&new_path) {
+            let msg = format!("Failed to use {}: {}", new_path.display(), e);
+            self.msg_window.open("Error", msg);
+            return;
+         }
+      }
+
+      self.apply_settings(&settings);
+      self.settings = settings;
+
+      if let Err(e) = self.settings.save() {
+         self.msg_window.open(
+            "Error",
+            format!("Failed to save the settings: {}", e),
+         );
+      }
+
+      ctx.request_repaint();
+   }
+
+   /// Switch between light and dark when the desktop does, if the theme is set to System
+   pub fn follow_system_theme(&mut self, ctx: &Context) {
+      if self.settings.theme != ThemeChoice::System {
+         return;
+      }
+
+      let kind = ThemeChoice::System.kind(ctx);
+      if kind != self.theme.kind {
+         self.theme = scaled_theme(kind, self.settings.text_scale);
+         set_style(ctx, &self.theme);
+      }
+   }
+
+   /// Save the preferences changed from the Home screen or the top menu
+   fn persist_settings_changes(&mut self) {
+      let current = self.current_settings();
+      if current == self.settings {
+         return;
+      }
+
+      self.settings = current;
+      if let Err(e) = self.settings.save() {
+         eprintln!("Failed to save the settings {}", e);
+      }
+   }
+
+   pub fn show_top_panel(&mut self, app: AppCtx, ui: &mut Ui) {
       let theme = &self.theme;
-      self.top_menu.show(theme, ui);
+      let unlocked = app.read(|app| app.passwd_derive.is_some());
+      self.top_menu.show(unlocked, &mut self.auto_lock, theme, ui);
+   }
+
+   /// Lock the app if the user asked for it or the auto-lock conditions are met
+   pub fn lock_if_needed(&mut self, app: AppCtx, ctx: &Context) {
+      let unlocked = app.read(|app| app.passwd_derive.is_some());
+      if !unlocked {
+         return;
+      }
+
+      let requested = std::mem::take(&mut self.top_menu.lock_requested)
+         || self.keymap.pressed(ctx, Action::Lock);
+      if requested || self.auto_lock.should_lock(ctx) {
+         self.lock(app, ctx);
+      }
+   }
+
+   /// Erase the seed, clear any sensitive state and go back to the [Auth] screen
+   pub fn lock(&mut self, app: AppCtx, ctx: &Context) {
+      app.write(|app| {
+         if let Some(mut deriver) = app.passwd_derive.take() {
+            deriver.erase();
+         }
+      });
+
+      self.home.lock(ctx);
+      let keyfile_path = self.auth.keyfile_path().to_string();
+      self.auth = Auth::new();
+      self.auth.set_keyfile_path(keyfile_path);
+      self.auth.set_argon2(self.settings.argon2_preset.params());
+      self.request_repaint();
    }
 }
 
 pub struct TopMenu {
    how_it_works_open: bool,
    about_open: bool,
+   /// Set when the user clicks Lock, handled by [GUI::lock_if_needed]
+   pub lock_requested: bool,
+   pub shortcuts_open: bool,
+   /// Set when the user clicks Settings, the window is opened by the [GUI]
+   pub settings_requested: bool,
 }
 
 impl TopMenu {
@@ -97,6 +272,9 @@ impl TopMenu {
       Self {
          how_it_works_open: false,
          about_open: false,
+         lock_requested: false,
+         shortcuts_open: false,
+         settings_requested: false,
       }
    }
 
@@ -104,10 +282,47 @@ impl TopMenu {
       self.about_open = true;
    }
 
-   pub fn show(&mut self, theme: &Theme, ui: &mut Ui) {
+   pub fn show(&mut self, unlocked: bool, auto_lock: &mut AutoLock, theme: &Theme, ui: &mut Ui) {
       MenuBar::new().ui(ui, |ui| {
          ui.spacing_mut().button_padding = vec2(8.0, 8.0);
 
+         if unlocked {
+            let text = RichText::new("Lock").size(theme.text_sizes.normal);
+            if ui.button(text).clicked() {
+               self.lock_requested = true;
+            }
+         }
+
+         let text = RichText::new("Auto-lock").size(theme.text_sizes.normal);
+         ui.menu_button(text, |ui| {
+            ui.spacing_mut().button_padding = vec2(4.0, 4.0);
+
+            let text = RichText::new("Lock when idle for").size(theme.text_sizes.normal);
+            ui.label(text);
+
+            let mut minutes = auto_lock.idle_timeout.as_secs() / 60;
+            for option in IDLE_TIMEOUTS {
+               let label = idle_timeout_to_str(option);
+               let text = RichText::new(label).size(theme.text_sizes.normal);
+               ui.radio_value(&mut minutes, option, text);
+            }
+            auto_lock.idle_timeout = Duration::from_secs(minutes * 60);
+
+            ui.separator();
+
+            let text = RichText::new("Lock when minimized").size(theme.text_sizes.normal);
+            ui.checkbox(&mut auto_lock.lock_on_minimize, text);
+
+            let text =
+               RichText::new("Lock when the window loses focus").size(theme.text_sizes.normal);
+            ui.checkbox(&mut auto_lock.lock_on_focus_loss, text);
+         });
+
+         let text = RichText::new("Settings").size(theme.text_sizes.normal);
+         if ui.button(text).clicked() {
+            self.settings_requested = true;
+         }
+
          let text = RichText::new("Help").size(theme.text_sizes.normal);
 
          ui.menu_button(text, |ui| {
@@ -118,6 +333,11 @@ impl TopMenu {
                self.how_it_works_open = true;
             }
 
+            let text = RichText::new("Keyboard shortcuts").size(theme.text_sizes.normal);
+            if ui.button(text).clicked() {
+               self.shortcuts_open = true;
+            }
+
             let text = RichText::new("About").size(theme.text_sizes.normal);
             if ui.button(text).clicked() {
                self.open_about();
@@ -164,6 +384,48 @@ impl TopMenu {
          });
    }
 
+   pub fn show_shortcuts(&mut self, keymap: &Keymap, theme: &Theme, ui: &mut Ui) {
+      if !self.shortcuts_open {
+         return;
+      }
+
+      Window::new("Keyboard shortcuts")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+
+               let text = RichText::new("Keyboard shortcuts").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               Grid::new("shortcuts_cheat_sheet").striped(true).spacing(vec2(20.0, 6.0)).show(
+                  ui,
+                  |ui| {
+                     for action in Action::to_vec() {
+                        let text = RichText::new(action.to_str()).size(theme.text_sizes.normal);
+                        ui.label(text);
+
+                        let text = RichText::new(keymap.shortcut_text(action))
+                           .size(theme.text_sizes.normal)
+                           .monospace();
+                        ui.label(text);
+                        ui.end_row();
+                     }
+                  },
+               );
+
+               let text = RichText::new("Close").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  self.shortcuts_open = false;
+               }
+            });
+         });
+   }
+
    pub fn show_how_it_works(&mut self, theme: &Theme, ui: &mut Ui) {
       if !self.how_it_works_open {
          return;
@@ -196,16 +458,15 @@ impl TopMenu {
    }
 }
 
-
-const MARKDOWN: &str =
-r"NoPassPlz is a deterministic password generator. Unlike traditional password managers like Bitwarden, your passwords are never stored in the cloud or even locally, they are always derived on-the-fly from your master username and password. Think of it as generating high-entropy passwords from a single set of master credentials.
+const MARKDOWN: &str = r"NoPassPlz is a deterministic password generator. Unlike traditional password managers like Bitwarden, your passwords are never stored in the cloud or even locally, they are always derived on-the-fly from your master username and password. Think of it as generating high-entropy passwords from a single set of master credentials.
 
 ## This is still WIP I may introduce breaking changes in the future.
 
 ## How It Works
 
 Given your master username and password, we first compute a seed using the Argon2id key derivation function (KDF) with these default parameters:
-- **Salt**: SHA3-512 hash of the username
+- **Salt**: SHA3-512 hash of the domain tag `NoPassPlz master seed`, the scheme version and the username
+- **Associated data**: the domain tag and the scheme version
 - **Memory cost**: 8192 MB
 - **Iterations**: 8
 - **Parallelism**: 1
@@ -234,13 +495,66 @@ way to recover it if you forget it you are locked out of your account. With `NoP
 ### Does NoPassPlz store any data?
 
 The only data that is stored locally is the a map of `Index Data` which contains metadata about a password entry on a given index.
-This data doesn't expose any secrets and is stored in a file called `NoPassPlz.json` in the same directory as the executable and is completely safe to make backups of it even unecrypted.
+This data doesn't expose any secrets and is stored in a file called `NoPassPlz.json` in the same directory as the executable (the location can be changed from Settings) and is completely safe to make backups of it even unecrypted. Your preferences are stored separately in `NoPassPlz.settings.json`.
 
 ### What metadata is stored?
 Each entry includes:
 - **Title** (string, required): Where the password is used (e.g., Google Account).
 - **Description** (string, optional): Additional notes (e.g., Main email login).
 - **Exposed** (bool, optional): Flag if the password has ever been compromised (e.g., via a breach).
+- **Username** (string, optional): The login of the account (e.g., me@example.com).
+- **URLs** (list, optional): Where the account is used (e.g., https://accounts.google.com).
+- **Tags** (list, optional): Free-form labels used to group entries (e.g., work, email).
+- **Custom fields** (list, optional): Arbitrary key/value pairs (e.g., a recovery email or an account number).
+- **Timestamps**: When the entry was created, last modified and last used.
+
+### Can I import my entries from another password manager?
+
+Yes, Data > Import reads Bitwarden JSON, KeePass XML and CSV, 1Password CSV and any other CSV export. Only the title, username, URL, notes and folder of each entry are imported, passwords are never read. Each entry gets a fresh index and a report lists the accounts whose password you now need to change to the derived one.
+
+### Can I move my entries to another password manager?
+
+Yes, Data > Export writes a Bitwarden JSON or a KeePass CSV file, or a printable HTML or Markdown emergency sheet. By default only the metadata and the index of each entry are exported, the derived passwords are only included after you confirm the file will hold them in plain text.
+
+### I use NoPassPlz on several computers, how do I keep the data files in sync?
+
+Open Data > Merge and pick the other `NoPassPlz.json`. Entries are matched by index. Where both files changed the same index, the title or the exposed flag differs, or one side deleted the entry, you pick which copy to keep side by side (the one modified last is preselected). The same merge can be run from a terminal with `NoPassPlz merge <data file> <other data file> [--out <file>] [--prefer newer|ours|theirs]`.
+
+If you sync the file with a tool like Syncthing, NoPassPlz reloads it when it changes while the app is open, and never overwrites changes it has not loaded yet.
+
+You can also sync it through git: set a remote in Settings and use Data > Sync with git. The data file is copied into a repository of its own, `NoPassPlz-sync` in the current directory unless you choose another empty folder, which talks to the remote under the name `nopassplz` and never touches an existing `origin`. Local changes are committed, then pulled from and pushed to the remote, and diverged copies are merged by index with the copy modified last winning conflicts. The data file holds no passwords but it does hold your titles, usernames, URLs and notes, so by default the copy in the repository is encrypted with a key derived from your master credentials. Every computer must use the same credentials and the same encryption setting.
+
+### Can my team share passwords without sharing master credentials?
+
+Yes, with a team vault (Data > Team vault). A team has its own random seed that derives the shared passwords, stored in a team file encrypted to the public key of each member. Your public key is derived from your own master credentials, send it to a member so they can add you. Removing a member replaces the team seed: every shared account then needs its new password, and the old one stays available to the remaining members until each account is marked done.
+
+### How do recovery shares work?
+
+`Data -> Recovery shares` splits your master seed, the output of Argon2, into shares with Shamir's secret sharing. You choose how many shares to make and how many are needed, for example any 2 of 3. Each share is shown as a list of words, the last two are a checksum that catches typos, or as a QR code. Every share also records which split it belongs to and a short digest of the seed, so shares of different splits or a wrong share are refused instead of unlocking with the wrong passwords.
+
+Give the shares to people or places you trust, like a family member, a safe and a lawyer. If you forget your master credentials press `Recover from shares` on the login screen and type the needed number of shares, the app unlocks with the same passwords as before. Fewer shares than needed reveal nothing about your seed, but the needed number gives full access to every password, so treat them like your master password.
+
+### What is the recovery kit?
+
+`Data -> Recovery kit` saves printable A4 pages as SVG with what someone needs to get your accounts back with the app: the Argon2 parameters, the derivation scheme version, the fingerprint of your credentials and the index and title of every entry. No password is printed. The fingerprint is shown in the same window, if it matches the printed one after logging in the credentials were typed right.
+
+From `Data -> Recovery shares` you can also print one share on the kit, with its words and a QR code. Keep such a kit as safe as any other share.
+
+### Can I use a keyfile?
+
+Yes, the login screen takes an optional keyfile: any file, typed as a path or dropped on the window. You can also start the app with `NoPassPlz --keyfile <file>` to preselect it. The file is hashed with SHA3-512 and the hash is passed to Argon2 as its secret input, so your passwords can only be derived with the credentials and the exact same file. Someone who learns your master password alone gets nothing.
+
+Losing the keyfile is like forgetting your password, keep a copy somewhere safe. Logging in without a keyfile derives the same passwords as before, and recovery shares already include it.
+
+### What are derivation schemes?
+
+The scheme is how your credentials become the seed. Scheme v2, the one to pick for new credentials, binds the Argon2 salt and associated data to a domain tag and the scheme version, so the same credentials give unrelated seeds in any other app or future scheme. Scheme v1 salted with the SHA3-512 hash of the username alone.
+
+The scheme version is recorded in the data file. Data files from before it was recorded are v1 and keep deriving the same passwords, there is nothing to do. Without a data file the login screen keeps v1 selected so a lost data file never changes your passwords, choose v2 there only when you start with new credentials. Merging or syncing two data files of different schemes is refused, the same index derives another password in each.
+
+### What happens when I delete an entry?
+
+Deleted entries are moved to the trash. The index keeps a record of the title and the deletion date so it is never handed out to a new entry, since the derived password may still be in use somewhere. From the trash you can restore an entry or purge it permanently.
 
 ### What if I lose the `NoPassPlz.json` file?
 
@@ -248,7 +562,7 @@ You can still regenerate passwords by manually entering the correct index in the
 
 ### What if I forget my master credentials?
 
-If you forget your master credentials you are never be able to recover your passwords.
+If you forget your master credentials you are never be able to recover your passwords, unless you created recovery shares beforehand.
 It is important to create a username and a password in your mind that not only it's stored anywhere but also
 not easy to guess, so you can always recover your passwords.
 
@@ -256,7 +570,8 @@ Some tips:
 
 - Username doesn't necessarily need to be something secret or hard to guess, the most important thing is your password.
 - Use a long enough password, at least 20+ characters.
+- Avoid common words, keyboard runs like `qwerty`, years and your username, the strength meter below the password field warns about them.
 - Do not store your password in any digital or physical form, it's should only be stored in your mind.
 - Your master password doesn't have to be a very long sequence of nosense words, you could possible
 create your own seed phrase based on something you know but is not publicly available information or easy to guess.
-";
\ No newline at end of file
+";
@@ -0,0 +1,102 @@
+/// Characters that are easily confused with another one when read or typed
+const AMBIGUOUS: &str = "0Oo1lI|5S2Z8B6G9gqQ`'\"";
+
+const NATO_LETTERS: [&str; 26] = [
+   "Alfa", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf", "Hotel", "India", "Juliett",
+   "Kilo", "Lima", "Mike", "November", "Oscar", "Papa", "Quebec", "Romeo", "Sierra", "Tango",
+   "Uniform", "Victor", "Whiskey", "X-ray", "Yankee", "Zulu",
+];
+
+const NATO_DIGITS: [&str; 10] = [
+   "Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Niner",
+];
+
+pub fn is_ambiguous(c: char) -> bool {
+   AMBIGUOUS.contains(c)
+}
+
+/// Spell a character with the NATO phonetic alphabet
+///
+/// Letters are prefixed with their case so `a` and `A` read differently,
+/// symbols are spelled by name.
+pub fn phonetic(c: char) -> String {
+   if c.is_ascii_digit() {
+      return NATO_DIGITS[c as usize - '0' as usize].to_string();
+   }
+
+   if c.is_ascii_alphabetic() {
+      let word = NATO_LETTERS[c.to_ascii_lowercase() as usize - 'a' as usize];
+      return match c.is_ascii_uppercase() {
+         true => format!("capital {}", word),
+         false => format!("small {}", word),
+      };
+   }
+
+   let name = match c {
+      ' ' => "Space",
+      '-' => "Dash",
+      '_' => "Underscore",
+      '.' => "Dot",
+      ',' => "Comma",
+      ':' => "Colon",
+      ';' => "Semicolon",
+      '!' => "Exclamation mark",
+      '?' => "Question mark",
+      '@' => "At sign",
+      '#' => "Hash",
+      '$' => "Dollar",
+      '%' => "Percent",
+      '^' => "Caret",
+      '&' => "Ampersand",
+      '*' => "Asterisk",
+      '(' => "Left parenthesis",
+      ')' => "Right parenthesis",
+      '[' => "Left bracket",
+      ']' => "Right bracket",
+      '{' => "Left brace",
+      '}' => "Right brace",
+      '<' => "Less than",
+      '>' => "Greater than",
+      '=' => "Equals",
+      '+' => "Plus",
+      '/' => "Slash",
+      '\\' => "Backslash",
+      '|' => "Pipe",
+      '~' => "Tilde",
+      '`' => "Backtick",
+      '\'' => "Apostrophe",
+      '"' => "Quote",
+      _ => return c.to_string(),
+   };
+
+   name.to_string()
+}
+
+/// Split the text in groups of `size` characters
+pub fn groups(text: &str, size: usize) -> Vec<String> {
+   let chars: Vec<char> = text.chars().collect();
+   chars.chunks(size.max(1)).map(|chunk| chunk.iter().collect()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_phonetic() {
+      assert_eq!(phonetic('a'), "small Alfa");
+      assert_eq!(phonetic('Z'), "capital Zulu");
+      assert_eq!(phonetic('9'), "Niner");
+      assert_eq!(phonetic('-'), "Dash");
+   }
+
+   #[test]
+   fn test_groups() {
+      assert_eq!(
+         groups("abcdefghij", 4),
+         vec!["abcd", "efgh", "ij"]
+      );
+      assert!(is_ambiguous('l'));
+      assert!(!is_ambiguous('a'));
+   }
+}
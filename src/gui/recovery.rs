@@ -0,0 +1,301 @@
+//! Recovery shares of the master seed
+//!
+//! The seed is split with [passwd_derive::shamir] into shares that are written down or printed
+//! as QR codes and given to people or places the user trusts. Any `threshold` of them unlock the
+//! app from the Auth screen without the username and password, fewer reveal nothing about the
+//! seed.
+
+use super::misc::show_qr_code;
+use argon2_rs::Argon2;
+use eframe::egui::{Align2, Button, ComboBox, FontId, Margin, RichText, Ui, Window, vec2};
+use passwd_derive::{PasswordDeriver, shamir::Share};
+use secure_types::SecureString;
+use zeus_theme::Theme;
+use zeus_widgets::SecureTextEdit;
+
+/// Most shares that can be made, more is never useful for a personal backup
+const MAX_SHARES: u8 = 10;
+
+/// Capacity of a share input, a share of the seed is 77 words
+const SHARE_CAPACITY: usize = 1024;
+
+/// Rebuild the master seed from shares written with [Share::to_words]
+pub fn recover(shares: &[SecureString], argon2: Argon2) -> Result<PasswordDeriver, String> {
+   let mut parsed = Vec::new();
+   for (i, share) in shares.iter().enumerate() {
+      if share.unlock_str(|share| share.trim().is_empty()) {
+         continue;
+      }
+
+      let res = share.unlock_str(Share::from_words);
+      parsed.push(res.map_err(|e| format!("Share {}: {}", i + 1, e))?);
+   }
+
+   PasswordDeriver::from_shares(&parsed, argon2).map_err(|e| e.to_string())
+}
+
+/// Creates the recovery shares of the unlocked seed
+pub struct SharesWindow {
+   open: bool,
+   threshold: u8,
+   count: u8,
+   /// The user acknowledged what the shares give access to
+   understood: bool,
+   shares: Vec<SecureString>,
+   current: usize,
+   show_qr: bool,
+   error: Option<String>,
+}
+
+impl SharesWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         threshold: 2,
+         count: 3,
+         understood: false,
+         shares: Vec::new(),
+         current: 0,
+         show_qr: false,
+         error: None,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+   }
+
+   pub fn is_open(&self) -> bool {
+      self.open
+   }
+
+   /// Close and erase the shares
+   pub fn close(&mut self) {
+      for share in self.shares.iter_mut() {
+         share.erase();
+      }
+      *self = Self::new();
+   }
+
+   /// Returns a share the user wants printed on the recovery kit
+   pub fn show(
+      &mut self,
+      deriver: Option<PasswordDeriver>,
+      theme: &Theme,
+      ui: &mut Ui,
+   ) -> Option<SecureString> {
+      if !self.open {
+         return None;
+      }
+
+      let Some(deriver) = deriver else {
+         self.close();
+         return None;
+      };
+
+      let mut close = false;
+      let mut to_kit = None;
+      Window::new("Recovery shares")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(500.0);
+
+               let text = RichText::new("Recovery shares").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               if self.shares.is_empty() {
+                  self.show_settings(&deriver, theme, ui);
+               } else {
+                  to_kit = self.show_share(theme, ui);
+               }
+
+               if let Some(error) = &self.error {
+                  let text = RichText::new(error).size(theme.text_sizes.normal);
+                  ui.label(text.color(theme.colors.error));
+               }
+
+               let text = RichText::new("Close").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  close = true;
+               }
+            });
+         });
+
+      if close {
+         self.close();
+      }
+      to_kit
+   }
+
+   fn show_settings(&mut self, deriver: &PasswordDeriver, theme: &Theme, ui: &mut Ui) {
+      let text = "Split your master seed into shares and give them to people or places you \
+         trust. Any of them together up to the number needed unlock all your passwords without \
+         your username and password, fewer reveal nothing.";
+      let text = RichText::new(text).size(theme.text_sizes.normal);
+      ui.label(text);
+
+      ui.horizontal(|ui| {
+         ui.add_space(ui.available_width() / 2.0 - 150.0);
+
+         let text = RichText::new("Needed").size(theme.text_sizes.normal);
+         ui.label(text);
+         ComboBox::from_id_salt("shares_threshold")
+            .selected_text(self.threshold.to_string())
+            .show_ui(ui, |ui| {
+               for threshold in 2..=MAX_SHARES {
+                  ui.selectable_value(
+                     &mut self.threshold,
+                     threshold,
+                     threshold.to_string(),
+                  );
+               }
+            });
+
+         let text = RichText::new("of").size(theme.text_sizes.normal);
+         ui.label(text);
+         ComboBox::from_id_salt("shares_count")
+            .selected_text(self.count.to_string())
+            .show_ui(ui, |ui| {
+               for count in 2..=MAX_SHARES {
+                  ui.selectable_value(&mut self.count, count, count.to_string());
+               }
+            });
+      });
+      self.count = self.count.max(self.threshold);
+
+      let text = RichText::new("I understand that the shares are as powerful as my password")
+         .size(theme.text_sizes.normal);
+      ui.checkbox(&mut self.understood, text);
+
+      let text = RichText::new("Create shares").size(theme.text_sizes.normal);
+      let button = Button::new(text).min_size(vec2(100.0, 25.0));
+      if ui.add_enabled(self.understood, button).clicked() {
+         match deriver.split_seed(self.threshold, self.count) {
+            Ok(shares) => {
+               self.shares =
+                  shares.iter().map(|share| SecureString::from(share.to_words())).collect();
+               self.current = 0;
+               self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+         }
+      }
+   }
+
+   fn show_share(&mut self, theme: &Theme, ui: &mut Ui) -> Option<SecureString> {
+      let text = format!(
+         "Share {} of {}, {} are needed",
+         self.current + 1,
+         self.shares.len(),
+         self.threshold
+      );
+      let text = RichText::new(text).size(theme.text_sizes.large);
+      ui.label(text);
+
+      let share = &self.shares[self.current];
+      if self.show_qr {
+         let res = share.unlock_str(|words| show_qr_code(ui, words.as_bytes(), 300.0));
+         if let Err(e) = res {
+            self.error = Some(format!("Failed to create the QR code: {}", e));
+         }
+      } else {
+         share.unlock_str(|words| {
+            let text = RichText::new(words).font(FontId::monospace(theme.text_sizes.normal));
+            ui.label(text);
+         });
+      }
+
+      let text = RichText::new("Write it down exactly, the last words are a checksum.")
+         .size(theme.text_sizes.small);
+      ui.label(text.color(theme.colors.text_muted));
+
+      ui.horizontal(|ui| {
+         ui.add_space(ui.available_width() / 2.0 - 165.0);
+
+         let text = RichText::new("Previous").size(theme.text_sizes.normal);
+         let button = Button::new(text).min_size(vec2(100.0, 25.0));
+         if ui.add_enabled(self.current > 0, button).clicked() {
+            self.current -= 1;
+         }
+
+         let label = if self.show_qr { "Words" } else { "QR code" };
+         let text = RichText::new(label).size(theme.text_sizes.normal);
+         let button = Button::new(text).min_size(vec2(100.0, 25.0));
+         if ui.add(button).clicked() {
+            self.show_qr = !self.show_qr;
+         }
+
+         let text = RichText::new("Next").size(theme.text_sizes.normal);
+         let button = Button::new(text).min_size(vec2(100.0, 25.0));
+         if ui.add_enabled(self.current + 1 < self.shares.len(), button).clicked() {
+            self.current += 1;
+         }
+      });
+
+      let text = RichText::new("Print on the recovery kit").size(theme.text_sizes.normal);
+      let button = Button::new(text).min_size(vec2(100.0, 25.0));
+      if ui.add(button).clicked() {
+         return Some(self.shares[self.current].clone());
+      }
+      None
+   }
+}
+
+/// Share inputs of the recovery mode on the Auth screen
+pub struct RecoveryForm {
+   pub shares: Vec<SecureString>,
+}
+
+impl RecoveryForm {
+   pub fn new() -> Self {
+      Self {
+         shares: vec![Self::empty_share(), Self::empty_share()],
+      }
+   }
+
+   fn empty_share() -> SecureString {
+      SecureString::new_with_capacity(SHARE_CAPACITY).unwrap()
+   }
+
+   pub fn erase(&mut self) {
+      for share in self.shares.iter_mut() {
+         share.erase();
+      }
+   }
+
+   pub fn show(&mut self, theme: &Theme, ui: &mut Ui) {
+      let text = RichText::new("Recover from shares").size(theme.text_sizes.large);
+      ui.label(text);
+
+      let text = "Type or paste the words of each share, as many as were needed when they \
+         were created.";
+      ui.label(RichText::new(text).size(theme.text_sizes.normal));
+
+      let width = ui.available_width() * 0.6;
+      for (i, share) in self.shares.iter_mut().enumerate() {
+         share.unlock_mut(|share| {
+            let text_edit = SecureTextEdit::multiline(share)
+               .id_salt(("recovery_share", i))
+               .hint_text(format!("Share {}", i + 1))
+               .desired_width(width)
+               .desired_rows(3)
+               .margin(Margin::same(10))
+               .password(false)
+               .font(FontId::proportional(theme.text_sizes.normal));
+            ui.add(text_edit);
+         });
+      }
+
+      let text = RichText::new("Add share").size(theme.text_sizes.normal);
+      let button = Button::new(text).min_size(vec2(100.0, 25.0));
+      if ui.add_enabled(self.shares.len() < MAX_SHARES as usize, button).clicked() {
+         self.shares.push(Self::empty_share());
+      }
+   }
+}
@@ -0,0 +1,321 @@
+use super::{
+   audit::output_format_text,
+   misc::show_qr_code,
+   readout::{groups, is_ambiguous, phonetic},
+};
+use eframe::egui::{
+   Align2, Button, Color32, ComboBox, FontId, RichText, ScrollArea, Stroke, Ui, Window,
+   text::{LayoutJob, TextFormat},
+   vec2,
+};
+use secure_types::SecureString;
+use std::time::{Duration, Instant};
+use zeus_theme::Theme;
+
+/// Group sizes the user can choose from
+const GROUP_SIZES: [usize; 5] = [3, 4, 5, 6, 8];
+
+/// Characters per line when displaying a password
+const CHARS_PER_LINE: usize = 32;
+
+/// Shows a derived password on screen and as a QR code
+///
+/// The password only lives in memory and is erased when the window hides.
+pub struct RevealWindow {
+   index: u32,
+   title: String,
+   password: Option<SecureString>,
+   shown_at: Instant,
+   show_qr: bool,
+   group_size: usize,
+   highlight_ambiguous: bool,
+   /// Show one group at a time, to read it aloud or type it on another device
+   readout: bool,
+   readout_group: usize,
+   spell: bool,
+   pub timeout: Duration,
+}
+
+impl RevealWindow {
+   pub fn new() -> Self {
+      Self {
+         index: 0,
+         title: String::new(),
+         password: None,
+         shown_at: Instant::now(),
+         show_qr: false,
+         group_size: 4,
+         highlight_ambiguous: true,
+         readout: false,
+         readout_group: 0,
+         spell: false,
+         timeout: Duration::from_secs(30),
+      }
+   }
+
+   pub fn open(&mut self, index: u32, title: String, password: SecureString) {
+      self.hide();
+      self.index = index;
+      self.title = title;
+      self.password = Some(password);
+      self.shown_at = Instant::now();
+      self.show_qr = false;
+      self.readout = false;
+      self.readout_group = 0;
+   }
+
+   pub fn hide(&mut self) {
+      if let Some(mut password) = self.password.take() {
+         password.erase();
+      }
+   }
+
+   pub fn show(&mut self, theme: &Theme, ui: &mut Ui) {
+      let Some(mut password) = self.password.take() else {
+         return;
+      };
+
+      let elapsed = self.shown_at.elapsed();
+      if elapsed >= self.timeout {
+         password.erase();
+         return;
+      }
+
+      let remaining = self.timeout - elapsed;
+      ui.ctx().request_repaint_after(Duration::from_secs(1).min(remaining));
+
+      let mut hide = false;
+
+      Window::new("Reveal Password")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+
+               let text = format!("{}. {}", self.index, self.title);
+               let text = RichText::new(text).size(theme.text_sizes.large);
+               ui.label(text);
+
+               let text = password.unlock_str(output_format_text);
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               self.show_options(theme, ui);
+
+               if self.readout {
+                  password.unlock_str(|s| self.show_readout(s, theme, ui));
+               } else {
+                  let job = password.unlock_str(|s| {
+                     colored_chunks(
+                        s,
+                        self.group_size,
+                        self.highlight_ambiguous,
+                        theme,
+                     )
+                  });
+                  ui.label(job);
+
+                  if self.spell {
+                     password.unlock_str(|s| self.show_spelling(s, theme, ui));
+                  }
+               }
+
+               if self.show_qr {
+                  let res = password.unlock_str(|s| show_qr_code(ui, s.as_bytes(), 240.0));
+                  if let Err(e) = res {
+                     let text = format!("Failed to create the QR code: {}", e);
+                     ui.label(RichText::new(text).color(theme.colors.error));
+                  }
+               }
+
+               let secs = remaining.as_millis().div_ceil(1_000);
+               let text = format!("Hiding in {}s", secs);
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               let text = RichText::new("Hide").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  hide = true;
+               }
+            });
+         });
+
+      if hide {
+         password.erase();
+      } else {
+         self.password = Some(password);
+      }
+   }
+}
+
+impl RevealWindow {
+   fn show_options(&mut self, theme: &Theme, ui: &mut Ui) {
+      ui.horizontal(|ui| {
+         ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+
+         let text = RichText::new("Group by").size(theme.text_sizes.normal);
+         ui.label(text);
+
+         let previous = self.group_size;
+         ComboBox::from_id_salt("reveal_group_size")
+            .selected_text(self.group_size.to_string())
+            .width(40.0)
+            .show_ui(ui, |ui| {
+               for size in GROUP_SIZES {
+                  ui.selectable_value(&mut self.group_size, size, size.to_string());
+               }
+            });
+
+         if previous != self.group_size {
+            self.readout_group = 0;
+         }
+
+         let text = RichText::new("Highlight ambiguous").size(theme.text_sizes.normal);
+         ui.checkbox(&mut self.highlight_ambiguous, text);
+      });
+
+      ui.horizontal(|ui| {
+         ui.spacing_mut().item_spacing = vec2(10.0, 0.0);
+
+         let text = RichText::new("Readout mode").size(theme.text_sizes.normal);
+         ui.checkbox(&mut self.readout, text);
+
+         let text = RichText::new("Spell (NATO)").size(theme.text_sizes.normal);
+         ui.checkbox(&mut self.spell, text);
+
+         let text = RichText::new("QR code").size(theme.text_sizes.normal);
+         ui.checkbox(&mut self.show_qr, text);
+      });
+   }
+
+   /// One group at a time, large, with Prev/Next to walk through the password
+   fn show_readout(&mut self, password: &str, theme: &Theme, ui: &mut Ui) {
+      let groups = groups(password, self.group_size);
+      if groups.is_empty() {
+         return;
+      }
+
+      self.readout_group = self.readout_group.min(groups.len() - 1);
+      let group = &groups[self.readout_group];
+
+      let text = format!(
+         "Group {} of {}",
+         self.readout_group + 1,
+         groups.len()
+      );
+      let text = RichText::new(text).size(theme.text_sizes.normal);
+      ui.label(text.color(theme.colors.text_muted));
+
+      let mut job = LayoutJob::default();
+      let font = FontId::monospace(theme.text_sizes.heading * 1.5);
+      for c in group.chars() {
+         append_char(
+            &mut job,
+            c,
+            font.clone(),
+            self.highlight_ambiguous,
+            theme,
+         );
+      }
+      ui.label(job);
+
+      if self.spell {
+         let spelled: Vec<String> = group.chars().map(phonetic).collect();
+         let text = RichText::new(spelled.join(" - ")).size(theme.text_sizes.large);
+         ui.label(text);
+      }
+
+      ui.horizontal(|ui| {
+         ui.add_space(ui.available_width() / 2.0 - 70.0);
+
+         let text = RichText::new("Prev").size(theme.text_sizes.normal);
+         let button = Button::new(text).min_size(vec2(60.0, 25.0));
+         if ui.add_enabled(self.readout_group > 0, button).clicked() {
+            self.readout_group -= 1;
+         }
+
+         let text = RichText::new("Next").size(theme.text_sizes.normal);
+         let button = Button::new(text).min_size(vec2(60.0, 25.0));
+         let has_next = self.readout_group + 1 < groups.len();
+         if ui.add_enabled(has_next, button).clicked() {
+            self.readout_group += 1;
+         }
+      });
+   }
+
+   /// Every group spelled with the NATO alphabet
+   fn show_spelling(&self, password: &str, theme: &Theme, ui: &mut Ui) {
+      ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+         for (i, group) in groups(password, self.group_size).iter().enumerate() {
+            let spelled: Vec<String> = group.chars().map(phonetic).collect();
+            let text = format!("{}. {}  ({})", i + 1, group, spelled.join(" - "));
+            let text = RichText::new(text).size(theme.text_sizes.small);
+            ui.label(text);
+         }
+      });
+   }
+}
+
+/// Color of a character based on its class so similar looking characters are easier to tell apart
+pub fn char_class_color(c: char, theme: &Theme) -> Color32 {
+   if c.is_ascii_digit() {
+      theme.colors.info
+   } else if c.is_ascii_uppercase() {
+      theme.colors.primary
+   } else if c.is_alphabetic() {
+      theme.colors.text
+   } else {
+      theme.colors.warning
+   }
+}
+
+/// Lay out the password in monospace groups, colored by character class
+fn colored_chunks(
+   password: &str,
+   group_size: usize,
+   highlight_ambiguous: bool,
+   theme: &Theme,
+) -> LayoutJob {
+   let font = FontId::monospace(theme.text_sizes.large);
+   let mut job = LayoutJob::default();
+   let per_line = (CHARS_PER_LINE / group_size).max(1) * group_size;
+
+   for (i, c) in password.chars().enumerate() {
+      if i > 0 && i % per_line == 0 {
+         job.append(
+            "\n",
+            0.0,
+            TextFormat::simple(font.clone(), theme.colors.text),
+         );
+      } else if i > 0 && i % group_size == 0 {
+         job.append(
+            " ",
+            0.0,
+            TextFormat::simple(font.clone(), theme.colors.text),
+         );
+      }
+
+      append_char(
+         &mut job,
+         c,
+         font.clone(),
+         highlight_ambiguous,
+         theme,
+      );
+   }
+
+   job
+}
+
+fn append_char(job: &mut LayoutJob, c: char, font: FontId, highlight: bool, theme: &Theme) {
+   let mut format = TextFormat::simple(font, char_class_color(c, theme));
+   if highlight && is_ambiguous(c) {
+      format.underline = Stroke::new(2.0, theme.colors.warning);
+      format.background = theme.colors.bg3;
+   }
+   job.append(c.encode_utf8(&mut [0; 4]), 0.0, format);
+}
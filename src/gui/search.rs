@@ -0,0 +1,253 @@
+use super::app::IndexData;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EntryFilter {
+   All,
+   Exposed,
+   HasEntry,
+   Empty,
+}
+
+impl EntryFilter {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         EntryFilter::All => "All",
+         EntryFilter::Exposed => "Exposed only",
+         EntryFilter::HasEntry => "Has entry",
+         EntryFilter::Empty => "Empty",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![Self::All, Self::Exposed, Self::HasEntry, Self::Empty]
+   }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+   Index,
+   Title,
+   LastUsed,
+   /// Best fuzzy match first, only meaningful with a search query
+   Relevance,
+}
+
+impl SortOrder {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         SortOrder::Index => "Index",
+         SortOrder::Title => "Title",
+         SortOrder::LastUsed => "Last used",
+         SortOrder::Relevance => "Best match",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![Self::Index, Self::Title, Self::LastUsed, Self::Relevance]
+   }
+}
+
+/// What the user is looking for in the Home list
+#[derive(Clone, PartialEq)]
+pub struct EntryQuery {
+   pub text: String,
+   pub filter: EntryFilter,
+   pub tag: Option<String>,
+   pub sort: SortOrder,
+}
+
+impl Default for EntryQuery {
+   fn default() -> Self {
+      Self {
+         text: String::new(),
+         filter: EntryFilter::All,
+         tag: None,
+         sort: SortOrder::Index,
+      }
+   }
+}
+
+impl EntryQuery {
+   /// True if nothing narrows down or reorders the entries,
+   /// in which case every index is listed including the unused ones
+   pub fn is_browsing(&self) -> bool {
+      self.text.trim().is_empty()
+         && self.filter == EntryFilter::All
+         && self.tag.is_none()
+         && self.sort == SortOrder::Index
+   }
+
+   /// Returns the match score of this entry or `None` if it doesn't match
+   pub fn score(&self, data: &IndexData) -> Option<i64> {
+      match self.filter {
+         EntryFilter::Exposed if !data.exposed => return None,
+         EntryFilter::Empty => return None,
+         _ => {}
+      }
+
+      if let Some(tag) = &self.tag
+         && !data.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+      {
+         return None;
+      }
+
+      let query = self.text.trim();
+      if query.is_empty() {
+         return Some(0);
+      }
+
+      // Title matches weigh more than matches in the other fields
+      let mut best = fuzzy_score(query, &data.title).map(|score| score * 2);
+      let others = [&data.username, &data.description]
+         .into_iter()
+         .chain(data.tags.iter())
+         .chain(data.urls.iter());
+
+      for field in others {
+         best = best.max(fuzzy_score(query, field));
+      }
+
+      best
+   }
+
+   /// Filter and sort the entries of this map
+   pub fn apply(&self, index_map: &HashMap<u32, IndexData>) -> Vec<(u32, IndexData)> {
+      let mut matches: Vec<(i64, u32, &IndexData)> = index_map
+         .iter()
+         .filter_map(|(index, data)| self.score(data).map(|score| (score, *index, data)))
+         .collect();
+
+      match self.sort {
+         SortOrder::Index => matches.sort_by_key(|(_, index, _)| *index),
+         SortOrder::Title => matches.sort_by(|a, b| {
+            let a_title = a.2.title.to_lowercase();
+            let b_title = b.2.title.to_lowercase();
+            a_title.cmp(&b_title).then(a.1.cmp(&b.1))
+         }),
+         SortOrder::LastUsed => {
+            matches.sort_by(|a, b| b.2.last_used_at.cmp(&a.2.last_used_at).then(a.1.cmp(&b.1)))
+         }
+         SortOrder::Relevance => matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1))),
+      }
+
+      matches.into_iter().map(|(_, index, data)| (index, data.clone())).collect()
+   }
+}
+
+/// All the distinct tags used in this map, sorted
+pub fn all_tags(index_map: &HashMap<u32, IndexData>) -> Vec<String> {
+   let mut tags: Vec<String> = Vec::new();
+   for data in index_map.values() {
+      for tag in &data.tags {
+         if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.clone());
+         }
+      }
+   }
+   tags.sort_by_key(|tag| tag.to_lowercase());
+   tags
+}
+
+/// Case insensitive subsequence match
+///
+/// Returns `None` if the characters of `query` do not appear in order in `text`,
+/// otherwise a score that favors consecutive characters, word starts and exact substrings.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+   let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+   if query.is_empty() {
+      return Some(0);
+   }
+
+   let text_lower = text.to_lowercase();
+   let chars: Vec<char> = text_lower.chars().collect();
+
+   let mut score = 0;
+   let mut query_pos = 0;
+   let mut prev_match: Option<usize> = None;
+
+   for (i, c) in chars.iter().enumerate() {
+      if query_pos == query.len() {
+         break;
+      }
+
+      if *c != query[query_pos] {
+         continue;
+      }
+
+      score += 1;
+
+      if prev_match.is_some_and(|prev| prev + 1 == i) {
+         score += 5;
+      }
+
+      let word_start = i == 0 || !chars[i - 1].is_alphanumeric();
+      if word_start {
+         score += 3;
+      }
+
+      prev_match = Some(i);
+      query_pos += 1;
+   }
+
+   if query_pos < query.len() {
+      return None;
+   }
+
+   let needle: String = query.iter().collect();
+   if text_lower.contains(&needle) {
+      score += 10;
+   }
+
+   Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_fuzzy_score() {
+      assert!(fuzzy_score("ggl", "Google Account").is_some());
+      assert!(fuzzy_score("lgg", "Google Account").is_none());
+      assert!(fuzzy_score("goo", "Google").unwrap() > fuzzy_score("goo", "Go Outside").unwrap());
+      assert_eq!(fuzzy_score("", "anything"), Some(0));
+   }
+
+   #[test]
+   fn test_query_apply() {
+      let mut map = HashMap::new();
+      map.insert(
+         0,
+         IndexData::titled("Google").with_tags(&["email"]),
+      );
+      map.insert(
+         1,
+         IndexData::titled("GitHub").with_tags(&["work", "dev"]).with_exposed(true),
+      );
+      map.insert(
+         2,
+         IndexData::titled("Bank").with_tags(&["finance"]),
+      );
+
+      let query = EntryQuery {
+         text: "g".to_string(),
+         sort: SortOrder::Title,
+         ..Default::default()
+      };
+      let found: Vec<u32> = query.apply(&map).into_iter().map(|(i, _)| i).collect();
+      assert_eq!(found, vec![1, 0]);
+
+      let query = EntryQuery {
+         filter: EntryFilter::Exposed,
+         ..Default::default()
+      };
+      assert_eq!(query.apply(&map).len(), 1);
+
+      let query = EntryQuery {
+         tag: Some("FINANCE".to_string()),
+         ..Default::default()
+      };
+      assert_eq!(query.apply(&map)[0].0, 2);
+   }
+}
@@ -0,0 +1,587 @@
+use super::{
+   clipboard::CLEAR_TIMEOUTS,
+   home::{PAGE_SIZES, timeout_to_str},
+   lock::{IDLE_TIMEOUTS, idle_timeout_to_str},
+   shortcuts::{Action, Keymap, format_shortcut, parse_shortcut},
+};
+use argon2_rs::Argon2;
+use eframe::egui::{
+   self, Align2, Button, ComboBox, Context, FontId, Grid, RichText, ScrollArea, TextEdit, Ui,
+   Window, vec2,
+};
+use passwd_derive::{fast, normal, slow, very_slow};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+use zeus_theme::{Theme, ThemeKind};
+
+/// Bump this when a setting changes meaning, older files are upgraded on load
+pub const SETTINGS_VERSION: u32 = 1;
+
+pub const SETTINGS_FILE: &str = "NoPassPlz.settings.json";
+
+/// Default name of the file that holds the index map
+pub const DATA_FILE: &str = "NoPassPlz.json";
+
+/// Default name of the folder the data file is synced through with git
+pub const SYNC_FOLDER: &str = "NoPassPlz-sync";
+
+/// UI scales the user can choose from, applied as the egui zoom factor
+pub const UI_SCALES: [f32; 7] = [0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 2.0];
+
+/// Text sizes relative to the theme's own
+pub const TEXT_SCALES: [f32; 5] = [0.9, 1.0, 1.15, 1.3, 1.5];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeChoice {
+   /// Follow the desktop's light or dark preference
+   System,
+   Dark,
+   Light,
+}
+
+impl ThemeChoice {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         ThemeChoice::System => "System",
+         ThemeChoice::Dark => "Dark",
+         ThemeChoice::Light => "Light",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![Self::System, Self::Dark, Self::Light]
+   }
+
+   /// The theme kind to use, dark if the desktop preference is unknown
+   pub fn kind(self, ctx: &Context) -> ThemeKind {
+      match self {
+         ThemeChoice::System => match ctx.system_theme() {
+            Some(egui::Theme::Light) => ThemeKind::Light,
+            _ => ThemeKind::Dark,
+         },
+         ThemeChoice::Dark => ThemeKind::Dark,
+         ThemeChoice::Light => ThemeKind::Light,
+      }
+   }
+}
+
+/// Use the theme's style no matter if egui itself is in dark or light mode
+pub fn set_style(ctx: &Context, theme: &Theme) {
+   ctx.set_style_of(egui::Theme::Dark, theme.style.clone());
+   ctx.set_style_of(egui::Theme::Light, theme.style.clone());
+}
+
+/// Create the theme with its text sizes scaled
+pub fn scaled_theme(kind: ThemeKind, text_scale: f32) -> Theme {
+   let mut theme = Theme::new(kind);
+   let sizes = &mut theme.text_sizes;
+   for size in [
+      &mut sizes.very_small,
+      &mut sizes.small,
+      &mut sizes.normal,
+      &mut sizes.large,
+      &mut sizes.very_large,
+      &mut sizes.heading,
+   ] {
+      *size = (*size * text_scale).round();
+   }
+   theme
+}
+
+/// The Argon2 parameters presets from [passwd_derive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Argon2Preset {
+   Fast,
+   Normal,
+   Slow,
+   VerySlow,
+}
+
+impl Argon2Preset {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         Argon2Preset::Fast => "Fast (~17 secs)",
+         Argon2Preset::Normal => "Normal (~35 secs)",
+         Argon2Preset::Slow => "Slow (~1:11 min)",
+         Argon2Preset::VerySlow => "Very slow (~2:17 mins)",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![Self::Fast, Self::Normal, Self::Slow, Self::VerySlow]
+   }
+
+   pub fn params(self) -> Argon2 {
+      match self {
+         Argon2Preset::Fast => fast(),
+         Argon2Preset::Normal => normal(),
+         Argon2Preset::Slow => slow(),
+         Argon2Preset::VerySlow => very_slow(),
+      }
+   }
+}
+
+/// User preferences, stored next to the index map in [SETTINGS_FILE]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+   pub version: u32,
+   pub theme: ThemeChoice,
+   pub ui_scale: f32,
+   pub text_scale: f32,
+   pub items_per_page: u32,
+   pub compact: bool,
+   /// Seconds, 0 means never
+   pub clipboard_timeout: u64,
+   /// Minutes, 0 means never
+   pub idle_timeout: u64,
+   pub lock_on_minimize: bool,
+   pub lock_on_focus_loss: bool,
+   pub argon2_preset: Argon2Preset,
+   /// Where the index map is stored, empty means [DATA_FILE] in the current directory
+   pub data_file: String,
+   /// Remote the data file is synced with, empty means no git sync
+   pub git_remote: String,
+   /// The git repository of the sync, empty means [SYNC_FOLDER] in the current directory
+   pub git_folder: String,
+   /// Encrypt the data file before it is committed
+   pub git_encrypt: bool,
+   pub keymap: Keymap,
+}
+
+impl Default for Settings {
+   fn default() -> Self {
+      Self {
+         version: SETTINGS_VERSION,
+         theme: ThemeChoice::Dark,
+         ui_scale: 1.0,
+         text_scale: 1.0,
+         items_per_page: 10,
+         compact: false,
+         clipboard_timeout: 20,
+         idle_timeout: 10,
+         lock_on_minimize: false,
+         lock_on_focus_loss: false,
+         argon2_preset: Argon2Preset::Slow,
+         data_file: String::new(),
+         git_remote: String::new(),
+         git_folder: String::new(),
+         git_encrypt: true,
+         keymap: Keymap::default(),
+      }
+   }
+}
+
+impl Settings {
+   pub fn path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+      Ok(std::env::current_dir()?.join(SETTINGS_FILE))
+   }
+
+   /// Load the settings file, defaults are used if there is none yet
+   pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+      let path = Self::path()?;
+      if !path.exists() {
+         return Ok(Self::default());
+      }
+
+      let data = std::fs::read(&path)?;
+      Self::from_json(&data)
+   }
+
+   pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+      self.validate()?;
+      let data = serde_json::to_string_pretty(self)?;
+      std::fs::write(Self::path()?, data)?;
+      Ok(())
+   }
+
+   pub fn from_json(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+      let value: serde_json::Value = serde_json::from_slice(data)?;
+
+      // Files without a version predate versioning, missing fields get their defaults
+      let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+      if version > SETTINGS_VERSION as u64 {
+         return Err(
+            format!(
+               "The settings file is version {} but this app only understands up to version {}",
+               version, SETTINGS_VERSION
+            )
+            .into(),
+         );
+      }
+
+      let mut settings: Settings = serde_json::from_value(value)?;
+      settings.version = SETTINGS_VERSION;
+      settings.validate()?;
+      Ok(settings)
+   }
+
+   pub fn validate(&self) -> Result<(), String> {
+      if !UI_SCALES.contains(&self.ui_scale) {
+         return Err(format!("Invalid UI scale {}", self.ui_scale));
+      }
+
+      if !TEXT_SCALES.contains(&self.text_scale) {
+         return Err(format!("Invalid text size {}", self.text_scale));
+      }
+
+      if !PAGE_SIZES.contains(&self.items_per_page) {
+         return Err(format!(
+            "Invalid page size {}",
+            self.items_per_page
+         ));
+      }
+
+      if !CLEAR_TIMEOUTS.contains(&self.clipboard_timeout) {
+         return Err(format!(
+            "Invalid clipboard timeout {}",
+            self.clipboard_timeout
+         ));
+      }
+
+      if !IDLE_TIMEOUTS.contains(&self.idle_timeout) {
+         return Err(format!(
+            "Invalid idle timeout {}",
+            self.idle_timeout
+         ));
+      }
+
+      let mut used: HashMap<String, Action> = HashMap::new();
+      for (action, binding) in &self.keymap.bindings {
+         let Some(shortcut) = parse_shortcut(binding) else {
+            return Err(format!(
+               "Invalid shortcut \"{}\" for {}",
+               binding,
+               action.to_str()
+            ));
+         };
+
+         let text = format_shortcut(&shortcut);
+         if let Some(other) = used.insert(text.clone(), *action) {
+            return Err(format!(
+               "{} is used by both {} and {}",
+               text,
+               other.to_str(),
+               action.to_str()
+            ));
+         }
+      }
+
+      if !self.data_file.trim().is_empty() {
+         let path = PathBuf::from(self.data_file.trim());
+         if path.is_dir() {
+            return Err(String::from(
+               "The data file location is a directory",
+            ));
+         }
+
+         let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+         if let Some(parent) = parent
+            && !parent.is_dir()
+         {
+            return Err(format!(
+               "The folder {} does not exist",
+               parent.display()
+            ));
+         }
+      }
+
+      Ok(())
+   }
+
+   /// Full path of the index map file
+   pub fn data_file_path(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+      let data_file = self.data_file.trim();
+      if data_file.is_empty() {
+         Ok(std::env::current_dir()?.join(DATA_FILE))
+      } else {
+         Ok(PathBuf::from(data_file))
+      }
+   }
+
+   /// Full path of the git sync repository
+   pub fn git_folder_path(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+      let git_folder = self.git_folder.trim();
+      if git_folder.is_empty() {
+         Ok(std::env::current_dir()?.join(SYNC_FOLDER))
+      } else {
+         Ok(PathBuf::from(git_folder))
+      }
+   }
+}
+
+/// Edits a copy of the [Settings], nothing changes until the user saves
+pub struct SettingsWindow {
+   open: bool,
+   draft: Settings,
+   error: Option<String>,
+   /// Set when the user saved valid settings, taken by the [super::GUI]
+   pub saved: Option<Settings>,
+}
+
+impl SettingsWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         draft: Settings::default(),
+         error: None,
+         saved: None,
+      }
+   }
+
+   pub fn open(&mut self, current: Settings) {
+      self.open = true;
+      self.draft = current;
+      self.error = None;
+   }
+
+   pub fn show(&mut self, theme: &Theme, ui: &mut Ui) {
+      if !self.open {
+         return;
+      }
+
+      Window::new("Settings")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+
+               let text = RichText::new("Settings").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               ScrollArea::vertical().max_height(350.0).show(ui, |ui| {
+                  self.show_general(theme, ui);
+                  ui.separator();
+                  self.show_keymap(theme, ui);
+               });
+
+               if let Some(error) = &self.error {
+                  let text = RichText::new(error).size(theme.text_sizes.normal);
+                  ui.label(text.color(theme.colors.error));
+               }
+
+               ui.horizontal(|ui| {
+                  ui.add_space(ui.available_width() / 2.0 - 170.0);
+
+                  let text = RichText::new("Save").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     match self.draft.validate() {
+                        Ok(_) => {
+                           self.saved = Some(self.draft.clone());
+                           self.open = false;
+                        }
+                        Err(e) => self.error = Some(e),
+                     }
+                  }
+
+                  let text = RichText::new("Reset to defaults").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     self.draft = Settings::default();
+                     self.error = None;
+                  }
+
+                  let text = RichText::new("Cancel").size(theme.text_sizes.normal);
+                  let button = Button::new(text).min_size(vec2(100.0, 25.0));
+                  if ui.add(button).clicked() {
+                     self.open = false;
+                  }
+               });
+            });
+         });
+   }
+
+   fn show_general(&mut self, theme: &Theme, ui: &mut Ui) {
+      let size = theme.text_sizes.normal;
+      let draft = &mut self.draft;
+
+      Grid::new("settings_general").spacing(vec2(20.0, 8.0)).show(ui, |ui| {
+         ui.label(RichText::new("Theme").size(size));
+         ComboBox::from_id_salt("settings_theme")
+            .selected_text(draft.theme.to_str())
+            .show_ui(ui, |ui| {
+               for choice in ThemeChoice::to_vec() {
+                  ui.selectable_value(&mut draft.theme, choice, choice.to_str());
+               }
+            });
+         ui.end_row();
+
+         ui.label(RichText::new("UI scale").size(size));
+         ComboBox::from_id_salt("settings_ui_scale")
+            .selected_text(percent(draft.ui_scale))
+            .show_ui(ui, |ui| {
+               for scale in UI_SCALES {
+                  ui.selectable_value(&mut draft.ui_scale, scale, percent(scale));
+               }
+            });
+         ui.end_row();
+
+         ui.label(RichText::new("Text size").size(size));
+         ComboBox::from_id_salt("settings_text_scale")
+            .selected_text(percent(draft.text_scale))
+            .show_ui(ui, |ui| {
+               for scale in TEXT_SCALES {
+                  ui.selectable_value(&mut draft.text_scale, scale, percent(scale));
+               }
+            });
+         ui.end_row();
+
+         ui.label(RichText::new("Entries per page").size(size));
+         ComboBox::from_id_salt("settings_page_size")
+            .selected_text(draft.items_per_page.to_string())
+            .show_ui(ui, |ui| {
+               for page_size in PAGE_SIZES {
+                  ui.selectable_value(
+                     &mut draft.items_per_page,
+                     page_size,
+                     page_size.to_string(),
+                  );
+               }
+            });
+         ui.end_row();
+
+         ui.label(RichText::new("Compact view").size(size));
+         ui.checkbox(&mut draft.compact, "");
+         ui.end_row();
+
+         ui.label(RichText::new("Clear clipboard after").size(size));
+         ComboBox::from_id_salt("settings_clipboard_timeout")
+            .selected_text(timeout_to_str(draft.clipboard_timeout))
+            .show_ui(ui, |ui| {
+               for secs in CLEAR_TIMEOUTS {
+                  ui.selectable_value(
+                     &mut draft.clipboard_timeout,
+                     secs,
+                     timeout_to_str(secs),
+                  );
+               }
+            });
+         ui.end_row();
+
+         ui.label(RichText::new("Lock when idle for").size(size));
+         ComboBox::from_id_salt("settings_idle_timeout")
+            .selected_text(idle_timeout_to_str(draft.idle_timeout))
+            .show_ui(ui, |ui| {
+               for minutes in IDLE_TIMEOUTS {
+                  ui.selectable_value(
+                     &mut draft.idle_timeout,
+                     minutes,
+                     idle_timeout_to_str(minutes),
+                  );
+               }
+            });
+         ui.end_row();
+
+         ui.label(RichText::new("Lock when minimized").size(size));
+         ui.checkbox(&mut draft.lock_on_minimize, "");
+         ui.end_row();
+
+         ui.label(RichText::new("Lock on focus loss").size(size));
+         ui.checkbox(&mut draft.lock_on_focus_loss, "");
+         ui.end_row();
+
+         ui.label(RichText::new("Default Argon2 preset").size(size));
+         ComboBox::from_id_salt("settings_argon2")
+            .selected_text(draft.argon2_preset.to_str())
+            .show_ui(ui, |ui| {
+               for preset in Argon2Preset::to_vec() {
+                  ui.selectable_value(&mut draft.argon2_preset, preset, preset.to_str());
+               }
+            });
+         ui.end_row();
+
+         ui.label(RichText::new("Data file").size(size));
+         let text_edit = TextEdit::singleline(&mut draft.data_file)
+            .font(FontId::proportional(size))
+            .desired_width(220.0)
+            .hint_text(DATA_FILE);
+         ui.add(text_edit);
+         ui.end_row();
+
+         ui.label(RichText::new("Git remote").size(size));
+         let text_edit = TextEdit::singleline(&mut draft.git_remote)
+            .font(FontId::proportional(size))
+            .desired_width(220.0)
+            .hint_text("git@example.com:me/passwords.git");
+         ui.add(text_edit);
+         ui.end_row();
+
+         ui.label(RichText::new("Git folder").size(size));
+         let text_edit = TextEdit::singleline(&mut draft.git_folder)
+            .font(FontId::proportional(size))
+            .desired_width(220.0)
+            .hint_text(SYNC_FOLDER);
+         ui.add(text_edit);
+         ui.end_row();
+
+         ui.label(RichText::new("Encrypt the synced file").size(size));
+         ui.checkbox(&mut draft.git_encrypt, "");
+         ui.end_row();
+      });
+
+      let text = "Every password is derived with the Argon2 parameters, \
+         use the same preset you created your entries with or all of them will change.";
+      let text = RichText::new(text).size(theme.text_sizes.small);
+      ui.label(text.color(theme.colors.warning));
+   }
+
+   fn show_keymap(&mut self, theme: &Theme, ui: &mut Ui) {
+      let size = theme.text_sizes.normal;
+
+      let text = RichText::new("Keyboard shortcuts").size(theme.text_sizes.large);
+      ui.label(text);
+
+      Grid::new("settings_keymap").spacing(vec2(20.0, 6.0)).show(ui, |ui| {
+         for action in Action::to_vec() {
+            ui.label(RichText::new(action.to_str()).size(size));
+
+            let binding = self.draft.keymap.bindings.entry(action).or_default();
+            let valid = parse_shortcut(binding).is_some();
+            let mut text_edit =
+               TextEdit::singleline(binding).font(FontId::monospace(size)).desired_width(140.0);
+            if !valid {
+               text_edit = text_edit.text_color(theme.colors.error);
+            }
+            ui.add(text_edit);
+            ui.end_row();
+         }
+      });
+   }
+}
+
+fn percent(scale: f32) -> String {
+   format!("{:.0}%", scale * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_from_json() {
+      // An unversioned file gets the defaults for anything missing
+      let settings = Settings::from_json(br#"{"items_per_page": 25}"#).unwrap();
+      assert_eq!(settings.items_per_page, 25);
+      assert_eq!(settings.version, SETTINGS_VERSION);
+      assert!(settings.keymap == Keymap::default());
+
+      assert!(Settings::from_json(br#"{"version": 999}"#).is_err());
+      assert!(Settings::from_json(br#"{"items_per_page": 7}"#).is_err());
+   }
+
+   #[test]
+   fn test_validate_keymap() {
+      let mut settings = Settings::default();
+      assert!(settings.validate().is_ok());
+
+      settings.keymap.bindings.insert(Action::Edit, String::from("Ctrl+F"));
+      assert!(settings.validate().is_err());
+
+      settings.keymap.bindings.insert(Action::Edit, String::from("Ctrl+Nope"));
+      assert!(settings.validate().is_err());
+   }
+}
@@ -0,0 +1,183 @@
+use eframe::egui::{Context, Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+   FocusSearch,
+   SelectNext,
+   SelectPrevious,
+   CopyPassword,
+   CopyUsername,
+   ShowPassword,
+   Edit,
+   Delete,
+   NewEntry,
+   Lock,
+   SubmitCredentials,
+   CheatSheet,
+}
+
+impl Action {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         Action::FocusSearch => "Focus search",
+         Action::SelectNext => "Select next entry",
+         Action::SelectPrevious => "Select previous entry",
+         Action::CopyPassword => "Copy password",
+         Action::CopyUsername => "Copy username",
+         Action::ShowPassword => "Show password",
+         Action::Edit => "Edit entry",
+         Action::Delete => "Delete entry",
+         Action::NewEntry => "New entry",
+         Action::Lock => "Lock",
+         Action::SubmitCredentials => "Submit credentials",
+         Action::CheatSheet => "Show shortcuts",
+      }
+   }
+
+   pub fn to_vec() -> Vec<Self> {
+      vec![
+         Self::FocusSearch,
+         Self::SelectNext,
+         Self::SelectPrevious,
+         Self::CopyPassword,
+         Self::CopyUsername,
+         Self::ShowPassword,
+         Self::Edit,
+         Self::Delete,
+         Self::NewEntry,
+         Self::Lock,
+         Self::SubmitCredentials,
+         Self::CheatSheet,
+      ]
+   }
+
+   /// `Ctrl+C` and friends are turned into clipboard events by egui so they are avoided here
+   fn default_shortcut(self) -> &'static str {
+      match self {
+         Action::FocusSearch => "Ctrl+F",
+         Action::SelectNext => "Down",
+         Action::SelectPrevious => "Up",
+         Action::CopyPassword => "Ctrl+P",
+         Action::CopyUsername => "Ctrl+U",
+         Action::ShowPassword => "Ctrl+R",
+         Action::Edit => "Ctrl+E",
+         Action::Delete => "Ctrl+Delete",
+         Action::NewEntry => "Ctrl+N",
+         Action::Lock => "Ctrl+L",
+         Action::SubmitCredentials => "Enter",
+         Action::CheatSheet => "F1",
+      }
+   }
+}
+
+/// Keyboard shortcut of each [Action], written like `Ctrl+Shift+F`
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keymap {
+   pub bindings: BTreeMap<Action, String>,
+}
+
+impl Default for Keymap {
+   fn default() -> Self {
+      let bindings = Action::to_vec()
+         .into_iter()
+         .map(|action| (action, action.default_shortcut().to_string()))
+         .collect();
+      Self { bindings }
+   }
+}
+
+impl Keymap {
+   pub fn shortcut(&self, action: Action) -> Option<KeyboardShortcut> {
+      let binding = self.bindings.get(&action).map(|s| s.as_str());
+      parse_shortcut(binding.unwrap_or(action.default_shortcut()))
+   }
+
+   /// The shortcut of this action as shown to the user
+   pub fn shortcut_text(&self, action: Action) -> String {
+      match self.shortcut(action) {
+         Some(shortcut) => format_shortcut(&shortcut),
+         None => String::from("Unassigned"),
+      }
+   }
+
+   /// Returns true if the shortcut of this action was pressed this frame
+   ///
+   /// The key press is consumed so widgets don't also react to it.
+   pub fn pressed(&self, ctx: &Context, action: Action) -> bool {
+      match self.shortcut(action) {
+         Some(shortcut) => ctx.input_mut(|i| i.consume_shortcut(&shortcut)),
+         None => false,
+      }
+   }
+}
+
+/// Parse a shortcut like `Ctrl+Shift+F`, `Alt+Up` or `F1`
+///
+/// `Ctrl` maps to `Cmd` on macOS.
+pub fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+   let mut modifiers = Modifiers::NONE;
+   let mut key = None;
+
+   for part in text.split('+').map(|part| part.trim()) {
+      match part.to_lowercase().as_str() {
+         "ctrl" | "cmd" | "command" => modifiers |= Modifiers::COMMAND,
+         "shift" => modifiers |= Modifiers::SHIFT,
+         "alt" | "option" => modifiers |= Modifiers::ALT,
+         _ => {
+            if key.is_some() {
+               return None;
+            }
+            key = Some(Key::from_name(part).or_else(|| Key::from_name(&part.to_uppercase()))?);
+         }
+      }
+   }
+
+   Some(KeyboardShortcut::new(modifiers, key?))
+}
+
+/// The inverse of [parse_shortcut]
+pub fn format_shortcut(shortcut: &KeyboardShortcut) -> String {
+   let mut parts = Vec::new();
+   if shortcut.modifiers.command || shortcut.modifiers.ctrl {
+      parts.push("Ctrl");
+   }
+   if shortcut.modifiers.shift {
+      parts.push("Shift");
+   }
+   if shortcut.modifiers.alt {
+      parts.push("Alt");
+   }
+   parts.push(shortcut.logical_key.name());
+   parts.join("+")
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_parse_shortcut() {
+      let shortcut = parse_shortcut("Ctrl+Shift+f").unwrap();
+      assert_eq!(shortcut.logical_key, Key::F);
+      assert!(shortcut.modifiers.command && shortcut.modifiers.shift);
+      assert_eq!(format_shortcut(&shortcut), "Ctrl+Shift+F");
+
+      assert_eq!(
+         parse_shortcut("Down").unwrap().logical_key,
+         Key::ArrowDown
+      );
+      assert!(parse_shortcut("Ctrl+A+B").is_none());
+      assert!(parse_shortcut("Ctrl").is_none());
+   }
+
+   #[test]
+   fn test_default_keymap_parses() {
+      let keymap = Keymap::default();
+      for action in Action::to_vec() {
+         assert!(keymap.shortcut(action).is_some(), "{:?}", action);
+      }
+   }
+}
@@ -0,0 +1,508 @@
+//! A small zxcvbn-style password strength estimator
+//!
+//! The password is split in the cheapest sequence of patterns an attacker would try
+//! (dictionary words, keyboard and alphabet runs, repeats, years) and brute force for the rest,
+//! the strength is the entropy of that cheapest guess.
+//!
+//! Every copy of the password made on the way is wiped before [estimate] returns.
+
+use secure_types::Zeroize;
+use std::ops::Deref;
+
+/// The README asks for at least this many characters
+pub const RECOMMENDED_LENGTH: usize = 20;
+
+/// Common passwords and words, in order of popularity
+const DICTIONARY: &[&str] = &[
+   "password",
+   "123456",
+   "qwerty",
+   "letmein",
+   "welcome",
+   "admin",
+   "login",
+   "master",
+   "secret",
+   "dragon",
+   "monkey",
+   "football",
+   "baseball",
+   "iloveyou",
+   "sunshine",
+   "princess",
+   "shadow",
+   "superman",
+   "batman",
+   "trustno1",
+   "starwars",
+   "whatever",
+   "freedom",
+   "hello",
+   "charlie",
+   "michael",
+   "jordan",
+   "jennifer",
+   "thomas",
+   "robert",
+   "daniel",
+   "ashley",
+   "hunter",
+   "ranger",
+   "buster",
+   "soccer",
+   "hockey",
+   "killer",
+   "george",
+   "pepper",
+   "summer",
+   "winter",
+   "spring",
+   "autumn",
+   "love",
+   "money",
+   "computer",
+   "internet",
+   "security",
+   "access",
+   "orange",
+   "purple",
+   "yellow",
+   "silver",
+   "golden",
+   "banana",
+   "cookie",
+   "cheese",
+   "coffee",
+   "chocolate",
+   "flower",
+   "family",
+   "friend",
+   "forever",
+   "happy",
+   "lucky",
+   "magic",
+   "music",
+   "pass",
+   "passw0rd",
+   "test",
+   "guest",
+   "user",
+   "root",
+   "office",
+   "google",
+   "apple",
+   "house",
+   "dog",
+   "cat",
+   "fish",
+   "tiger",
+   "lion",
+   "eagle",
+   "bear",
+   "wolf",
+   "horse",
+   "angel",
+   "devil",
+   "heaven",
+   "nothing",
+   "blue",
+   "green",
+   "black",
+   "white",
+   "red",
+   "mother",
+   "father",
+   "sister",
+   "brother",
+   "baby",
+   "girl",
+   "boy",
+   "king",
+   "queen",
+   "prince",
+   "star",
+   "moon",
+   "sun",
+   "night",
+   "day",
+   "time",
+   "life",
+   "world",
+   "home",
+   "work",
+   "game",
+   "player",
+   "change",
+   "christmas",
+   "birthday",
+];
+
+const KEYBOARD_ROWS: &[&str] = &["qwertyuiop", "asdfghjkl", "zxcvbnm", "1234567890"];
+
+const ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weakness {
+   TooShort,
+   DictionaryWord,
+   ContainsUsername,
+   Sequence,
+   Repeat,
+   Year,
+}
+
+impl Weakness {
+   pub fn to_str(self) -> &'static str {
+      match self {
+         Weakness::TooShort => "Shorter than the recommended 20 characters",
+         Weakness::DictionaryWord => "Contains a common word or password",
+         Weakness::ContainsUsername => "Contains the username",
+         Weakness::Sequence => "Contains a sequence like abc, 123 or qwerty",
+         Weakness::Repeat => "Contains repeated characters",
+         Weakness::Year => "Contains a year",
+      }
+   }
+}
+
+/// Result of [estimate]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Strength {
+   /// log2 of the guesses needed for the cheapest way to find the password
+   pub bits: f64,
+   pub weaknesses: Vec<Weakness>,
+}
+
+impl Strength {
+   /// 0 (very weak) to 4 (very strong)
+   pub fn score(&self) -> u8 {
+      match self.bits {
+         b if b < 28.0 => 0,
+         b if b < 40.0 => 1,
+         b if b < 60.0 => 2,
+         b if b < 80.0 => 3,
+         _ => 4,
+      }
+   }
+
+   pub fn label(&self) -> &'static str {
+      match self.score() {
+         0 => "Very weak",
+         1 => "Weak",
+         2 => "Fair",
+         3 => "Strong",
+         _ => "Very strong",
+      }
+   }
+}
+
+/// A part of the password that can be guessed with fewer tries than brute force
+struct Match {
+   start: usize,
+   end: usize,
+   bits: f64,
+   weakness: Weakness,
+}
+
+/// Characters of a secret, zeroized on drop
+///
+/// The capacity is reserved up front so filling it never reallocates and leaves copies behind.
+struct SecretChars(Vec<char>);
+
+impl SecretChars {
+   fn from_iter(len: usize, chars: impl Iterator<Item = char>) -> Self {
+      let mut vec = Vec::with_capacity(len);
+      vec.extend(chars.take(len));
+      Self(vec)
+   }
+}
+
+impl Deref for SecretChars {
+   type Target = [char];
+
+   fn deref(&self) -> &[char] {
+      &self.0
+   }
+}
+
+impl Drop for SecretChars {
+   fn drop(&mut self) {
+      self.0.zeroize();
+   }
+}
+
+/// The lowercase of a character if it is a single character
+fn single_lowercase(c: char) -> Option<char> {
+   let mut lower = c.to_lowercase();
+   match (lower.next(), lower.next()) {
+      (Some(c), None) => Some(c),
+      _ => None,
+   }
+}
+
+pub fn estimate(password: &str, username: &str) -> Strength {
+   let n = password.chars().count();
+   let chars = SecretChars::from_iter(n, password.chars());
+   // A character without a single char lowercase keeps its place but matches no lowercase
+   // pattern, so it's brute forced
+   let lower = SecretChars::from_iter(
+      n,
+      password.chars().map(|c| single_lowercase(c).unwrap_or(c)),
+   );
+   if chars.is_empty() {
+      return Strength {
+         bits: brute_force_bits(&chars) * chars.len() as f64,
+         weaknesses: vec![Weakness::TooShort],
+      };
+   }
+
+   let mut matches = Vec::new();
+   dictionary_matches(&lower, &chars, &mut matches);
+   username_matches(&lower, username, &mut matches);
+   sequence_matches(&lower, &mut matches);
+   repeat_matches(&chars, &mut matches);
+   year_matches(&chars, &mut matches);
+
+   // Cheapest cover of the password, brute forcing anything no pattern explains
+   let char_bits = brute_force_bits(&chars);
+   let mut best: Vec<(f64, Option<usize>)> = vec![(f64::INFINITY, None); n + 1];
+   best[0] = (0.0, None);
+   for i in 0..n {
+      let (bits, _) = best[i];
+      if bits + char_bits < best[i + 1].0 {
+         best[i + 1] = (bits + char_bits, None);
+      }
+
+      for (m, candidate) in matches.iter().enumerate().filter(|(_, m)| m.start == i) {
+         let total = bits + candidate.bits;
+         if total < best[candidate.end].0 {
+            best[candidate.end] = (total, Some(m));
+         }
+      }
+   }
+
+   let mut weaknesses = Vec::new();
+   let mut i = n;
+   while i > 0 {
+      match best[i].1 {
+         Some(m) => {
+            let m = &matches[m];
+            if !weaknesses.contains(&m.weakness) {
+               weaknesses.push(m.weakness);
+            }
+            i = m.start;
+         }
+         None => i -= 1,
+      }
+   }
+
+   if n < RECOMMENDED_LENGTH {
+      weaknesses.insert(0, Weakness::TooShort);
+   }
+
+   Strength {
+      bits: best[n].0,
+      weaknesses,
+   }
+}
+
+/// Bits per character when guessing blindly with the character classes in use
+fn brute_force_bits(chars: &[char]) -> f64 {
+   let mut pool = 0;
+   if chars.iter().any(|c| c.is_ascii_lowercase()) {
+      pool += 26;
+   }
+   if chars.iter().any(|c| c.is_ascii_uppercase()) {
+      pool += 26;
+   }
+   if chars.iter().any(|c| c.is_ascii_digit()) {
+      pool += 10;
+   }
+   if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+      pool += 33;
+   }
+   if chars.iter().any(|c| !c.is_ascii()) {
+      pool += 100;
+   }
+   (pool.max(10) as f64).log2()
+}
+
+/// Undo common l33t substitutions
+fn unleet(c: char) -> char {
+   match c {
+      '@' | '4' => 'a',
+      '3' => 'e',
+      '1' | '!' => 'i',
+      '0' => 'o',
+      '$' | '5' => 's',
+      '7' => 't',
+      c => c,
+   }
+}
+
+fn dictionary_matches(lower: &[char], chars: &[char], matches: &mut Vec<Match>) {
+   let plain = SecretChars::from_iter(lower.len(), lower.iter().map(|c| unleet(*c)));
+
+   for (rank, word) in DICTIONARY.iter().enumerate() {
+      let word: Vec<char> = word.chars().collect();
+      if word.len() > plain.len() {
+         continue;
+      }
+
+      for start in 0..=plain.len() - word.len() {
+         let end = start + word.len();
+         let matches_plain = plain[start..end].iter().zip(&word).all(|(a, b)| a == b);
+         let matches_raw = lower[start..end].iter().zip(&word).all(|(a, b)| a == b);
+         if !matches_plain && !matches_raw {
+            continue;
+         }
+
+         let mut bits = ((rank + 1) as f64).log2();
+         // Capitals and substitutions add a little
+         if chars[start..end].iter().any(|c| c.is_uppercase()) {
+            bits += 1.0;
+         }
+         if !matches_raw {
+            bits += 1.0;
+         }
+
+         matches.push(Match {
+            start,
+            end,
+            bits: bits.max(1.0),
+            weakness: Weakness::DictionaryWord,
+         });
+      }
+   }
+}
+
+fn username_matches(lower: &[char], username: &str, matches: &mut Vec<Match>) {
+   let username = username.trim();
+   let len = username.chars().count();
+   let username = SecretChars::from_iter(len, username.chars().map_while(single_lowercase));
+   if username.len() != len || username.len() < 3 || username.len() > lower.len() {
+      return;
+   }
+
+   for start in 0..=lower.len() - username.len() {
+      let end = start + username.len();
+      if lower[start..end] == username[..] {
+         matches.push(Match {
+            start,
+            end,
+            bits: 1.0,
+            weakness: Weakness::ContainsUsername,
+         });
+      }
+   }
+}
+
+/// Runs of 3 or more consecutive characters from the alphabet or a keyboard row, either way
+fn sequence_matches(lower: &[char], matches: &mut Vec<Match>) {
+   let mut sequences: Vec<Vec<char>> = KEYBOARD_ROWS.iter().map(|s| s.chars().collect()).collect();
+   sequences.push(ALPHABET.chars().collect());
+
+   for sequence in &sequences {
+      let reversed: Vec<char> = sequence.iter().rev().copied().collect();
+      for seq in [sequence, &reversed] {
+         let mut start = 0;
+         while start < lower.len() {
+            let Some(pos) = seq.iter().position(|c| *c == lower[start]) else {
+               start += 1;
+               continue;
+            };
+
+            let mut len = 1;
+            while start + len < lower.len()
+               && pos + len < seq.len()
+               && lower[start + len] == seq[pos + len]
+            {
+               len += 1;
+            }
+
+            if len >= 3 {
+               matches.push(Match {
+                  start,
+                  end: start + len,
+                  bits: (seq.len() as f64).log2() + (len as f64).log2() + 1.0,
+                  weakness: Weakness::Sequence,
+               });
+            }
+            start += len;
+         }
+      }
+   }
+}
+
+/// The same character 3 or more times in a row
+fn repeat_matches(chars: &[char], matches: &mut Vec<Match>) {
+   let mut start = 0;
+   while start < chars.len() {
+      let mut end = start + 1;
+      while end < chars.len() && chars[end] == chars[start] {
+         end += 1;
+      }
+
+      let len = end - start;
+      if len >= 3 {
+         matches.push(Match {
+            start,
+            end,
+            bits: brute_force_bits(&chars[start..end]) + (len as f64).log2(),
+            weakness: Weakness::Repeat,
+         });
+      }
+      start = end;
+   }
+}
+
+/// Years from 1900 to 2099
+fn year_matches(chars: &[char], matches: &mut Vec<Match>) {
+   for start in 0..chars.len().saturating_sub(3) {
+      let year = &chars[start..start + 4];
+      if matches!(year[..2], ['1', '9'] | ['2', '0']) && year.iter().all(|c| c.is_ascii_digit()) {
+         matches.push(Match {
+            start,
+            end: start + 4,
+            bits: 200f64.log2(),
+            weakness: Weakness::Year,
+         });
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_weak_passwords() {
+      let strength = estimate("P@ssw0rd1990", "");
+      assert_eq!(strength.score(), 0);
+      assert!(strength.weaknesses.contains(&Weakness::DictionaryWord));
+      assert!(strength.weaknesses.contains(&Weakness::Year));
+      assert!(strength.weaknesses.contains(&Weakness::TooShort));
+
+      let strength = estimate("aliceqwertyuiop", "Alice");
+      assert!(strength.score() <= 1);
+      assert!(strength.weaknesses.contains(&Weakness::ContainsUsername));
+      assert!(strength.weaknesses.contains(&Weakness::Sequence));
+
+      assert!(estimate("aaaaaaaaaaaaaaaaaaaaaaaa", "").score() == 0);
+   }
+
+   #[test]
+   fn test_strong_password() {
+      let strength = estimate("v7#Lq!xR2m@Zp9$Wk4&tYe", "alice");
+      assert_eq!(strength.score(), 4);
+      assert!(strength.weaknesses.is_empty());
+   }
+
+   #[test]
+   fn test_long_non_ascii_password() {
+      // 'İ' lowercases to two chars, it must not cut the estimate short
+      let strength = estimate("İstanbul-kÖy-Ğ7ş!ç9Ü#ö2ı@ğ5Ş&İ8x%Çw", "");
+      assert!(!strength.weaknesses.contains(&Weakness::TooShort));
+      assert!(strength.score() >= 3);
+      assert!(strength.bits > 100.0);
+   }
+}
@@ -0,0 +1,742 @@
+//! Sync of the data file through a git repository
+//!
+//! The repository lives in a folder of its own, the data file is copied into it, optionally
+//! encrypted with a key derived from the master seed, and pushed to and pulled from the remote
+//! set in the settings with the `git` command line, so the user's credentials and ssh keys
+//! just work. Diverged histories are merged per index with a [MergePlan].
+
+use super::{
+   AppCtx, SHARED_GUI,
+   app::{AppData, content_hash},
+   merge::{MergePlan, load_data_file},
+   settings::Settings,
+};
+use chacha20poly1305::{
+   XChaCha20Poly1305, XNonce,
+   aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use passwd_derive::Scheme;
+use secure_types::SecureArray;
+use std::{
+   path::{Path, PathBuf},
+   process::Command,
+};
+
+/// Name of the remote in the sync repository, so a remote the user added is never touched
+const REMOTE: &str = "nopassplz";
+
+/// Name of the copy of the data file in the repository
+const PLAIN_FILE: &str = "NoPassPlz.json";
+const ENCRYPTED_FILE: &str = "NoPassPlz.json.enc";
+
+/// Purpose of the key the copy in the repository is encrypted with
+const SYNC_KEY_PURPOSE: &str = "git sync data file";
+
+const NONCE_LEN: usize = 24;
+
+/// Where and how the data file is synced, see [Settings::git_remote]
+#[derive(Clone)]
+pub struct SyncConfig {
+   /// The repository, a folder used for nothing else
+   pub folder: PathBuf,
+   pub remote_url: String,
+   pub encrypt: bool,
+}
+
+impl SyncConfig {
+   /// None if no remote is set
+   pub fn from_settings(settings: &Settings) -> Option<Self> {
+      let remote_url = settings.git_remote.trim();
+      if remote_url.is_empty() {
+         return None;
+      }
+
+      Some(Self {
+         folder: settings.git_folder_path().ok()?,
+         remote_url: remote_url.to_string(),
+         encrypt: settings.git_encrypt,
+      })
+   }
+}
+
+pub struct GitSync {
+   repo: PathBuf,
+   data_file: PathBuf,
+   /// Name of the copy of the data file inside the repository
+   file_name: &'static str,
+   remote_url: String,
+   /// Set when the copy in the repository is encrypted
+   key: Option<SecureArray<u8, 32>>,
+   /// The scheme of the unlocked seed, files of another one are never pulled in
+   scheme: Scheme,
+}
+
+impl GitSync {
+   pub fn new(
+      config: &SyncConfig,
+      data_file: &Path,
+      key: Option<SecureArray<u8, 32>>,
+      scheme: Scheme,
+   ) -> Result<Self, Box<dyn std::error::Error>> {
+      let remote_url = config.remote_url.trim();
+      if remote_url.is_empty() {
+         return Err("Set a git remote in the settings first".into());
+      }
+
+      let repo = std::path::absolute(&config.folder)?;
+      let data_file = std::path::absolute(data_file)?;
+      if data_file.starts_with(&repo) {
+         return Err(
+            format!(
+               "The data file is inside the git folder {}, choose a folder of its own for the sync",
+               repo.display()
+            )
+            .into(),
+         );
+      }
+
+      Ok(Self {
+         repo,
+         data_file,
+         file_name: if key.is_some() {
+            ENCRYPTED_FILE
+         } else {
+            PLAIN_FILE
+         },
+         remote_url: remote_url.to_string(),
+         key,
+         scheme,
+      })
+   }
+
+   fn command(&self, args: &[&str]) -> Command {
+      let mut command = Command::new("git");
+      command.arg("-C").arg(&self.repo).args(args);
+      // Fail instead of waiting for a password on a terminal nobody sees
+      command.env("GIT_TERMINAL_PROMPT", "0");
+      command
+   }
+
+   /// Run git and return its raw output, fails with what git printed on errors
+   fn git_bytes(&self, args: &[&str]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+      let output = self
+         .command(args)
+         .output()
+         .map_err(|e| format!("Failed to run git, is it installed? {}", e))?;
+
+      if !output.status.success() {
+         let stderr = String::from_utf8_lossy(&output.stderr);
+         return Err(format!("git {} failed: {}", args.join(" "), stderr.trim()).into());
+      }
+
+      Ok(output.stdout)
+   }
+
+   /// Run git and return its output as text
+   fn git(&self, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+      let output = self.git_bytes(args)?;
+      Ok(String::from_utf8_lossy(&output).trim().to_string())
+   }
+
+   /// Run git for its exit status only
+   fn git_succeeds(&self, args: &[&str]) -> Result<bool, Box<dyn std::error::Error>> {
+      let output = self
+         .command(args)
+         .output()
+         .map_err(|e| format!("Failed to run git, is it installed? {}", e))?;
+      Ok(output.status.success())
+   }
+
+   /// Create the repository, or make sure the folder is one we created
+   fn ensure_repo(&self) -> Result<(), Box<dyn std::error::Error>> {
+      let folder = self.repo.display();
+      if !self.repo.join(".git").exists() {
+         std::fs::create_dir_all(&self.repo)?;
+         if std::fs::read_dir(&self.repo)?.next().is_some() {
+            return Err(
+               format!(
+                  "{} is not empty, choose an empty folder for the git sync",
+                  folder
+               )
+               .into(),
+            );
+         }
+         self.git(&["init", "--quiet", "--initial-branch=main"])?;
+      }
+
+      self.check_tracked()?;
+
+      if let Ok(url) = self.git(&["remote", "get-url", "origin"])
+         && url != self.remote_url
+      {
+         return Err(format!("The origin of {} points to {}", folder, url).into());
+      }
+
+      match self.git(&["remote", "get-url", REMOTE]) {
+         Ok(url) if url == self.remote_url => {}
+         Ok(url) => {
+            return Err(
+               format!(
+                  "{} syncs with {}, choose another folder to sync with {}",
+                  folder, url, self.remote_url
+               )
+               .into(),
+            );
+         }
+         Err(_) => {
+            self.git(&["remote", "add", REMOTE, &self.remote_url])?;
+         }
+      }
+
+      Ok(())
+   }
+
+   /// Refuse a repository that tracks anything but our copy of the data file
+   fn check_tracked(&self) -> Result<(), Box<dyn std::error::Error>> {
+      let folder = self.repo.display();
+      let tracked = self.git(&["ls-files"])?;
+      if let Some(other) = tracked.lines().find(|file| ![PLAIN_FILE, ENCRYPTED_FILE].contains(file))
+      {
+         return Err(
+            format!(
+               "{} holds {}, it is not a git sync folder of NoPassPlz",
+               folder, other
+            )
+            .into(),
+         );
+      }
+
+      // All computers must agree on the encryption or they would sync two different files
+      let other_file = match self.key.is_some() {
+         true => PLAIN_FILE,
+         false => ENCRYPTED_FILE,
+      };
+      if tracked.lines().any(|file| file == other_file) {
+         let text = match self.key.is_some() {
+            true => "an unencrypted",
+            false => "an encrypted",
+         };
+         return Err(
+            format!(
+               "{} holds {} data file, use the same encryption setting on every computer",
+               folder, text
+            )
+            .into(),
+         );
+      }
+
+      Ok(())
+   }
+
+   /// The copy of the data file as stored in the repository
+   fn encode(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+      let Some(key) = &self.key else {
+         return Ok(data.to_vec());
+      };
+
+      let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+      let payload = Payload {
+         msg: data,
+         aad: self.file_name.as_bytes(),
+      };
+      let sealed = key.unlock(|key| {
+         XChaCha20Poly1305::new_from_slice(key).map(|cipher| cipher.encrypt(&nonce, payload))
+      });
+      let sealed = sealed
+         .map_err(|e| e.to_string())?
+         .map_err(|_| "Failed to encrypt the data file")?;
+
+      let mut out = nonce.to_vec();
+      out.extend_from_slice(&sealed);
+      Ok(out)
+   }
+
+   /// The data file from its copy in the repository
+   fn decode(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+      let Some(key) = &self.key else {
+         return Ok(data.to_vec());
+      };
+
+      if data.len() < NONCE_LEN {
+         return Err("The encrypted data file is truncated".into());
+      }
+      let (nonce, sealed) = data.split_at(NONCE_LEN);
+      let payload = Payload {
+         msg: sealed,
+         aad: self.file_name.as_bytes(),
+      };
+      let plain = key.unlock(|key| {
+         XChaCha20Poly1305::new_from_slice(key)
+            .map(|cipher| cipher.decrypt(XNonce::from_slice(nonce), payload))
+      });
+      let plain = plain.map_err(|e| e.to_string())?.map_err(
+         |_| "Failed to decrypt the synced data file, it was encrypted with other credentials",
+      )?;
+      Ok(plain)
+   }
+
+   fn read_synced(&self) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+      let path = self.repo.join(self.file_name);
+      if !path.exists() {
+         return Ok(None);
+      }
+      self.decode(&std::fs::read(path)?).map(Some)
+   }
+
+   /// Copy the data file into the repository, returns it as loaded
+   fn export(&self) -> Result<Option<AppData>, Box<dyn std::error::Error>> {
+      let data = match std::fs::read(&self.data_file) {
+         Ok(data) => data,
+         Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+         Err(e) => {
+            return Err(
+               format!(
+                  "Failed to read {}: {}",
+                  self.data_file.display(),
+                  e
+               )
+               .into(),
+            );
+         }
+      };
+
+      let mut local: AppData = serde_json::from_slice(&data)?;
+      local.data_file = self.data_file.clone();
+      local.file_hash = Some(content_hash(&data));
+
+      // A new nonce changes every byte, only write when the content changed
+      if self.read_synced()?.as_deref() != Some(&data[..]) {
+         std::fs::write(
+            self.repo.join(self.file_name),
+            self.encode(&data)?,
+         )?;
+      }
+      Ok(Some(local))
+   }
+
+   /// Write what the sync brought in to the data file
+   ///
+   /// Fails if the data file changed since [GitSync::export].
+   fn import(&self, local: Option<AppData>) -> Result<(), Box<dyn std::error::Error>> {
+      let Some(data) = self.read_synced()? else {
+         return Ok(());
+      };
+      if std::fs::read(&self.data_file).ok().as_deref() == Some(&data[..]) {
+         return Ok(());
+      }
+
+      let synced: AppData = serde_json::from_slice(&data)?;
+      check_scheme(&synced, self.scheme)?;
+      let mut local = local.unwrap_or_else(|| AppData {
+         data_file: self.data_file.clone(),
+         ..Default::default()
+      });
+      local.index_map = synced.index_map;
+      local.tombstones = synced.tombstones;
+      local.scheme_version = synced.scheme_version;
+      local.save_index_map_to_file()
+   }
+
+   /// Run a git command that records commits
+   fn git_as_author(&self, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+      let mut with_identity = Vec::new();
+      // Commits need an author, use a placeholder if the user never configured git
+      if !self.git_succeeds(&["config", "user.email"])? {
+         with_identity.extend([
+            "-c",
+            "user.name=NoPassPlz",
+            "-c",
+            "user.email=nopassplz@localhost",
+         ]);
+      }
+      with_identity.extend(args);
+      self.git(&with_identity)
+   }
+
+   fn commit(&self, message: &str) -> Result<(), Box<dyn std::error::Error>> {
+      self.git_as_author(&["commit", "--quiet", "-m", message])?;
+      Ok(())
+   }
+
+   /// Commit the copy of the data file if it changed, returns true if a commit was made
+   fn commit_local(&self) -> Result<bool, Box<dyn std::error::Error>> {
+      if !self.repo.join(self.file_name).exists() {
+         return Ok(false);
+      }
+
+      self.git(&["add", "--", self.file_name])?;
+      let changed = !self.git_succeeds(&["diff", "--cached", "--quiet", "--", self.file_name])?;
+      if changed {
+         self.commit("Update NoPassPlz data")?;
+      }
+      Ok(changed)
+   }
+
+   /// Commit, pull, merge and push the data file
+   pub fn sync(&self) -> Result<String, Box<dyn std::error::Error>> {
+      self.ensure_repo()?;
+      let local = self.export()?;
+      let message = self.sync_repo()?;
+      // The remote may hold the file of a computer with another encryption setting
+      self.check_tracked()?;
+      self.import(local)?;
+      Ok(message)
+   }
+
+   fn sync_repo(&self) -> Result<String, Box<dyn std::error::Error>> {
+      let committed = self.commit_local()?;
+      let has_head = self.git_succeeds(&["rev-parse", "--verify", "--quiet", "HEAD"])?;
+
+      let branch = self.git(&["symbolic-ref", "--short", "HEAD"])?;
+      self.git(&["fetch", "--quiet", REMOTE])?;
+
+      let upstream = format!("{}/{}", REMOTE, branch);
+      let has_upstream = self.git_succeeds(&["rev-parse", "--verify", "--quiet", &upstream])?;
+
+      let mut message = match committed {
+         true => String::from("Committed the local changes. "),
+         false => String::new(),
+      };
+
+      if has_upstream && !has_head {
+         check_scheme(&self.read_upstream(&upstream)?, self.scheme)?;
+         self.git(&["reset", "--quiet", "--hard", &upstream])?;
+         message.push_str("Pulled the data file from the remote.");
+         return Ok(message);
+      }
+
+      if !has_head {
+         return Err(
+            format!(
+               "Nothing to sync, {} does not exist yet",
+               self.data_file.display()
+            )
+            .into(),
+         );
+      }
+
+      if has_upstream {
+         if self.git_succeeds(&["merge-base", "--is-ancestor", "HEAD", &upstream])? {
+            if self.git(&["rev-parse", "HEAD"])? == self.git(&["rev-parse", &upstream])? {
+               message.push_str("Already up to date.");
+               return Ok(message);
+            }
+
+            check_scheme(&self.read_upstream(&upstream)?, self.scheme)?;
+            self.git(&["merge", "--quiet", "--ff-only", &upstream])?;
+            message.push_str("Pulled the changes from the remote.");
+            return Ok(message);
+         }
+
+         if !self.git_succeeds(&["merge-base", "--is-ancestor", &upstream, "HEAD"])? {
+            let conflicts = self.merge_diverged(&upstream)?;
+            message.push_str(&format!(
+               "Merged the remote changes, {} conflicting indices kept the copy modified last. ",
+               conflicts
+            ));
+         }
+      }
+
+      self.git(&[
+         "push",
+         "--quiet",
+         REMOTE,
+         &format!("HEAD:refs/heads/{}", branch),
+      ])?;
+      message.push_str("Pushed to the remote.");
+      Ok(message)
+   }
+
+   /// The data file as committed on the remote branch
+   fn read_upstream(&self, upstream: &str) -> Result<AppData, Box<dyn std::error::Error>> {
+      let data = self.git_bytes(&["show", &format!("{}:{}", upstream, self.file_name)])?;
+      Ok(serde_json::from_slice(&self.decode(&data)?)?)
+   }
+
+   /// Merge the remote data file into ours by index, returns the number of conflicts
+   fn merge_diverged(&self, upstream: &str) -> Result<usize, Box<dyn std::error::Error>> {
+      let ours = self.read_synced()?.ok_or("The data file is missing from the repository")?;
+      let ours: AppData = serde_json::from_slice(&ours)?;
+
+      let theirs = self.read_upstream(upstream)?;
+
+      let plan = MergePlan::new(&ours, &theirs)?;
+      let scheme_version = plan.scheme_version;
+      let conflicts = plan.conflicts.len();
+      let (index_map, tombstones) = plan.resolve();
+
+      // Record both histories, the content is the per index merge
+      self.git_as_author(&[
+         "merge",
+         "--quiet",
+         "--no-ff",
+         "--no-commit",
+         "--allow-unrelated-histories",
+         "-s",
+         "ours",
+         upstream,
+      ])?;
+
+      let merged = AppData {
+         index_map,
+         tombstones,
+         scheme_version,
+         ..Default::default()
+      };
+      let merged = serde_json::to_vec(&merged)?;
+      std::fs::write(
+         self.repo.join(self.file_name),
+         self.encode(&merged)?,
+      )?;
+
+      self.git(&["add", "--", self.file_name])?;
+      self.commit("Merge NoPassPlz data from the remote")?;
+      Ok(conflicts)
+   }
+}
+
+/// Refuse a synced file of another scheme, the seed would derive other passwords for it
+fn check_scheme(data: &AppData, scheme: Scheme) -> Result<(), Box<dyn std::error::Error>> {
+   let synced = data.scheme()?;
+   if synced != scheme {
+      return Err(
+         format!(
+            "The synced data file uses the {} derivation scheme and this one {}, \
+            syncing them is refused",
+            synced.to_str(),
+            scheme.to_str()
+         )
+         .into(),
+      );
+   }
+   Ok(())
+}
+
+/// Sync in the background and reload the entries the remote brought in
+pub fn sync_in_background(app: AppCtx, config: SyncConfig) {
+   let key = match config.encrypt {
+      true => app.read(|app| match &app.passwd_derive {
+         Some(deriver) => deriver.derive_key(SYNC_KEY_PURPOSE).map(Some),
+         None => Err("No deriver instance found".into()),
+      }),
+      false => Ok(None),
+   };
+   let res = key.and_then(|key| Ok((key, app.read(|app| app.scheme())?)));
+   let (key, scheme) = match res {
+      Ok(res) => res,
+      Err(e) => {
+         SHARED_GUI.write(|gui| gui.msg_window.open("Error", e.to_string()));
+         return;
+      }
+   };
+
+   SHARED_GUI.write(|gui| gui.loading_window.open("Syncing with git..."));
+
+   std::thread::spawn(move || {
+      let data_file = app.read(|app| app.data_file.clone());
+      let res = GitSync::new(&config, &data_file, key, scheme).and_then(|sync| sync.sync());
+      let res = res.and_then(|message| {
+         // Another program may have written the file since
+         check_scheme(&load_data_file(&data_file)?, scheme)?;
+         app.load_index_map_from_file()?;
+         Ok(message)
+      });
+
+      SHARED_GUI.write(|gui| {
+         gui.loading_window.reset();
+         match res {
+            Ok(message) => {
+               gui.home.audit.invalidate();
+               gui.msg_window.open("Success", message);
+            }
+            Err(e) => gui.msg_window.open("Error", e.to_string()),
+         }
+         gui.request_repaint();
+      });
+   });
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::gui::{app::IndexData, merge::load_data_file};
+
+   fn save(path: &Path, entries: Vec<(u32, IndexData)>) {
+      let mut data = load_data_file(path).unwrap_or_default();
+      data.data_file = path.to_path_buf();
+      data.index_map.extend(entries);
+      data.save_index_map_to_file().unwrap();
+   }
+
+   fn test_root(name: &str) -> PathBuf {
+      let root = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+      let _ = std::fs::remove_dir_all(&root);
+      std::fs::create_dir_all(&root).unwrap();
+      root
+   }
+
+   fn bare_repository(root: &Path) -> String {
+      let remote = root.join("remote.git");
+      Command::new("git")
+         .args(["init", "--quiet", "--bare"])
+         .arg(&remote)
+         .output()
+         .unwrap();
+      remote.to_str().unwrap().to_string()
+   }
+
+   fn config(folder: PathBuf, remote_url: &str, encrypt: bool) -> SyncConfig {
+      SyncConfig {
+         folder,
+         remote_url: remote_url.to_string(),
+         encrypt,
+      }
+   }
+
+   #[test]
+   fn test_sync_with_bare_repository() {
+      let root = test_root("git-sync-test");
+      let remote = bare_repository(&root);
+      let key = || Some(SecureArray::from_slice(&[7u8; 32]).unwrap());
+
+      let laptop_file = root.join("laptop.json");
+      let desktop_file = root.join("desktop.json");
+      let laptop = config(root.join("laptop-sync"), &remote, true);
+      let desktop = config(root.join("desktop-sync"), &remote, true);
+      let laptop = GitSync::new(&laptop, &laptop_file, key(), Scheme::V1).unwrap();
+      let desktop = GitSync::new(&desktop, &desktop_file, key(), Scheme::V1).unwrap();
+
+      save(
+         &laptop_file,
+         vec![(0, IndexData::titled("Mail").modified_at(10))],
+      );
+      laptop.sync().unwrap();
+
+      let synced = std::fs::read(root.join("laptop-sync").join(ENCRYPTED_FILE)).unwrap();
+      assert!(!String::from_utf8_lossy(&synced).contains("Mail"));
+
+      desktop.sync().unwrap();
+      assert_eq!(
+         load_data_file(&desktop_file).unwrap().index_map[&0].title,
+         "Mail"
+      );
+
+      save(
+         &laptop_file,
+         vec![(1, IndexData::titled("Bank").modified_at(20))],
+      );
+      save(
+         &desktop_file,
+         vec![
+            (0, IndexData::titled("Work mail").modified_at(30)),
+            (2, IndexData::titled("Forum").modified_at(30)),
+         ],
+      );
+      laptop.sync().unwrap();
+      let message = desktop.sync().unwrap();
+      assert!(message.contains("1 conflicting"));
+      laptop.sync().unwrap();
+
+      for file in [&laptop_file, &desktop_file] {
+         let data = load_data_file(file).unwrap();
+         assert_eq!(data.index_map.len(), 3);
+         assert_eq!(data.index_map[&0].title, "Work mail");
+      }
+
+      // Other credentials can't read the synced file
+      let other_file = root.join("other.json");
+      let other = config(root.join("other-sync"), &remote, true);
+      let other_key = Some(SecureArray::from_slice(&[8u8; 32]).unwrap());
+      let other = GitSync::new(&other, &other_file, other_key, Scheme::V1).unwrap();
+      assert!(other.sync().is_err());
+      assert!(!other_file.exists());
+
+      // Nor can a computer that syncs without encryption
+      let plain = config(root.join("plain-sync"), &remote, false);
+      let plain = GitSync::new(&plain, &root.join("plain.json"), None, Scheme::V1).unwrap();
+      assert!(plain.sync().is_err());
+
+      std::fs::remove_dir_all(&root).unwrap();
+   }
+
+   #[test]
+   fn test_sync_refuses_other_scheme() {
+      let root = test_root("git-sync-scheme-test");
+      let remote = bare_repository(&root);
+
+      let laptop_file = root.join("laptop.json");
+      save(&laptop_file, vec![(0, IndexData::titled("Mail"))]);
+      let mut data = load_data_file(&laptop_file).unwrap();
+      data.scheme_version = Some(2);
+      data.save_index_map_to_file().unwrap();
+      let laptop = config(root.join("laptop-sync"), &remote, false);
+      let laptop = GitSync::new(&laptop, &laptop_file, None, Scheme::V2).unwrap();
+      laptop.sync().unwrap();
+
+      // A computer logged in with v1 pulls nothing in
+      let desktop_file = root.join("desktop.json");
+      let desktop = config(root.join("desktop-sync"), &remote, false);
+      let desktop = GitSync::new(&desktop, &desktop_file, None, Scheme::V1).unwrap();
+      let err = desktop.sync().unwrap_err().to_string();
+      assert!(err.contains("scheme"));
+      assert!(!desktop_file.exists());
+
+      // Nor merges its v1 entries with them
+      save(
+         &desktop_file,
+         vec![(1, IndexData::titled("Bank"))],
+      );
+      assert!(desktop.sync().is_err());
+      assert_eq!(
+         load_data_file(&desktop_file).unwrap().index_map.len(),
+         1
+      );
+
+      std::fs::remove_dir_all(&root).unwrap();
+   }
+
+   #[test]
+   fn test_sync_refuses_foreign_folders() {
+      let root = test_root("git-sync-foreign-test");
+      let remote = bare_repository(&root);
+      let data_file = root.join("data.json");
+      save(&data_file, vec![(0, IndexData::titled("Mail"))]);
+
+      // A folder that holds other files
+      let folder = root.join("documents");
+      std::fs::create_dir_all(&folder).unwrap();
+      std::fs::write(folder.join("notes.txt"), "notes").unwrap();
+      let sync = GitSync::new(
+         &config(folder.clone(), &remote, false),
+         &data_file,
+         None,
+         Scheme::V1,
+      )
+      .unwrap();
+      assert!(sync.sync().is_err());
+      assert!(!folder.join(".git").exists());
+
+      // A repository whose origin points elsewhere
+      let folder = root.join("project");
+      std::fs::create_dir_all(&folder).unwrap();
+      let git = |args: &[&str]| Command::new("git").arg("-C").arg(&folder).args(args).output();
+      git(&["init", "--quiet"]).unwrap();
+      git(&["remote", "add", "origin", "https://example.com/project.git"]).unwrap();
+      let sync = GitSync::new(
+         &config(folder.clone(), &remote, false),
+         &data_file,
+         None,
+         Scheme::V1,
+      )
+      .unwrap();
+      let err = sync.sync().unwrap_err().to_string();
+      assert!(err.contains("origin"));
+      assert!(!folder.join(PLAIN_FILE).exists());
+
+      // The data file inside the sync folder
+      let inside = config(root.clone(), &remote, false);
+      assert!(GitSync::new(&inside, &data_file, None, Scheme::V1).is_err());
+
+      std::fs::remove_dir_all(&root).unwrap();
+   }
+}
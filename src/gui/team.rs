@@ -0,0 +1,745 @@
+//! Shared team vaults
+//!
+//! A team has its own random seed that derives the shared passwords. The seed is sealed to the
+//! X25519 public key of every member, derived from their own master seed, so nobody shares their
+//! master credentials. Removing a member replaces the seed and starts a rotation: every shared
+//! account must get its new password, the previous seed stays readable by the remaining
+//! members until then.
+
+use super::app::{IndexData, content_hash, unix_now, write_data_file};
+use eframe::egui::{
+   Align2, Button, FontId, Grid, RichText, ScrollArea, TextEdit, Ui, Window, vec2,
+};
+use passwd_derive::{PasswordDeriver, public_key, random_seed};
+use secure_types::SecureString;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+use zeus_theme::Theme;
+
+pub const TEAM_FILE_VERSION: u32 = 1;
+
+/// Purpose of the member key, see [PasswordDeriver::derive_key]
+const MEMBER_KEY_PURPOSE: &str = "team member key";
+
+/// The public key other members add you with, as hex
+pub fn member_public_key(deriver: &PasswordDeriver) -> Result<String, Box<dyn std::error::Error>> {
+   let secret = deriver.derive_key(MEMBER_KEY_PURPOSE)?;
+   Ok(hex::encode(public_key(&secret)?))
+}
+
+fn parse_public_key(public_key: &str) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+   let bytes = hex::decode(public_key.trim()).map_err(|_| "The public key is not hex")?;
+   bytes.try_into().map_err(|_| "The public key must be 64 hex characters".into())
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamMember {
+   pub name: String,
+   /// Hex X25519 public key
+   pub public_key: String,
+   /// Hex team seed sealed to the public key
+   pub sealed_seed: String,
+}
+
+/// Left over from removing a member until every shared account got its new password
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rotation {
+   /// Names of the removed members
+   pub removed: Vec<String>,
+   /// The previous team seed sealed to each remaining member, by public key
+   pub sealed_seeds: HashMap<String, String>,
+   /// Indices whose account still uses the password of the previous seed
+   pub pending: Vec<u32>,
+}
+
+/// The shared metadata file of a team
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamVault {
+   pub version: u32,
+   pub name: String,
+   /// Bumped every time the team seed is replaced
+   pub generation: u32,
+   pub members: Vec<TeamMember>,
+   pub index_map: HashMap<u32, IndexData>,
+   pub rotation: Option<Rotation>,
+   /// Hash of the file content we last loaded or saved
+   #[serde(skip)]
+   pub file_hash: Option<[u8; 32]>,
+}
+
+impl TeamVault {
+   /// A new team with a random seed and `deriver`'s owner as its only member
+   pub fn create(
+      name: &str,
+      member_name: &str,
+      deriver: &PasswordDeriver,
+   ) -> Result<(Self, PasswordDeriver), Box<dyn std::error::Error>> {
+      if name.trim().is_empty() || member_name.trim().is_empty() {
+         return Err("The team and your name are required".into());
+      }
+
+      let team = PasswordDeriver::from_seed(random_seed()?, deriver.argon2.clone());
+      let mut vault = Self {
+         version: TEAM_FILE_VERSION,
+         name: name.trim().to_string(),
+         ..Default::default()
+      };
+      vault.add_member(&team, member_name, &member_public_key(deriver)?)?;
+      Ok((vault, team))
+   }
+
+   pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+      let data = std::fs::read(path)?;
+      let mut vault: Self = serde_json::from_slice(&data)?;
+      if vault.version > TEAM_FILE_VERSION {
+         return Err(
+            format!(
+               "The team file is version {} but this app only understands up to version {}",
+               vault.version, TEAM_FILE_VERSION
+            )
+            .into(),
+         );
+      }
+      vault.file_hash = Some(content_hash(&data));
+      Ok(vault)
+   }
+
+   /// Refuses to overwrite changes another member saved since we loaded the file
+   pub fn save(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+      let data = serde_json::to_string_pretty(self)?;
+      let hint = "Open the team file again to get their changes.";
+      self.file_hash = Some(write_data_file(
+         path,
+         data.as_bytes(),
+         self.file_hash,
+         hint,
+      )?);
+      Ok(())
+   }
+
+   fn open_sealed(
+      sealed_hex: &str,
+      deriver: &PasswordDeriver,
+   ) -> Result<PasswordDeriver, Box<dyn std::error::Error>> {
+      let sealed = hex::decode(sealed_hex)?;
+      let secret = deriver.derive_key(MEMBER_KEY_PURPOSE)?;
+      PasswordDeriver::open_seed(&secret, &sealed, deriver.argon2.clone())
+   }
+
+   /// The deriver of the shared passwords, if `deriver`'s owner is a member
+   pub fn unlock(
+      &self,
+      deriver: &PasswordDeriver,
+   ) -> Result<PasswordDeriver, Box<dyn std::error::Error>> {
+      let public_key = member_public_key(deriver)?;
+      let member = self
+         .members
+         .iter()
+         .find(|m| m.public_key == public_key)
+         .ok_or("You are not a member of this team, send your public key to a member")?;
+      Self::open_sealed(&member.sealed_seed, deriver)
+   }
+
+   /// The deriver of the passwords before the last rotation
+   pub fn unlock_previous(
+      &self,
+      deriver: &PasswordDeriver,
+   ) -> Result<PasswordDeriver, Box<dyn std::error::Error>> {
+      let rotation = self.rotation.as_ref().ok_or("No rotation in progress")?;
+      let public_key = member_public_key(deriver)?;
+      let sealed = rotation
+         .sealed_seeds
+         .get(&public_key)
+         .ok_or("You were added after the rotation started")?;
+      Self::open_sealed(sealed, deriver)
+   }
+
+   pub fn add_member(
+      &mut self,
+      team: &PasswordDeriver,
+      name: &str,
+      public_key: &str,
+   ) -> Result<(), Box<dyn std::error::Error>> {
+      let name = name.trim();
+      if name.is_empty() {
+         return Err("The member needs a name".into());
+      }
+
+      let key = parse_public_key(public_key)?;
+      let public_key = hex::encode(key);
+      if self.members.iter().any(|m| m.name == name || m.public_key == public_key) {
+         return Err(format!("{} is already a member", name).into());
+      }
+
+      self.members.push(TeamMember {
+         name: name.to_string(),
+         public_key,
+         sealed_seed: hex::encode(team.seal_seed(&key)?),
+      });
+      Ok(())
+   }
+
+   /// Remove a member and replace the team seed, returns the new team deriver
+   ///
+   /// Every entry is left pending until its account gets the new password.
+   pub fn remove_member(
+      &mut self,
+      team: &PasswordDeriver,
+      name: &str,
+   ) -> Result<PasswordDeriver, Box<dyn std::error::Error>> {
+      if self.rotation.is_some() {
+         return Err("Finish the current rotation before removing another member".into());
+      }
+
+      let position = self
+         .members
+         .iter()
+         .position(|m| m.name == name)
+         .ok_or("No member with this name")?;
+      if self.members.len() == 1 {
+         return Err("The last member can't be removed".into());
+      }
+      self.members.remove(position);
+
+      let mut rotation = Rotation {
+         removed: vec![name.to_string()],
+         ..Default::default()
+      };
+      for member in &self.members {
+         let sealed = team.seal_seed(&parse_public_key(&member.public_key)?)?;
+         rotation.sealed_seeds.insert(member.public_key.clone(), hex::encode(sealed));
+      }
+      rotation.pending = self.index_map.keys().copied().collect();
+      rotation.pending.sort();
+
+      let new_team = PasswordDeriver::from_seed(random_seed()?, team.argon2.clone());
+      for member in self.members.iter_mut() {
+         let sealed = new_team.seal_seed(&parse_public_key(&member.public_key)?)?;
+         member.sealed_seed = hex::encode(sealed);
+      }
+
+      self.generation += 1;
+      if !rotation.pending.is_empty() {
+         self.rotation = Some(rotation);
+      }
+      Ok(new_team)
+   }
+
+   /// The account at this index now uses the password of the current seed
+   pub fn mark_rotated(&mut self, index: u32) {
+      let Some(rotation) = &mut self.rotation else {
+         return;
+      };
+
+      rotation.pending.retain(|i| *i != index);
+      if rotation.pending.is_empty() {
+         self.rotation = None;
+      }
+
+      if let Some(data) = self.index_map.get_mut(&index) {
+         data.rotated_at = unix_now();
+         data.modified_at = data.rotated_at;
+      }
+   }
+
+   pub fn is_pending(&self, index: u32) -> bool {
+      self.rotation.as_ref().is_some_and(|r| r.pending.contains(&index))
+   }
+
+   pub fn next_index(&self) -> u32 {
+      self.index_map.keys().max().map_or(0, |i| i.saturating_add(1))
+   }
+}
+
+/// Open, create and manage a team vault
+pub struct TeamWindow {
+   open: bool,
+   path: String,
+   /// Name of the team and your name in it, when creating one
+   team_name: String,
+   member_name: String,
+   vault: Option<TeamVault>,
+   team: Option<PasswordDeriver>,
+   previous: Option<PasswordDeriver>,
+   new_member_name: String,
+   new_member_key: String,
+   new_title: String,
+   new_username: String,
+   /// Member waiting for the user to confirm their removal
+   remove_confirm: Option<String>,
+   error: Option<String>,
+}
+
+impl TeamWindow {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         path: String::new(),
+         team_name: String::new(),
+         member_name: String::new(),
+         vault: None,
+         team: None,
+         previous: None,
+         new_member_name: String::new(),
+         new_member_key: String::new(),
+         new_title: String::new(),
+         new_username: String::new(),
+         remove_confirm: None,
+         error: None,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+      self.error = None;
+   }
+
+   pub fn is_open(&self) -> bool {
+      self.open
+   }
+
+   /// Returns a password the user asked to copy
+   pub fn show(
+      &mut self,
+      deriver: Option<PasswordDeriver>,
+      theme: &Theme,
+      ui: &mut Ui,
+   ) -> Option<SecureString> {
+      if !self.open {
+         return None;
+      }
+
+      let Some(deriver) = deriver else {
+         *self = Self::new();
+         return None;
+      };
+
+      let mut copy = None;
+      Window::new("Team vault")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(550.0);
+
+               let text = RichText::new("Team vault").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               self.show_public_key(&deriver, theme, ui);
+
+               if let Some(error) = &self.error {
+                  let text = RichText::new(error).size(theme.text_sizes.normal);
+                  ui.label(text.color(theme.colors.error));
+               }
+
+               match self.team.is_some() {
+                  true => copy = self.show_vault(theme, ui),
+                  false => self.show_open(&deriver, theme, ui),
+               }
+
+               let text = RichText::new("Close").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  *self = Self::new();
+               }
+            });
+         });
+
+      copy
+   }
+
+   fn show_public_key(&mut self, deriver: &PasswordDeriver, theme: &Theme, ui: &mut Ui) {
+      let Ok(public_key) = member_public_key(deriver) else {
+         return;
+      };
+
+      let text = "Your public key, send it to a team member so they can add you:";
+      let text = RichText::new(text).size(theme.text_sizes.small);
+      ui.label(text.color(theme.colors.text_muted));
+
+      ui.horizontal(|ui| {
+         let text = RichText::new(&public_key).size(theme.text_sizes.small).monospace();
+         ui.label(text);
+
+         let text = RichText::new("Copy").size(theme.text_sizes.small);
+         if ui.button(text).clicked() {
+            ui.ctx().copy_text(public_key.clone());
+         }
+      });
+   }
+
+   fn show_open(&mut self, deriver: &PasswordDeriver, theme: &Theme, ui: &mut Ui) {
+      let size = theme.text_sizes.normal;
+
+      Grid::new("team_open").spacing(vec2(20.0, 8.0)).show(ui, |ui| {
+         ui.label(RichText::new("Team file").size(size));
+         let text_edit = TextEdit::singleline(&mut self.path)
+            .font(FontId::proportional(size))
+            .desired_width(300.0)
+            .hint_text("NoPassPlz.team.json");
+         ui.add(text_edit);
+         ui.end_row();
+
+         ui.label(RichText::new("New team name").size(size));
+         let text_edit = TextEdit::singleline(&mut self.team_name)
+            .font(FontId::proportional(size))
+            .desired_width(300.0)
+            .hint_text("Only to create a team");
+         ui.add(text_edit);
+         ui.end_row();
+
+         ui.label(RichText::new("Your name").size(size));
+         let text_edit = TextEdit::singleline(&mut self.member_name)
+            .font(FontId::proportional(size))
+            .desired_width(300.0)
+            .hint_text("Only to create a team");
+         ui.add(text_edit);
+         ui.end_row();
+      });
+
+      ui.horizontal(|ui| {
+         ui.add_space(ui.available_width() / 2.0 - 110.0);
+
+         let text = RichText::new("Open").size(size);
+         let button = Button::new(text).min_size(vec2(100.0, 25.0));
+         if ui.add(button).clicked() {
+            let res = TeamVault::load(Path::new(self.path.trim()));
+            let res = res.and_then(|vault| self.unlock(vault, deriver));
+            self.error = res.err().map(|e| e.to_string());
+         }
+
+         let text = RichText::new("Create").size(size);
+         let button = Button::new(text).min_size(vec2(100.0, 25.0));
+         if ui.add(button).clicked() {
+            self.error = self.create(deriver).err().map(|e| e.to_string());
+         }
+      });
+   }
+
+   fn unlock(
+      &mut self,
+      vault: TeamVault,
+      deriver: &PasswordDeriver,
+   ) -> Result<(), Box<dyn std::error::Error>> {
+      self.team = Some(vault.unlock(deriver)?);
+      self.previous = vault.unlock_previous(deriver).ok();
+      self.vault = Some(vault);
+      Ok(())
+   }
+
+   fn create(&mut self, deriver: &PasswordDeriver) -> Result<(), Box<dyn std::error::Error>> {
+      let path = Path::new(self.path.trim());
+      if self.path.trim().is_empty() {
+         return Err("Choose where to save the team file".into());
+      }
+      if path.exists() {
+         return Err(format!("{} already exists", path.display()).into());
+      }
+
+      let (mut vault, team) = TeamVault::create(&self.team_name, &self.member_name, deriver)?;
+      vault.save(path)?;
+      self.vault = Some(vault);
+      self.team = Some(team);
+      Ok(())
+   }
+
+   /// Returns false and puts back the `previous` vault if the file could not be written
+   fn save(&mut self, previous: TeamVault) -> bool {
+      let Some(vault) = &mut self.vault else {
+         return false;
+      };
+
+      if let Err(e) = vault.save(Path::new(self.path.trim())) {
+         self.error = Some(format!("Failed to save the team file: {}", e));
+         self.vault = Some(previous);
+         false
+      } else {
+         self.error = None;
+         true
+      }
+   }
+
+   fn show_vault(&mut self, theme: &Theme, ui: &mut Ui) -> Option<SecureString> {
+      let (Some(vault), Some(team)) = (self.vault.clone(), self.team.clone()) else {
+         return None;
+      };
+
+      let text = format!("{} ({} members)", vault.name, vault.members.len());
+      ui.label(RichText::new(text).size(theme.text_sizes.large));
+
+      if let Some(rotation) = &vault.rotation {
+         let text = format!(
+            "{} was removed, give these {} accounts their new password then mark them done. \
+            Until then the old password can still be copied.",
+            rotation.removed.join(", "),
+            rotation.pending.len()
+         );
+         let text = RichText::new(text).size(theme.text_sizes.small);
+         ui.label(text.color(theme.colors.warning));
+      }
+
+      let copy = self.show_entries(&vault, &team, theme, ui);
+      ui.separator();
+      self.show_members(&vault, &team, theme, ui);
+      copy
+   }
+
+   fn show_entries(
+      &mut self,
+      vault: &TeamVault,
+      team: &PasswordDeriver,
+      theme: &Theme,
+      ui: &mut Ui,
+   ) -> Option<SecureString> {
+      let size = theme.text_sizes.normal;
+      let mut copy = None;
+      let mut rotated = None;
+
+      let mut indices: Vec<u32> = vault.index_map.keys().copied().collect();
+      indices.sort();
+
+      ScrollArea::vertical().max_height(200.0).id_salt("team_entries").show(ui, |ui| {
+         Grid::new("team_entries").striped(true).spacing(vec2(12.0, 6.0)).show(ui, |ui| {
+            for index in indices {
+               let data = &vault.index_map[&index];
+               ui.label(RichText::new(index.to_string()).size(size));
+               ui.label(RichText::new(&data.title).size(size));
+               ui.label(RichText::new(&data.username).size(size));
+
+               if ui.button(RichText::new("Copy").size(size)).clicked() {
+                  copy = Some(team.derive_at(index));
+               }
+
+               if vault.is_pending(index) {
+                  if let Some(previous) = &self.previous
+                     && ui.button(RichText::new("Copy old").size(size)).clicked()
+                  {
+                     copy = Some(previous.derive_at(index));
+                  }
+
+                  if ui.button(RichText::new("Done").size(size)).clicked() {
+                     rotated = Some(index);
+                  }
+               }
+               ui.end_row();
+            }
+         });
+      });
+
+      if let Some(index) = rotated {
+         let previous = vault.clone();
+         if let Some(vault) = &mut self.vault {
+            vault.mark_rotated(index);
+            if vault.rotation.is_none() {
+               self.previous = None;
+            }
+         }
+         self.save(previous);
+      }
+
+      ui.horizontal(|ui| {
+         let text_edit = TextEdit::singleline(&mut self.new_title)
+            .font(FontId::proportional(size))
+            .desired_width(180.0)
+            .hint_text("Title");
+         ui.add(text_edit);
+
+         let text_edit = TextEdit::singleline(&mut self.new_username)
+            .font(FontId::proportional(size))
+            .desired_width(180.0)
+            .hint_text("Username");
+         ui.add(text_edit);
+
+         if ui.button(RichText::new("Add entry").size(size)).clicked() {
+            self.add_entry(vault.clone());
+         }
+      });
+
+      copy
+   }
+
+   fn add_entry(&mut self, previous: TeamVault) {
+      if self.new_title.trim().is_empty() {
+         self.error = Some(String::from("The entry needs a title"));
+         return;
+      }
+
+      let Some(vault) = &mut self.vault else {
+         return;
+      };
+
+      let now = unix_now();
+      let data = IndexData {
+         title: self.new_title.trim().to_string(),
+         username: self.new_username.trim().to_string(),
+         created_at: now,
+         modified_at: now,
+         ..Default::default()
+      };
+      vault.index_map.insert(vault.next_index(), data);
+      self.new_title.clear();
+      self.new_username.clear();
+      self.save(previous);
+   }
+
+   fn show_members(
+      &mut self,
+      vault: &TeamVault,
+      team: &PasswordDeriver,
+      theme: &Theme,
+      ui: &mut Ui,
+   ) {
+      let size = theme.text_sizes.normal;
+
+      Grid::new("team_members").spacing(vec2(12.0, 6.0)).show(ui, |ui| {
+         for member in &vault.members {
+            ui.label(RichText::new(&member.name).size(size));
+            let key = format!(
+               "{}...",
+               &member.public_key[..16.min(member.public_key.len())]
+            );
+            ui.label(RichText::new(key).size(theme.text_sizes.small).monospace());
+
+            match self.remove_confirm.as_deref() == Some(member.name.as_str()) {
+               true => {
+                  if ui.button(RichText::new("Confirm removal").size(size)).clicked() {
+                     self.remove_member(vault.clone(), team, &member.name);
+                  }
+               }
+               false => {
+                  if ui.button(RichText::new("Remove").size(size)).clicked() {
+                     self.remove_confirm = Some(member.name.clone());
+                  }
+               }
+            }
+            ui.end_row();
+         }
+      });
+
+      ui.horizontal(|ui| {
+         let text_edit = TextEdit::singleline(&mut self.new_member_name)
+            .font(FontId::proportional(size))
+            .desired_width(120.0)
+            .hint_text("Name");
+         ui.add(text_edit);
+
+         let text_edit = TextEdit::singleline(&mut self.new_member_key)
+            .font(FontId::monospace(theme.text_sizes.small))
+            .desired_width(280.0)
+            .hint_text("Their public key");
+         ui.add(text_edit);
+
+         if ui.button(RichText::new("Add member").size(size)).clicked() {
+            let previous = vault.clone();
+            let res = self
+               .vault
+               .as_mut()
+               .map(|vault| vault.add_member(team, &self.new_member_name, &self.new_member_key));
+
+            match res {
+               Some(Err(e)) => self.error = Some(e.to_string()),
+               _ => {
+                  self.new_member_name.clear();
+                  self.new_member_key.clear();
+                  self.save(previous);
+               }
+            }
+         }
+      });
+   }
+
+   fn remove_member(&mut self, previous: TeamVault, team: &PasswordDeriver, name: &str) {
+      self.remove_confirm = None;
+      let Some(vault) = &mut self.vault else {
+         return;
+      };
+
+      match vault.remove_member(team, name) {
+         Ok(new_team) => {
+            // Keep the current seed if the removal can't be saved
+            let previous_team = vault.rotation.as_ref().map(|_| team.clone());
+            if self.save(previous) {
+               self.previous = previous_team;
+               self.team = Some(new_team);
+            }
+         }
+         Err(e) => {
+            self.error = Some(e.to_string());
+            self.vault = Some(previous);
+         }
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use argon2_rs::Argon2;
+
+   fn member() -> PasswordDeriver {
+      PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1))
+   }
+
+   #[test]
+   fn test_members_and_rotation() {
+      let alice = member();
+      let bob = member();
+      let carol = member();
+
+      let (mut vault, team) = TeamVault::create("Ops", "alice", &alice).unwrap();
+      vault.add_member(&team, "bob", &member_public_key(&bob).unwrap()).unwrap();
+      vault
+         .add_member(
+            &team,
+            "carol",
+            &member_public_key(&carol).unwrap(),
+         )
+         .unwrap();
+      vault.index_map.insert(0, IndexData::default());
+
+      let shared = |d: &PasswordDeriver| d.derive_at(0).unlock_str(|s| s.to_string());
+      let old = shared(&team);
+      assert_eq!(shared(&vault.unlock(&bob).unwrap()), old);
+      assert!(vault.unlock(&member()).is_err());
+
+      let new_team = vault.remove_member(&team, "carol").unwrap();
+      assert!(vault.unlock(&carol).is_err());
+      assert_eq!(
+         shared(&vault.unlock(&bob).unwrap()),
+         shared(&new_team)
+      );
+      assert_ne!(shared(&new_team), old);
+      assert_eq!(shared(&vault.unlock_previous(&bob).unwrap()), old);
+      assert!(vault.unlock_previous(&carol).is_err());
+
+      assert!(vault.is_pending(0));
+      vault.mark_rotated(0);
+      assert!(vault.rotation.is_none());
+   }
+
+   #[test]
+   fn test_save_refuses_other_changes() {
+      let path = std::env::temp_dir().join(format!("team-test-{}.json", std::process::id()));
+      let (mut vault, _) = TeamVault::create("Ops", "alice", &member()).unwrap();
+      vault.save(&path).unwrap();
+
+      // Another member adds an entry meanwhile
+      let mut other = TeamVault::load(&path).unwrap();
+      other.index_map.insert(0, IndexData::titled("Mail"));
+      other.save(&path).unwrap();
+
+      vault.index_map.insert(0, IndexData::titled("Bank"));
+      assert!(vault.save(&path).is_err());
+      let mut vault = TeamVault::load(&path).unwrap();
+      assert_eq!(vault.index_map[&0].title, "Mail");
+      vault.save(&path).unwrap();
+
+      std::fs::remove_file(&path).unwrap();
+   }
+}
@@ -0,0 +1,166 @@
+use super::{AppCtx, SHARED_GUI, app::format_timestamp};
+use eframe::egui::{Align2, Button, RichText, ScrollArea, Ui, Window, vec2};
+use zeus_theme::Theme;
+
+/// Lists the deleted entries so they can be restored or purged
+pub struct Trash {
+   open: bool,
+   /// Index waiting for the user to confirm its permanent removal
+   purge_confirm: Option<u32>,
+}
+
+impl Trash {
+   pub fn new() -> Self {
+      Self {
+         open: false,
+         purge_confirm: None,
+      }
+   }
+
+   pub fn open(&mut self) {
+      self.open = true;
+      self.purge_confirm = None;
+   }
+
+   pub fn show(&mut self, app: AppCtx, theme: &Theme, ui: &mut Ui) {
+      if !self.open {
+         return;
+      }
+
+      let mut tombstones: Vec<_> =
+         app.read(|app| app.tombstones.iter().map(|(i, t)| (*i, t.clone())).collect());
+      tombstones.sort_by_key(|(index, _)| *index);
+
+      Window::new("Trash")
+         .title_bar(false)
+         .resizable(false)
+         .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+         .show(ui.ctx(), |ui| {
+            ui.vertical_centered(|ui| {
+               ui.spacing_mut().item_spacing = vec2(10.0, 10.0);
+               ui.spacing_mut().button_padding = vec2(8.0, 8.0);
+               ui.set_width(400.0);
+
+               let text = RichText::new("Trash").size(theme.text_sizes.heading);
+               ui.label(text);
+
+               let text = "Deleted entries keep their index reserved so it is never reused. \
+               Purging removes the record permanently.";
+               let text = RichText::new(text).size(theme.text_sizes.small);
+               ui.label(text.color(theme.colors.text_muted));
+
+               if tombstones.is_empty() {
+                  let text = RichText::new("The trash is empty").size(theme.text_sizes.normal);
+                  ui.label(text);
+               }
+
+               ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                  for (index, tombstone) in &tombstones {
+                     let index = *index;
+
+                     theme.frame2.outer_margin(0).show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+
+                        let text = format!("{}. {}", index, tombstone.entry.title);
+                        let text = RichText::new(text).size(theme.text_sizes.normal);
+                        ui.label(text);
+
+                        let text = format!(
+                           "Deleted {}",
+                           format_timestamp(tombstone.deleted_at)
+                        );
+                        let text = RichText::new(text).size(theme.text_sizes.small);
+                        ui.label(text.color(theme.colors.text_muted));
+
+                        ui.horizontal(|ui| {
+                           if self.purge_confirm == Some(index) {
+                              let text = RichText::new("Purge permanently?")
+                                 .size(theme.text_sizes.small)
+                                 .color(theme.colors.error);
+                              ui.label(text);
+
+                              let text = RichText::new("Yes").size(theme.text_sizes.small);
+                              if ui.button(text).clicked() {
+                                 self.purge_confirm = None;
+                                 let app = app.clone();
+                                 std::thread::spawn(move || {
+                                    purge_and_save(app, index);
+                                 });
+                              }
+
+                              let text = RichText::new("No").size(theme.text_sizes.small);
+                              if ui.button(text).clicked() {
+                                 self.purge_confirm = None;
+                              }
+                              return;
+                           }
+
+                           let text = RichText::new("Restore").size(theme.text_sizes.small);
+                           if ui.button(text).clicked() {
+                              let app = app.clone();
+                              std::thread::spawn(move || {
+                                 restore_and_save(app, index);
+                              });
+                           }
+
+                           let text = RichText::new("Purge").size(theme.text_sizes.small);
+                           if ui.button(text).clicked() {
+                              self.purge_confirm = Some(index);
+                           }
+                        });
+                     });
+                  }
+               });
+
+               let text = RichText::new("Close").size(theme.text_sizes.normal);
+               let button = Button::new(text).min_size(vec2(100.0, 25.0));
+               if ui.add(button).clicked() {
+                  self.open = false;
+               }
+            });
+         });
+   }
+}
+
+pub fn restore_and_save(app: AppCtx, index: u32) {
+   let tombstone = app.read(|app| app.tombstones.get(&index).cloned());
+
+   if let Err(err) = app.write(|app| app.restore_index(index)) {
+      SHARED_GUI.write(|gui| {
+         gui.msg_window.open("Error", err.to_string());
+      });
+      return;
+   }
+
+   match app.save_index_map_to_file() {
+      Ok(_) => {
+         SHARED_GUI.write(|gui| {
+            gui.msg_window.open("Success", "Entry restored");
+         });
+      }
+      Err(err) => {
+         app.write(|app| {
+            app.index_map.remove(&index);
+            if let Some(tombstone) = tombstone {
+               app.tombstones.insert(index, tombstone);
+            }
+         });
+         SHARED_GUI.write(|gui| {
+            gui.msg_window.open("Error", err.to_string());
+         });
+      }
+   }
+}
+
+fn purge_and_save(app: AppCtx, index: u32) {
+   let Some(tombstone) = app.write(|app| app.tombstones.remove(&index)) else {
+      return;
+   };
+
+   if let Err(err) = app.save_index_map_to_file() {
+      app.write(|app| app.tombstones.insert(index, tombstone));
+      SHARED_GUI.write(|gui| {
+         gui.msg_window.open("Error", err.to_string());
+      });
+   }
+}
@@ -0,0 +1,200 @@
+use super::{
+   AppCtx, SHARED_GUI,
+   app::{AppData, content_hash},
+};
+use notify::{RecursiveMode, Watcher};
+use passwd_derive::Scheme;
+use std::{
+   path::{Path, PathBuf},
+   sync::mpsc::{self, RecvTimeoutError},
+   time::Duration,
+};
+
+/// How often to look whether the data file was switched in the settings
+const SWITCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reload the data file when another program changes it, like Syncthing or the merge command
+///
+/// Watches the folder of the data file, since saves replace the file by renaming a new one
+/// over it, then compares the content hash with the one we last loaded or saved, so our own
+/// saves never trigger a reload.
+pub fn spawn(app: AppCtx) {
+   std::thread::spawn(move || {
+      let (sender, receiver) = mpsc::channel();
+      let mut watcher = match notify::recommended_watcher(sender) {
+         Ok(watcher) => watcher,
+         Err(e) => {
+            eprintln!("Failed to watch the data file {}", e);
+            return;
+         }
+      };
+      let mut watched: Option<PathBuf> = None;
+      // Set while a file of another scheme waits for the app to lock
+      let mut other_scheme = false;
+
+      loop {
+         let path = app.read(|app| app.data_file.clone());
+         let folder = folder_of(&path);
+         if watched.as_ref() != Some(&folder) {
+            if let Some(old) = watched.take() {
+               let _ = watcher.unwatch(&old);
+            }
+            if let Err(e) = watcher.watch(&folder, RecursiveMode::NonRecursive) {
+               eprintln!("Failed to watch {} {}", folder.display(), e);
+            }
+            watched = Some(folder);
+         }
+
+         let changed = match receiver.recv_timeout(SWITCH_INTERVAL) {
+            Ok(Ok(event)) => event.paths.iter().any(|p| p.file_name() == path.file_name()),
+            Ok(Err(e)) => {
+               eprintln!("Failed to watch the data file {}", e);
+               false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => return,
+         };
+
+         if !changed && !other_scheme {
+            continue;
+         }
+
+         match reload_if_changed(&app, &path) {
+            Reload::Unchanged => other_scheme = false,
+            Reload::Reloaded => {
+               other_scheme = false;
+               SHARED_GUI.write(|gui| {
+                  gui.home.audit.invalidate();
+                  gui.request_repaint();
+               });
+            }
+            Reload::OtherScheme(_) if other_scheme => {}
+            Reload::OtherScheme(scheme) => {
+               other_scheme = true;
+               let text = format!(
+                  "Another program changed {} to the {} derivation scheme, your credentials \
+                  derive other passwords for its entries. The app was locked, log in again to \
+                  load it.",
+                  path.display(),
+                  scheme.to_str()
+               );
+               SHARED_GUI.write(|gui| {
+                  gui.top_menu.lock_requested = true;
+                  gui.msg_window.open("Error", text);
+                  gui.request_repaint();
+               });
+            }
+         }
+      }
+   });
+}
+
+/// The folder to watch for this file, the working directory for a bare file name
+fn folder_of(path: &Path) -> PathBuf {
+   match path.parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+      _ => PathBuf::from("."),
+   }
+}
+
+#[derive(Debug, PartialEq)]
+enum Reload {
+   Unchanged,
+   Reloaded,
+   /// The file uses another scheme than the unlocked seed, it's loaded once the app locks
+   OtherScheme(Scheme),
+}
+
+fn reload_if_changed(app: &AppCtx, path: &Path) -> Reload {
+   // Not while we are saving, the file may already be ours with the hash not updated yet
+   let _guard = app.lock_file();
+   let Ok(data) = std::fs::read(path) else {
+      return Reload::Unchanged;
+   };
+
+   let hash = content_hash(&data);
+   if app.read(|app| app.file_hash == Some(hash)) {
+      return Reload::Unchanged;
+   }
+
+   // A file another program is still writing fails to parse, its next write is picked up
+   let Ok(temp) = serde_json::from_slice::<AppData>(&data) else {
+      return Reload::Unchanged;
+   };
+
+   app.write(|app| {
+      // The data file may have been switched in the settings meanwhile
+      if app.data_file != path {
+         return Reload::Unchanged;
+      }
+      // The unlocked seed derives other passwords for the entries of another scheme
+      if let (Some(_), Ok(scheme)) = (&app.passwd_derive, temp.scheme())
+         && app.scheme().ok() != Some(scheme)
+      {
+         return Reload::OtherScheme(scheme);
+      }
+      app.index_map = temp.index_map;
+      app.tombstones = temp.tombstones;
+      app.scheme_version = temp.scheme_version;
+      app.file_hash = Some(hash);
+      Reload::Reloaded
+   })
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::gui::app::IndexData;
+   use argon2_rs::Argon2;
+   use passwd_derive::{PasswordDeriver, random_seed};
+
+   #[test]
+   fn test_external_change() {
+      let path = std::env::temp_dir().join(format!("watch-test-{}.json", std::process::id()));
+      let app = AppCtx::default();
+      app.write(|app| app.data_file = path.clone());
+
+      let entry = IndexData::titled("Mail");
+      app.set_index(0, entry.clone());
+      app.save_index_map_to_file().unwrap();
+      assert_eq!(reload_if_changed(&app, &path), Reload::Unchanged);
+
+      // Another program adds an entry
+      let mut other = AppData {
+         data_file: path.clone(),
+         ..Default::default()
+      };
+      other.load_index_map_from_file().unwrap();
+      other.index_map.insert(1, entry);
+      other.scheme_version = Some(2);
+      other.save_index_map_to_file().unwrap();
+
+      // Saving our stale state is refused until the file is reloaded
+      app.remove_index(0);
+      assert!(app.save_index_map_to_file().is_err());
+      assert!(!path.with_extension("json.tmp").exists());
+      assert_eq!(reload_if_changed(&app, &path), Reload::Reloaded);
+      assert_eq!(app.read(|app| app.index_map.len()), 2);
+      assert_eq!(app.read(|app| app.scheme_version), Some(2));
+      app.save_index_map_to_file().unwrap();
+
+      // Entries of another scheme are not loaded under the unlocked seed
+      let deriver = PasswordDeriver::from_seed(random_seed().unwrap(), Argon2::new(16_000, 1, 1));
+      app.write(|app| app.passwd_derive = Some(deriver));
+      other.load_index_map_from_file().unwrap();
+      other.scheme_version = Some(1);
+      other.save_index_map_to_file().unwrap();
+      assert_eq!(
+         reload_if_changed(&app, &path),
+         Reload::OtherScheme(Scheme::V1)
+      );
+      assert_eq!(app.read(|app| app.scheme_version), Some(2));
+
+      // Once locked the file is loaded with its scheme
+      app.write(|app| app.passwd_derive = None);
+      assert_eq!(reload_if_changed(&app, &path), Reload::Reloaded);
+      assert_eq!(app.read(|app| app.scheme_version), Some(1));
+
+      std::fs::remove_file(&path).unwrap();
+   }
+}
@@ -8,9 +8,16 @@ use eframe::{
 };
 use std::sync::Arc;
 
+mod cli;
 mod gui;
 
 fn main() -> eframe::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+    let keyfile = cli::keyfile_arg(&args);
+
     let wgpu_setup = WgpuSetup::CreateNew(WgpuSetupCreateNew {
         device_descriptor: Arc::new(|_adapter| wgpu::DeviceDescriptor {
             memory_hints: MemoryHints::MemoryUsage,
@@ -41,8 +48,11 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "NoPassPlz",
         options,
-        Box::new(|cc| {
+        Box::new(move |cc| {
             let app = App::new(cc);
+            if let Some(path) = keyfile {
+                gui::SHARED_GUI.write(|gui| gui.auth.set_keyfile_path(path));
+            }
 
             Ok(Box::new(app))
         }),